@@ -0,0 +1,326 @@
+//! Benchmarks of the code that runs in the data callback, so that changes
+//! to it can be measured. None of these need audio hardware: they operate
+//! on heap buffers.
+//!
+//! Run them with `cargo bench --features bench --bench callback`.
+
+use std::os::raw::c_void;
+
+use criterion::{
+    black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput,
+};
+use rtaudio::bench_support;
+use rtaudio::rt::SpscRing;
+use rtaudio::{Buffers, SampleFormat, StreamInfo, StreamStatus};
+
+/// The data callback is stored like this in the stream.
+type DataCallback = Box<dyn FnMut(Buffers<'_>, &StreamInfo, StreamStatus) + Send>;
+
+const FRAMES: [usize; 2] = [64, 1024];
+const CHANNELS: [usize; 2] = [2, 32];
+
+fn stream_info(in_channels: usize, out_channels: usize, frames: usize) -> StreamInfo {
+    StreamInfo {
+        out_channels,
+        in_channels,
+        sample_format: SampleFormat::Float32,
+        sample_rate: 48000,
+        max_frames: frames,
+        ..Default::default()
+    }
+}
+
+/// A test signal of `len` samples in `-1.0..1.0`.
+fn signal(len: usize) -> Vec<f32> {
+    (0..len).map(|i| (i as f32 * 0.001).sin()).collect()
+}
+
+/// Every combination of `FRAMES` and `CHANNELS`, with a label.
+fn sizes() -> impl Iterator<Item = (usize, usize, String)> {
+    FRAMES.into_iter().flat_map(|frames| {
+        CHANNELS
+            .into_iter()
+            .map(move |channels| (frames, channels, format!("{}x{}", frames, channels)))
+    })
+}
+
+fn bench_from_raw(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Buffers::from_raw");
+
+    for format in [SampleFormat::SInt16, SampleFormat::Float32] {
+        let mut output = vec![0u8; 1024 * 2 * 4];
+        let mut input = vec![0u8; 1024 * 2 * 4];
+
+        group.bench_function(format!("{:?}", format), |b| {
+            b.iter(|| {
+                // Safe because both buffers hold 1024 stereo frames of the
+                // widest format.
+                let buffers = unsafe {
+                    bench_support::buffers_from_raw(
+                        black_box(output.as_mut_ptr() as *mut c_void),
+                        black_box(input.as_mut_ptr() as *mut c_void),
+                        1024,
+                        2,
+                        2,
+                        format,
+                    )
+                };
+                black_box(buffers);
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_conversion(c: &mut Criterion) {
+    let mut group = c.benchmark_group("f32 -> output format");
+
+    for (frames, channels, size) in sizes() {
+        let len = frames * channels;
+        let samples = signal(len);
+        let info = stream_info(0, channels, frames);
+        group.throughput(Throughput::Elements(len as u64));
+
+        let mut i16_out = vec![0i16; len];
+        let mut i32_out = vec![0i32; len];
+        let mut i24_out = vec![0u8; len * 3];
+
+        let filled_ring = || {
+            let (mut producer, consumer) = SpscRing::new(len);
+            producer.push_iter(samples.iter().copied());
+            (producer, consumer)
+        };
+
+        group.bench_function(BenchmarkId::new("i16", &size), |b| {
+            b.iter_batched_ref(
+                filled_ring,
+                |(_, consumer)| {
+                    let mut buffers = Buffers::SInt16 {
+                        output: &mut i16_out,
+                        input: &[],
+                    };
+                    bench_support::pop_output(consumer, &mut buffers, &info)
+                },
+                BatchSize::SmallInput,
+            )
+        });
+        group.bench_function(BenchmarkId::new("i24", &size), |b| {
+            b.iter_batched_ref(
+                filled_ring,
+                |(_, consumer)| {
+                    let mut buffers = Buffers::SInt24 {
+                        output: &mut i24_out,
+                        input: &[],
+                    };
+                    bench_support::pop_output(consumer, &mut buffers, &info)
+                },
+                BatchSize::SmallInput,
+            )
+        });
+        group.bench_function(BenchmarkId::new("i32", &size), |b| {
+            b.iter_batched_ref(
+                filled_ring,
+                |(_, consumer)| {
+                    let mut buffers = Buffers::SInt32 {
+                        output: &mut i32_out,
+                        input: &[],
+                    };
+                    bench_support::pop_output(consumer, &mut buffers, &info)
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+
+    group.finish();
+
+    let mut group = c.benchmark_group("input format -> f32");
+
+    for (frames, channels, size) in sizes() {
+        let len = frames * channels;
+        let samples = signal(len);
+        let info = stream_info(channels, 0, frames);
+        group.throughput(Throughput::Elements(len as u64));
+
+        let i16_in: Vec<i16> = samples.iter().map(|s| (s * 32_767.0) as i16).collect();
+        let i32_in: Vec<i32> = samples
+            .iter()
+            .map(|s| (f64::from(*s) * 2_147_483_647.0) as i32)
+            .collect();
+        let i24_in: Vec<u8> = samples
+            .iter()
+            .flat_map(|s| {
+                let bytes = ((s * 8_388_607.0) as i32).to_ne_bytes();
+                if cfg!(target_endian = "little") {
+                    [bytes[0], bytes[1], bytes[2]]
+                } else {
+                    [bytes[1], bytes[2], bytes[3]]
+                }
+            })
+            .collect();
+
+        let empty_ring = || SpscRing::<f32>::new(len);
+
+        group.bench_function(BenchmarkId::new("i16", &size), |b| {
+            b.iter_batched_ref(
+                empty_ring,
+                |(producer, _)| {
+                    let buffers = Buffers::SInt16 {
+                        output: &mut [],
+                        input: &i16_in,
+                    };
+                    bench_support::push_input(producer, &buffers, &info)
+                },
+                BatchSize::SmallInput,
+            )
+        });
+        group.bench_function(BenchmarkId::new("i24", &size), |b| {
+            b.iter_batched_ref(
+                empty_ring,
+                |(producer, _)| {
+                    let buffers = Buffers::SInt24 {
+                        output: &mut [],
+                        input: &i24_in,
+                    };
+                    bench_support::push_input(producer, &buffers, &info)
+                },
+                BatchSize::SmallInput,
+            )
+        });
+        group.bench_function(BenchmarkId::new("i32", &size), |b| {
+            b.iter_batched_ref(
+                empty_ring,
+                |(producer, _)| {
+                    let buffers = Buffers::SInt32 {
+                        output: &mut [],
+                        input: &i32_in,
+                    };
+                    bench_support::push_input(producer, &buffers, &info)
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_layout(c: &mut Criterion) {
+    let mut group = c.benchmark_group("layout");
+
+    for (frames, channels, size) in sizes() {
+        let len = frames * channels;
+        let samples = signal(len);
+        let info = stream_info(channels, channels, frames);
+        group.throughput(Throughput::Elements(len as u64));
+
+        let mut scratch = vec![0.0f32; len];
+        group.bench_function(BenchmarkId::new("deinterleave input", &size), |b| {
+            b.iter(|| {
+                let buffers = Buffers::Float32 {
+                    output: &mut [],
+                    input: black_box(&samples),
+                };
+                buffers
+                    .input_to_planar(&info, &mut scratch)
+                    .map(|planar| planar.len())
+            })
+        });
+
+        let mut output = vec![0.0f32; len];
+        group.bench_function(BenchmarkId::new("interleave output", &size), |b| {
+            b.iter(|| {
+                let mut buffers = Buffers::Float32 {
+                    output: &mut output,
+                    input: &[],
+                };
+                buffers.output_from_planar(&info, black_box(&samples))
+            })
+        });
+    }
+
+    group.finish();
+}
+
+/// The work done by the callbacks in `bench_dispatch()`.
+fn apply_gain(buffers: Buffers<'_>, _info: &StreamInfo, _status: StreamStatus) {
+    if let Buffers::Float32 { output, input } = buffers {
+        for (out, s) in output.iter_mut().zip(input) {
+            *out = s * 0.5;
+        }
+    }
+}
+
+fn bench_dispatch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("callback dispatch");
+
+    for frames in FRAMES {
+        let input = signal(frames * 2);
+        let mut output = vec![0.0f32; frames * 2];
+        let info = stream_info(2, 2, frames);
+
+        group.bench_function(BenchmarkId::new("direct call", frames), |b| {
+            b.iter(|| {
+                let buffers = Buffers::Float32 {
+                    output: &mut output,
+                    input: black_box(&input),
+                };
+                apply_gain(buffers, &info, StreamStatus::empty());
+            })
+        });
+
+        let mut boxed: DataCallback = Box::new(apply_gain);
+        group.bench_function(BenchmarkId::new("boxed closure", frames), |b| {
+            b.iter(|| {
+                let buffers = Buffers::Float32 {
+                    output: &mut output,
+                    input: black_box(&input),
+                };
+                black_box(&mut boxed)(buffers, &info, StreamStatus::empty());
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_ring(c: &mut Criterion) {
+    let mut group = c.benchmark_group("ring buffer");
+    let len = 1024;
+    let samples = signal(len);
+    let mut dest = vec![0.0f32; len];
+    group.throughput(Throughput::Elements(len as u64));
+
+    let (mut producer, mut consumer) = SpscRing::new(len);
+
+    group.bench_function("push/pop one at a time", |b| {
+        b.iter(|| {
+            for &s in &samples {
+                let _ = producer.push(black_box(s));
+            }
+            while let Some(s) = consumer.pop() {
+                black_box(s);
+            }
+        })
+    });
+
+    group.bench_function("push_iter/pop_slice", |b| {
+        b.iter(|| {
+            producer.push_iter(black_box(&samples).iter().copied());
+            consumer.pop_slice(&mut dest)
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_from_raw,
+    bench_conversion,
+    bench_layout,
+    bench_dispatch,
+    bench_ring
+);
+criterion_main!(benches);
@@ -0,0 +1,61 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rtaudio::{Buffers, SampleFormat, StreamInfo};
+
+const FRAMES: usize = 1024;
+
+fn stream_info(in_channels: usize, out_channels: usize) -> StreamInfo {
+    StreamInfo {
+        out_channels,
+        in_channels,
+        sample_format: SampleFormat::Float32,
+        sample_rate: 48000,
+        max_frames: FRAMES,
+        ..Default::default()
+    }
+}
+
+/// The per-sample copy that was used for every case before the bulk copy
+/// fast path was added.
+fn route_per_sample(input: &[f32], output: &mut [f32], channels: usize) {
+    for frame in 0..input.len() / channels {
+        for ch in 0..channels {
+            let i = frame * channels + ch;
+            output[i] = input[i];
+        }
+    }
+}
+
+fn bench_route(c: &mut Criterion) {
+    let input: Vec<f32> = (0..FRAMES * 2).map(|i| (i as f32 * 0.001).sin()).collect();
+    let mut output = vec![0.0f32; FRAMES * 2];
+
+    let stereo = stream_info(2, 2);
+    c.bench_function("route f32 stereo -> stereo (fast path)", |b| {
+        b.iter(|| {
+            let mut buffers = Buffers::Float32 {
+                output: &mut output,
+                input: black_box(&input),
+            };
+            buffers.route_input_to_output(&stereo);
+        })
+    });
+
+    c.bench_function("route f32 stereo -> stereo (per sample)", |b| {
+        b.iter(|| route_per_sample(black_box(&input), &mut output, 2))
+    });
+
+    let mono_input = &input[..FRAMES];
+    let mono = stream_info(1, 2);
+    c.bench_function("route f32 mono -> stereo (generic path)", |b| {
+        b.iter(|| {
+            let mut buffers = Buffers::Float32 {
+                output: &mut output,
+                input: black_box(mono_input),
+            };
+            buffers.route_input_to_output(&mono);
+        })
+    });
+}
+
+criterion_group!(benches, bench_route);
+criterion_main!(benches);
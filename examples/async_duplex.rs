@@ -0,0 +1,106 @@
+//! Monitor the microphone through the speakers, as a voice chat app would,
+//! while controlling the stream from async code.
+//!
+//! `StreamTask` does not depend on an async runtime, so this uses a minimal
+//! executor to stay free of dependencies. The `run()` future can be spawned
+//! on any runtime instead.
+
+use std::future::Future;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::time::Duration;
+
+use rtaudio::{
+    Api, DeviceParams, StreamConfig, StreamEvent, StreamOptions, StreamState, StreamTask,
+};
+
+async fn run() -> Result<(), rtaudio::Error> {
+    let config = StreamConfig {
+        // Monitoring the input is only usable with a short delay.
+        options: StreamOptions::low_latency(),
+        ..StreamConfig::default()
+    };
+
+    let task = StreamTask::open(
+        Api::Unspecified,
+        Some(DeviceParams::default()),
+        Some(DeviceParams::default()),
+        config,
+        |error| eprintln!("{}", error),
+    )
+    .await?;
+    let info = task.info();
+    println!(
+        "{} Hz, {} frames per buffer, {} in / {} out",
+        info.sample_rate, info.max_frames, info.in_channels, info.out_channels
+    );
+
+    let mut events = task.events().await?;
+    task.start(|mut buffers, info, _status| buffers.route_input_to_output(info))
+        .await?;
+
+    // "Hang up" from another task after 5 seconds. `StreamTask` can be
+    // shared, and the stream time is read without going through the owner
+    // thread.
+    let task = Arc::new(task);
+    let hang_up = std::thread::spawn({
+        let task = Arc::clone(&task);
+        move || {
+            while task.stream_time() < 5.0 {
+                std::thread::sleep(Duration::from_millis(100));
+            }
+            if let Some(load) = task.cpu_load() {
+                println!("average load: {:.1}%", load * 100.0);
+            }
+            block_on(task.stop())
+        }
+    });
+
+    while let Some(event) = events.next().await {
+        match event {
+            StreamEvent::Error(e) => {
+                eprintln!("stream error: {}", e);
+                break;
+            }
+            StreamEvent::Warning(warning) => eprintln!("{}", warning),
+            StreamEvent::Xrun(stats) => eprintln!("xrun ({} so far)", stats.xruns),
+            StreamEvent::StateChanged(state) => {
+                println!("{:?}", state);
+                if state != StreamState::Running {
+                    break;
+                }
+            }
+        }
+    }
+
+    hang_up.join().unwrap()?;
+    match Arc::try_unwrap(task) {
+        Ok(task) => task.close().await,
+        Err(_) => unreachable!(),
+    }
+}
+
+/// A minimal executor which runs a future on the current thread.
+fn block_on<F: Future>(fut: F) -> F::Output {
+    struct ThreadWaker(std::thread::Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    let mut fut = Box::pin(fut);
+    loop {
+        if let Poll::Ready(output) = fut.as_mut().poll(&mut cx) {
+            return output;
+        }
+        std::thread::park_timeout(Duration::from_millis(100));
+    }
+}
+
+fn main() {
+    block_on(run()).unwrap();
+}
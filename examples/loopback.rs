@@ -1,4 +1,4 @@
-use rtaudio::{Api, Buffers, DeviceParams, SampleFormat, StreamInfo, StreamOptions, StreamStatus};
+use rtaudio::{Api, Buffers, SampleFormat, StreamInfo, StreamOptions, StreamStatus};
 
 fn main() {
     let host = rtaudio::Host::new(Api::Unspecified).unwrap();
@@ -9,20 +9,13 @@ fn main() {
 
     let mut stream_handle = host
         .open_stream(
-            Some(DeviceParams {
-                device_id: out_device.id,
-                num_channels: 2,
-                first_channel: 0,
-            }),
-            Some(DeviceParams {
-                device_id: in_device.id,
-                num_channels: 2,
-                first_channel: 0,
-            }),
+            out_device.output_params(Some(2)),
+            in_device.input_params(None),
             SampleFormat::Float32,
             out_device.preferred_sample_rate,
             256,
-            StreamOptions::default(),
+            // Monitoring the input is only usable with a short delay.
+            StreamOptions::low_latency(),
             |error| eprintln!("{}", error),
         )
         .unwrap();
@@ -30,11 +23,14 @@ fn main() {
 
     stream_handle
         .start(
-            move |buffers: Buffers<'_>, _info: &StreamInfo, _status: StreamStatus| {
-                if let Buffers::Float32 { output, input } = buffers {
-                    // Copy the input to the output.
-                    output.copy_from_slice(input);
-                }
+            move |mut buffers: Buffers<'_>, info: &StreamInfo, _status: StreamStatus| {
+                // Copy the input to the output. This also handles the case
+                // where the number of input and output channels differ (i.e.
+                // a mono microphone and stereo speakers).
+                //
+                // Alternatively, `stream_handle.start_loopback()` does this
+                // for you.
+                buffers.route_input_to_output(info);
             },
         )
         .unwrap();
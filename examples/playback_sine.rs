@@ -1,4 +1,4 @@
-use rtaudio::{Api, Buffers, DeviceParams, SampleFormat, StreamInfo, StreamOptions, StreamStatus};
+use rtaudio::{Api, Buffers, SampleFormat, StreamInfo, StreamOptions, StreamStatus};
 
 const AMPLITUDE: f32 = 0.5;
 const FREQ_HZ: f32 = 440.0;
@@ -10,13 +10,9 @@ fn main() {
     let out_device = host.default_output_device().unwrap();
 
     let mut stream_handle = host
-        .open_stream(
-            Some(DeviceParams {
-                device_id: out_device.id,
-                num_channels: 2,
-                first_channel: 0,
-            }),
-            None,
+        .open_output_stream(
+            Some(out_device.id),
+            2,
             SampleFormat::Float32,
             out_device.preferred_sample_rate,
             256,
@@ -0,0 +1,95 @@
+use crate::{Api, StreamFlags};
+
+/// Options which only apply to a specific API.
+///
+/// Options can be given for several APIs at once, in which case only the
+/// options for the API of the `Host` that opens the stream are used. This
+/// makes it possible to use the same `StreamOptions` regardless of which
+/// API was picked by `Api::Unspecified`. Options for any other API are
+/// ignored, with a note in the log.
+///
+/// ```
+/// use rtaudio::{ApiOptions, JackOptions, StreamOptions};
+///
+/// let options = StreamOptions {
+///     api_specific: ApiOptions {
+///         jack: Some(JackOptions {
+///             auto_connect: false,
+///         }),
+///         ..Default::default()
+///     },
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct ApiOptions {
+    /// Options for `Api::LinuxALSA`.
+    pub alsa: Option<AlsaOptions>,
+    /// Options for `Api::UnixJack`.
+    pub jack: Option<JackOptions>,
+}
+
+/// Options which only apply to `Api::LinuxALSA`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct AlsaOptions {
+    /// Whether to open the "default" PCM device instead of the requested
+    /// devices (the same as `StreamFlags::ALSA_USE_DEFAULT`).
+    ///
+    /// Note that this currently has no effect: the bundled version of RtAudio
+    /// never reads the `ALSA_USE_DEFAULT` flag. The "default" PCM device is
+    /// instead listed as a device of its own, so open it by its `DeviceID`.
+    ///
+    /// The default value is `false`.
+    pub use_default_pcm: bool,
+}
+
+/// Options which only apply to `Api::UnixJack`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct JackOptions {
+    /// Whether to automatically connect the ports of the stream to the
+    /// system ports. Setting this to `false` is the same as
+    /// `StreamFlags::JACK_DONT_CONNECT`.
+    ///
+    /// The default value is `true`.
+    pub auto_connect: bool,
+}
+
+impl Default for JackOptions {
+    fn default() -> Self {
+        Self { auto_connect: true }
+    }
+}
+
+impl ApiOptions {
+    /// The stream flags which the options for the given API add to the
+    /// `StreamOptions::flags`.
+    pub(crate) fn flags_for(&self, api: Api) -> StreamFlags {
+        let mut flags = StreamFlags::empty();
+
+        if let (Some(alsa), Api::LinuxALSA) = (&self.alsa, api) {
+            flags.set(StreamFlags::ALSA_USE_DEFAULT, alsa.use_default_pcm);
+        }
+        if let (Some(jack), Api::UnixJack) = (&self.jack, api) {
+            flags.set(StreamFlags::JACK_DONT_CONNECT, !jack.auto_connect);
+        }
+
+        flags
+    }
+
+    /// Print a note to the log for each set of options which does not
+    /// apply to the given API.
+    pub(crate) fn log_ignored(&self, api: Api) {
+        if self.alsa.is_some() && api != Api::LinuxALSA {
+            log::info!("RtAudio: the ALSA options are ignored by the {} API", api);
+        }
+        if self.jack.is_some() && api != Api::UnixJack {
+            log::info!("RtAudio: the JACK options are ignored by the {} API", api);
+        }
+    }
+}
@@ -0,0 +1,43 @@
+//! Entry points into the private hot paths of the data callback, for the
+//! benchmarks in `benches/`. Run them with `cargo bench --features bench`.
+//!
+//! This is only available with the `bench` feature. It is not part of the
+//! public API, and may change at any time.
+
+use std::os::raw::c_void;
+
+use crate::rt::{Consumer, Producer};
+use crate::{Buffers, SampleFormat, StreamInfo};
+
+/// See `Buffers::from_raw()`.
+///
+/// # Safety
+///
+/// `out` and `in_` must be null or point to buffers of `frames` frames of
+/// the given sample format and channel counts, which outlive `'a`.
+pub unsafe fn buffers_from_raw<'a>(
+    out: *mut c_void,
+    in_: *mut c_void,
+    frames: usize,
+    out_channels: usize,
+    in_channels: usize,
+    sample_format: SampleFormat,
+) -> Buffers<'a> {
+    Buffers::from_raw(out, in_, frames, out_channels, in_channels, sample_format)
+}
+
+/// Convert the input to interleaved `f32` samples, as done by
+/// `StreamHandle::start_input_capture()`.
+pub fn push_input(producer: &mut Producer<f32>, buffers: &Buffers<'_>, info: &StreamInfo) -> usize {
+    crate::capture::push_input(producer, buffers, info)
+}
+
+/// Convert interleaved `f32` samples to the output, as done by
+/// `StreamHandle::start_output_playback()`.
+pub fn pop_output(
+    consumer: &mut Consumer<f32>,
+    buffers: &mut Buffers<'_>,
+    info: &StreamInfo,
+) -> usize {
+    crate::playback::pop_output(consumer, buffers, info)
+}
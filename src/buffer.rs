@@ -1,6 +1,6 @@
 use std::ffi::c_void;
 
-use crate::SampleFormat;
+use crate::{InterleaveMode, OutputLimiter, SampleFormat, StreamInfo};
 
 /// The input/output audio buffers.
 #[derive(Debug, PartialEq)]
@@ -93,12 +93,18 @@ impl<'a> Buffers<'a> {
                 let output: &'a mut [u8] = if out_ptr.is_null() || out_channels == 0 {
                     &mut []
                 } else {
-                    std::slice::from_raw_parts_mut(out_ptr, out_channels * frames * 3)
+                    std::slice::from_raw_parts_mut(
+                        out_ptr,
+                        out_channels * frames * SampleFormat::SInt24.bytes_per_sample(),
+                    )
                 };
                 let input: &'a [u8] = if in_ptr.is_null() || in_channels == 0 {
                     &mut []
                 } else {
-                    std::slice::from_raw_parts(in_ptr, in_channels * frames * 3)
+                    std::slice::from_raw_parts(
+                        in_ptr,
+                        in_channels * frames * SampleFormat::SInt24.bytes_per_sample(),
+                    )
                 };
 
                 Buffers::SInt24 { output, input }
@@ -157,3 +163,436 @@ impl<'a> Buffers<'a> {
         }
     }
 }
+
+impl<'a> Buffers<'a> {
+    /// Copy the input buffer into `scratch` in planar (deinterleaved)
+    /// layout, regardless of whether the stream was opened with
+    /// interleaved or deinterleaved buffers.
+    ///
+    /// Returns the portion of `scratch` that was written to. Returns `None`
+    /// if `T` does not match the sample format of these buffers, or if
+    /// `scratch` is too small to hold the input buffer.
+    ///
+    /// This does not allocate, so it is safe to call from the realtime
+    /// thread.
+    pub fn input_to_planar<'s, T: Sample>(
+        &self,
+        info: &StreamInfo,
+        scratch: &'s mut [T],
+    ) -> Option<&'s [T]> {
+        self.input_to_layout(info, scratch, true)
+    }
+
+    /// Copy the input buffer into `scratch` in interleaved layout,
+    /// regardless of whether the stream was opened with interleaved or
+    /// deinterleaved buffers.
+    ///
+    /// Returns the portion of `scratch` that was written to. Returns `None`
+    /// if `T` does not match the sample format of these buffers, or if
+    /// `scratch` is too small to hold the input buffer.
+    ///
+    /// This does not allocate, so it is safe to call from the realtime
+    /// thread.
+    pub fn input_to_interleaved<'s, T: Sample>(
+        &self,
+        info: &StreamInfo,
+        scratch: &'s mut [T],
+    ) -> Option<&'s [T]> {
+        self.input_to_layout(info, scratch, false)
+    }
+
+    /// Fill the output buffer from `planar`, which holds data in planar
+    /// (deinterleaved) layout, regardless of whether the stream was opened
+    /// with interleaved or deinterleaved buffers.
+    ///
+    /// Returns `false` if `T` does not match the sample format of these
+    /// buffers, or if `planar` is not the same length as the output buffer.
+    ///
+    /// This does not allocate, so it is safe to call from the realtime
+    /// thread.
+    pub fn output_from_planar<T: Sample>(&mut self, info: &StreamInfo, planar: &[T]) -> bool {
+        self.output_from_layout(info, planar, true)
+    }
+
+    /// Fill the output buffer from `interleaved`, which holds data in
+    /// interleaved layout, regardless of whether the stream was opened with
+    /// interleaved or deinterleaved buffers.
+    ///
+    /// Returns `false` if `T` does not match the sample format of these
+    /// buffers, or if `interleaved` is not the same length as the output
+    /// buffer.
+    ///
+    /// This does not allocate, so it is safe to call from the realtime
+    /// thread.
+    pub fn output_from_interleaved<T: Sample>(
+        &mut self,
+        info: &StreamInfo,
+        interleaved: &[T],
+    ) -> bool {
+        self.output_from_layout(info, interleaved, false)
+    }
+
+    /// The output buffer, if it holds samples of type `T` in the given
+    /// layout.
+    ///
+    /// Returns `None` if `T` does not match the sample format of these
+    /// buffers, or if the stream was opened with the other layout (see
+    /// `StreamBuilder::interleave_mode()`), so that the buffer can never be
+    /// read with the wrong layout.
+    ///
+    /// ```
+    /// use rtaudio::{Buffers, InterleaveMode, StreamInfo};
+    ///
+    /// let mut output = [0.0f32; 4];
+    /// let mut buffers = Buffers::Float32 {
+    ///     output: &mut output,
+    ///     input: &[],
+    /// };
+    /// let info = StreamInfo {
+    ///     out_channels: 2,
+    ///     deinterleaved: true,
+    ///     ..Default::default()
+    /// };
+    ///
+    /// assert!(buffers.output_as::<f32>(&info, InterleaveMode::Interleaved).is_none());
+    /// assert!(buffers.output_as::<i16>(&info, InterleaveMode::Planar).is_none());
+    ///
+    /// let planar = buffers.output_as::<f32>(&info, InterleaveMode::Planar).unwrap();
+    /// planar[..2].fill(1.0); // The left channel
+    /// assert_eq!(output, [1.0, 1.0, 0.0, 0.0]);
+    /// ```
+    pub fn output_as<T: Sample>(
+        &mut self,
+        info: &StreamInfo,
+        mode: InterleaveMode,
+    ) -> Option<&mut [T]> {
+        if info.interleave_mode() != mode {
+            return None;
+        }
+
+        T::output(self)
+    }
+
+    /// The input buffer, if it holds samples of type `T` in the given
+    /// layout.
+    ///
+    /// See `Buffers::output_as()`.
+    pub fn input_as<T: Sample>(&self, info: &StreamInfo, mode: InterleaveMode) -> Option<&[T]> {
+        if info.interleave_mode() != mode {
+            return None;
+        }
+
+        T::input(self)
+    }
+
+    fn input_to_layout<'s, T: Sample>(
+        &self,
+        info: &StreamInfo,
+        scratch: &'s mut [T],
+        planar: bool,
+    ) -> Option<&'s [T]> {
+        let input = T::input(self)?;
+        let scratch = scratch.get_mut(0..input.len())?;
+
+        if info.deinterleaved == planar {
+            scratch.copy_from_slice(input);
+        } else {
+            convert_layout(input, scratch, info.in_channels, T::WIDTH, planar);
+        }
+
+        Some(scratch)
+    }
+
+    fn output_from_layout<T: Sample>(
+        &mut self,
+        info: &StreamInfo,
+        src: &[T],
+        planar: bool,
+    ) -> bool {
+        let Some(output) = T::output(self) else {
+            return false;
+        };
+        if output.len() != src.len() {
+            return false;
+        }
+
+        if info.deinterleaved == planar {
+            output.copy_from_slice(src);
+        } else {
+            convert_layout(src, output, info.out_channels, T::WIDTH, info.deinterleaved);
+        }
+
+        true
+    }
+}
+
+impl<'a> Buffers<'a> {
+    /// Copy the input buffer to the output buffer, adapting the number of
+    /// channels if the input and output channel counts differ.
+    ///
+    /// * If the input is mono, it is copied to every output channel.
+    /// * Otherwise each input channel is copied to the output channel with
+    ///   the same index. Extra input channels are discarded, and extra output
+    ///   channels are filled with silence.
+    ///
+    /// If there is no input, then the output is filled with silence.
+    ///
+    /// This does not allocate, so it is safe to call from the realtime
+    /// thread.
+    pub fn route_input_to_output(&mut self, info: &StreamInfo) {
+        match self {
+            Buffers::SInt8 { output, input } => route_channels(input, output, info, 1),
+            Buffers::SInt16 { output, input } => route_channels(input, output, info, 1),
+            Buffers::SInt24 { output, input } => route_channels(input, output, info, u8::WIDTH),
+            Buffers::SInt32 { output, input } => route_channels(input, output, info, 1),
+            Buffers::Float32 { output, input } => route_channels(input, output, info, 1),
+            Buffers::Float64 { output, input } => route_channels(input, output, info, 1),
+        }
+    }
+
+    /// Fill the output buffer with silence, regardless of the sample format.
+    ///
+    /// For `SInt24`, every byte of each packed 3-byte sample is zeroed.
+    ///
+    /// ```
+    /// use rtaudio::Buffers;
+    ///
+    /// let mut output = [0x7f, 0xff, 0x12, 0x80, 0x00, 0x01];
+    /// let mut buffers = Buffers::SInt24 {
+    ///     output: &mut output,
+    ///     input: &[],
+    /// };
+    ///
+    /// buffers.fill_output_silence();
+    /// assert_eq!(output, [0; 6]);
+    /// ```
+    ///
+    /// This does not allocate, so it is safe to call from the realtime
+    /// thread.
+    pub fn fill_output_silence(&mut self) {
+        match self {
+            Buffers::SInt8 { output, .. } => output.fill(0),
+            Buffers::SInt16 { output, .. } => output.fill(0),
+            Buffers::SInt24 { output, .. } => output.fill(0),
+            Buffers::SInt32 { output, .. } => output.fill(0),
+            Buffers::Float32 { output, .. } => output.fill(0.0),
+            Buffers::Float64 { output, .. } => output.fill(0.0),
+        }
+    }
+}
+
+impl<'a> Buffers<'a> {
+    /// Apply the given limiter to the output buffer.
+    ///
+    /// Returns the number of samples that were out of range.
+    pub(crate) fn limit_output(&mut self, limiter: OutputLimiter) -> usize {
+        match self {
+            Buffers::Float32 { output, .. } => output.iter_mut().fold(0, |count, s| {
+                let (limited, out_of_range) = limit_sample(f64::from(*s), limiter);
+                *s = limited as f32;
+                count + usize::from(out_of_range)
+            }),
+            Buffers::Float64 { output, .. } => output.iter_mut().fold(0, |count, s| {
+                let (limited, out_of_range) = limit_sample(*s, limiter);
+                *s = limited;
+                count + usize::from(out_of_range)
+            }),
+            _ => 0,
+        }
+    }
+}
+
+impl<'a> Buffers<'a> {
+    /// Returns `true` if every sample in the output buffer is exactly zero.
+    ///
+    /// An empty output buffer is not considered silent.
+    pub(crate) fn is_output_silent(&self) -> bool {
+        fn all_zero<T: PartialEq>(output: &[T], zero: T) -> bool {
+            !output.is_empty() && output.iter().all(|s| *s == zero)
+        }
+
+        match self {
+            Buffers::SInt8 { output, .. } => all_zero(output, 0),
+            Buffers::SInt16 { output, .. } => all_zero(output, 0),
+            Buffers::SInt24 { output, .. } => all_zero(output, 0),
+            Buffers::SInt32 { output, .. } => all_zero(output, 0),
+            Buffers::Float32 { output, .. } => all_zero(output, 0.0),
+            Buffers::Float64 { output, .. } => all_zero(output, 0.0),
+        }
+    }
+}
+
+/// Returns the limited sample, and whether or not the original sample was
+/// out of range.
+fn limit_sample(s: f64, limiter: OutputLimiter) -> (f64, bool) {
+    const THRESHOLD: f64 = 0.9;
+
+    if !s.is_finite() {
+        return (0.0, true);
+    }
+
+    let out_of_range = !(-1.0..=1.0).contains(&s);
+
+    let limited = match limiter {
+        OutputLimiter::Off => s,
+        OutputLimiter::Clip => s.clamp(-1.0, 1.0),
+        OutputLimiter::SoftKnee => {
+            let magnitude = s.abs();
+            if magnitude <= THRESHOLD {
+                s
+            } else {
+                let knee = 1.0 - THRESHOLD;
+                (THRESHOLD + knee * ((magnitude - THRESHOLD) / knee).tanh()).copysign(s)
+            }
+        }
+    };
+
+    (limited, out_of_range)
+}
+
+fn route_channels<T: Copy + Default>(
+    input: &[T],
+    output: &mut [T],
+    info: &StreamInfo,
+    width: usize,
+) {
+    let out_channels = info.out_channels;
+    let in_channels = info.in_channels;
+
+    if out_channels == 0 || width == 0 {
+        return;
+    }
+
+    let frames = output.len() / (out_channels * width);
+
+    if in_channels == 0 || input.len() < frames * in_channels * width {
+        output.fill(T::default());
+        return;
+    }
+
+    // When the channel counts match, both buffers have the same layout, so
+    // the whole buffer can be copied at once. This is the common case (i.e.
+    // interleaved f32 stereo), and it is much faster than copying each
+    // sample individually.
+    if in_channels == out_channels {
+        let len = frames * out_channels * width;
+        output[..len].copy_from_slice(&input[..len]);
+        output[len..].fill(T::default());
+        return;
+    }
+
+    // Index of the first element of a sample in a buffer.
+    let index = |frame: usize, ch: usize, channels: usize| {
+        if info.deinterleaved {
+            (ch * frames + frame) * width
+        } else {
+            (frame * channels + ch) * width
+        }
+    };
+
+    for frame in 0..frames {
+        for out_ch in 0..out_channels {
+            let o = index(frame, out_ch, out_channels);
+
+            let in_ch = if in_channels == 1 { 0 } else { out_ch };
+
+            if in_ch < in_channels {
+                let i = index(frame, in_ch, in_channels);
+                output[o..o + width].copy_from_slice(&input[i..i + width]);
+            } else {
+                output[o..o + width].fill(T::default());
+            }
+        }
+    }
+}
+
+/// Convert `src` from one layout to the other, writing the result into
+/// `dst`.
+///
+/// `width` is the number of elements that make up a single sample.
+fn convert_layout<T: Copy>(
+    src: &[T],
+    dst: &mut [T],
+    channels: usize,
+    width: usize,
+    to_planar: bool,
+) {
+    if channels == 0 || width == 0 {
+        return;
+    }
+
+    let frames = src.len() / (channels * width);
+
+    for frame in 0..frames {
+        for ch in 0..channels {
+            let i = (frame * channels + ch) * width;
+            let p = (ch * frames + frame) * width;
+
+            if to_planar {
+                dst[p..p + width].copy_from_slice(&src[i..i + width]);
+            } else {
+                dst[i..i + width].copy_from_slice(&src[p..p + width]);
+            }
+        }
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl Sealed for i8 {}
+    impl Sealed for i16 {}
+    impl Sealed for u8 {}
+    impl Sealed for i32 {}
+    impl Sealed for f32 {}
+    impl Sealed for f64 {}
+}
+
+/// A type of sample that can appear in `Buffers`.
+///
+/// Note that `u8` represents the packed bytes of the `SInt24` format, where
+/// each sample is 3 bytes.
+pub trait Sample: sealed::Sealed + Copy + Send + 'static {
+    /// The sample format of this type.
+    const FORMAT: SampleFormat;
+    /// The number of elements that make up a single sample.
+    const WIDTH: usize;
+
+    /// Get the input buffer if the buffers are of this sample type.
+    fn input<'b>(buffers: &'b Buffers<'_>) -> Option<&'b [Self]>;
+
+    /// Get the output buffer if the buffers are of this sample type.
+    fn output<'b>(buffers: &'b mut Buffers<'_>) -> Option<&'b mut [Self]>;
+}
+
+macro_rules! impl_sample {
+    ($t:ty, $variant:ident, $width:expr) => {
+        impl Sample for $t {
+            const FORMAT: SampleFormat = SampleFormat::$variant;
+            const WIDTH: usize = $width;
+
+            fn input<'b>(buffers: &'b Buffers<'_>) -> Option<&'b [Self]> {
+                if let Buffers::$variant { input, .. } = buffers {
+                    Some(input)
+                } else {
+                    None
+                }
+            }
+
+            fn output<'b>(buffers: &'b mut Buffers<'_>) -> Option<&'b mut [Self]> {
+                if let Buffers::$variant { output, .. } = buffers {
+                    Some(output)
+                } else {
+                    None
+                }
+            }
+        }
+    };
+}
+
+impl_sample!(i8, SInt8, 1);
+impl_sample!(i16, SInt16, 1);
+impl_sample!(u8, SInt24, SampleFormat::SInt24.bytes_per_sample());
+impl_sample!(i32, SInt32, 1);
+impl_sample!(f32, Float32, 1);
+impl_sample!(f64, Float64, 1);
@@ -0,0 +1,565 @@
+use std::fmt;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use crate::error::{Error, ErrorContext, ErrorOrigin, HostError};
+use crate::{
+    BufferSizePolicy, DeviceInfo, DeviceParams, Host, InterleaveMode, RatePolicy, Role,
+    SampleFormat, StreamConfig, StreamHandle, StreamOptions,
+};
+
+/// A builder for opening a stream.
+///
+/// This is created with `Host::stream_builder()`.
+#[derive(Debug)]
+pub struct StreamBuilder {
+    host: Host,
+    output_device: Option<DeviceParams>,
+    input_device: Option<DeviceParams>,
+    config: StreamConfig,
+    format_ranking: Option<Vec<SampleFormat>>,
+    strict_options: bool,
+    /// The number of buffers given to `StreamBuilder::num_buffers()`.
+    num_buffers: Option<u32>,
+    /// The mode given to `StreamBuilder::interleave_mode()`.
+    interleave_mode: Option<InterleaveMode>,
+}
+
+impl StreamBuilder {
+    pub(crate) fn new(host: Host) -> Self {
+        Self {
+            host,
+            output_device: None,
+            input_device: None,
+            config: StreamConfig::default(),
+            format_ranking: None,
+            strict_options: false,
+            num_buffers: None,
+            interleave_mode: None,
+        }
+    }
+
+    /// The parameters for the output device to use.
+    ///
+    /// By default no output device is used.
+    pub fn output_device(mut self, params: DeviceParams) -> Self {
+        self.output_device = Some(params);
+        self
+    }
+
+    /// The parameters for the input device to use.
+    ///
+    /// By default no input device is used.
+    pub fn input_device(mut self, params: DeviceParams) -> Self {
+        self.input_device = Some(params);
+        self
+    }
+
+    /// Set the entire configuration of the stream at once.
+    pub fn config(mut self, config: StreamConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// The sample format to use. See `StreamConfig::sample_format`.
+    ///
+    /// This turns off `StreamBuilder::format_auto()`.
+    pub fn sample_format(mut self, sample_format: SampleFormat) -> Self {
+        self.config.sample_format = sample_format;
+        self.format_ranking = None;
+        self
+    }
+
+    /// Pick the sample format from the native formats of the devices when
+    /// the stream is opened, so that RtAudio does not need to convert the
+    /// samples.
+    ///
+    /// The first format in `SampleFormat::AUTO_RANKING` which is supported
+    /// by both devices is used. If the devices have no native format in
+    /// common, then the best native format of the output device is used
+    /// (or of the input device if there is no output device). If the
+    /// devices do not report any native formats, then the sample format
+    /// from `StreamBuilder::sample_format()` or the `StreamConfig` is used.
+    ///
+    /// The format that was picked is available in
+    /// `StreamInfo::sample_format`, and it determines which variant of
+    /// `Buffers` is passed to the data callback. A callback which is
+    /// generic over `Sample` works with any of them.
+    pub fn format_auto(self) -> Self {
+        self.format_auto_ranked(&SampleFormat::AUTO_RANKING)
+    }
+
+    /// The same as `StreamBuilder::format_auto()`, but with the given order
+    /// of preference instead of `SampleFormat::AUTO_RANKING`. Formats which
+    /// are not in `ranking` are never picked.
+    pub fn format_auto_ranked(mut self, ranking: &[SampleFormat]) -> Self {
+        self.format_ranking = Some(ranking.to_vec());
+        self
+    }
+
+    /// The sample rate to use. See `StreamConfig::sample_rate`.
+    pub fn sample_rate(mut self, sample_rate: u32) -> Self {
+        self.config.sample_rate = sample_rate;
+        self
+    }
+
+    /// The desired maximum number of frames that can appear in a single
+    /// process call. See `StreamConfig::buffer_frames`.
+    pub fn buffer_frames(mut self, buffer_frames: u32) -> Self {
+        self.config.buffer_frames = buffer_frames;
+        self
+    }
+
+    /// Additional options for the stream.
+    pub fn options(mut self, options: StreamOptions) -> Self {
+        self.config.options = options;
+        self
+    }
+
+    /// The number of buffers to use. See `StreamOptions::num_buffers`.
+    ///
+    /// This is kept if `StreamBuilder::options()` or
+    /// `StreamBuilder::config()` is called afterwards. If this is not
+    /// called and `StreamOptions::num_buffers` is `None`, then the default
+    /// for the API of the `Host` is used instead (see
+    /// `StreamOptions::default_num_buffers()`). This is recorded in
+    /// `NegotiationReport::num_buffers_from_api_default`.
+    pub fn num_buffers(mut self, num_buffers: u32) -> Self {
+        self.num_buffers = Some(num_buffers);
+        self
+    }
+
+    /// How the samples are laid out in the buffers passed to the data
+    /// callback. This sets or clears the `StreamFlags::NONINTERLEAVED` flag.
+    ///
+    /// Read the buffers with `Buffers::output_as()` and `Buffers::input_as()`
+    /// using the same mode, which return `None` if the buffers are laid out
+    /// differently. Alternatively, `Buffers::output_from_planar()` and the
+    /// related methods convert from either layout.
+    ///
+    /// This is kept if `StreamBuilder::options()` or
+    /// `StreamBuilder::config()` is called afterwards. If this is not
+    /// called, then the mode follows the flags of the `StreamOptions`, and
+    /// the default is `InterleaveMode::Interleaved`.
+    pub fn interleave_mode(mut self, mode: InterleaveMode) -> Self {
+        self.interleave_mode = Some(mode);
+        self
+    }
+
+    /// If `true`, then opening the stream fails with
+    /// `Error::InvalidParameter` if any of the stream options have no effect
+    /// on the API of the `Host` (see `StreamOptions::validate_for()`), or if
+    /// there are any problems with the device parameters (see
+    /// `DeviceParams::validate()`). All problems are listed in the error
+    /// message.
+    ///
+    /// By default this is `false`, in which case these are printed to the
+    /// log instead.
+    pub fn strict_options(mut self, strict: bool) -> Self {
+        self.strict_options = strict;
+        self
+    }
+
+    /// How the requested buffer size is adjusted before it is passed to the
+    /// backend. See `StreamConfig::buffer_size_policy`.
+    pub fn buffer_size_policy(mut self, policy: BufferSizePolicy) -> Self {
+        self.config.buffer_size_policy = policy;
+        self
+    }
+
+    /// How the sample rate is picked when the devices do not support the
+    /// requested sample rate. See `StreamConfig::rate_policy`.
+    pub fn rate_policy(mut self, rate_policy: RatePolicy) -> Self {
+        self.config.rate_policy = rate_policy;
+        self
+    }
+
+    /// If `true`, then the requested sample rate is checked against the
+    /// sample rates reported by each device before the stream is opened,
+    /// and an error listing the supported sample rates is returned if it
+    /// isn't supported.
+    ///
+    /// Devices which do not report any sample rates are not checked.
+    ///
+    /// This is the same as setting `StreamBuilder::rate_policy()` to
+    /// `RatePolicy::ExactOrError` (or back to `RatePolicy::AsRequested` if
+    /// `strict` is `false`).
+    pub fn strict_sample_rate(self, strict: bool) -> Self {
+        self.rate_policy(if strict {
+            RatePolicy::ExactOrError
+        } else {
+            RatePolicy::AsRequested
+        })
+    }
+
+    /// Open the stream.
+    ///
+    /// * `error_callback` - This will be called if there was an error that caused the
+    ///   stream to close. If this happens, the returned `Stream` struct should be
+    ///   manually closed or dropped. This is called from a separate notifier
+    ///   thread, never from the audio thread.
+    ///
+    /// Only one stream can be opened at a time (this is a limitation with RtAudio).
+    pub fn open<E>(mut self, error_callback: E) -> Result<StreamHandle, HostError>
+    where
+        E: FnOnce(Error) + Send + 'static,
+    {
+        if let Some(ranking) = &self.format_ranking {
+            match self.pick_sample_format(ranking) {
+                Ok(Some(format)) => self.config.sample_format = format,
+                Ok(None) => {}
+                Err(e) => {
+                    return Err(HostError {
+                        host: self.host,
+                        error: e,
+                    })
+                }
+            }
+        }
+
+        self.resolve_num_buffers();
+        self.resolve_interleave_mode();
+
+        if let Err(e) = self.check_options() {
+            return Err(HostError {
+                host: self.host,
+                error: e,
+            });
+        }
+
+        StreamHandle::new(
+            self.host,
+            self.output_device,
+            self.input_device,
+            self.config,
+            error_callback,
+        )
+    }
+
+    /// Open the stream on a helper thread, giving up if it takes longer than
+    /// the given timeout.
+    ///
+    /// This is useful because opening a stream can block for many seconds
+    /// with some devices (i.e. Bluetooth devices).
+    ///
+    /// If the timeout elapses, then `OpenTimeoutError::TimedOut` is returned
+    /// and the `Host` is consumed. The helper thread continues to wait for
+    /// the stream to finish opening, after which the stream is immediately
+    /// closed and the RtAudio instance is destroyed, so nothing is leaked.
+    /// Note that another stream cannot be opened until this has happened.
+    ///
+    /// See `StreamBuilder::open()` for more details.
+    pub fn open_with_timeout<E>(
+        self,
+        timeout: Duration,
+        error_callback: E,
+    ) -> Result<StreamHandle, OpenTimeoutError>
+    where
+        E: FnOnce(Error) + Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel();
+
+        let builder = AssertSend(self);
+
+        std::thread::spawn(move || {
+            let builder = builder.into_inner();
+
+            let res = AssertSend(builder.open(error_callback));
+
+            // If the timeout has elapsed, then the receiver has been dropped
+            // and the result is dropped here instead. This closes the stream
+            // and destroys the RtAudio instance.
+            let _ = tx.send(res);
+        });
+
+        match rx.recv_timeout(timeout) {
+            Ok(AssertSend(res)) => {
+                res.map_err(|HostError { host, error }| OpenTimeoutError::Failed { host, error })
+            }
+            Err(_) => Err(OpenTimeoutError::TimedOut { timeout }),
+        }
+    }
+
+    /// Look up the device for the given parameters, if there is one.
+    fn device_info(
+        &self,
+        params: Option<DeviceParams>,
+        role: Role,
+    ) -> Result<Option<DeviceInfo>, Error> {
+        let Some(params) = params else {
+            return Ok(None);
+        };
+        let params = self.host.resolve_device_params(params, role)?;
+        let Some(device_id) = params.device_id else {
+            return Ok(None);
+        };
+
+        self.host.device_by_id(device_id)
+    }
+
+    /// Apply `StreamBuilder::num_buffers()` to the options, if it was
+    /// called.
+    fn resolve_num_buffers(&mut self) {
+        if let Some(num_buffers) = self.num_buffers {
+            self.config.options.num_buffers = Some(num_buffers);
+        }
+    }
+
+    /// Apply `StreamBuilder::interleave_mode()` to the flags of the options,
+    /// if it was called.
+    fn resolve_interleave_mode(&mut self) {
+        if let Some(mode) = self.interleave_mode {
+            mode.apply_to(&mut self.config.options.flags);
+        }
+    }
+
+    fn check_options(&self) -> Result<(), Error> {
+        let api = self.host.api();
+        let options = &self.config.options;
+
+        if !self.strict_options {
+            return Ok(());
+        }
+
+        let mut problems: Vec<String> = options
+            .validate_for(api)
+            .iter()
+            .map(|w| w.to_string())
+            .collect();
+
+        for (params, role) in [
+            (self.output_device, Role::Output),
+            (self.input_device, Role::Input),
+        ] {
+            let Some(params) = params else {
+                continue;
+            };
+            let params = self.host.resolve_device_params(params, role)?;
+            let Some(device) = self.device_info(Some(params), role)? else {
+                continue;
+            };
+
+            if let Err(found) = params.validate(&device, role) {
+                problems.extend(
+                    found
+                        .iter()
+                        .filter(|p| !(options.skip_channel_validation && p.is_channel_range()))
+                        .map(|p| format!("{} device: {}", role, p)),
+                );
+            }
+        }
+
+        if problems.is_empty() {
+            return Ok(());
+        }
+
+        let msg = problems.join(", ");
+
+        Err(Error::InvalidParameter {
+            msg: Some(msg.into()),
+            context: Some(ErrorContext::OpenStream),
+            origin: Some(Box::new(ErrorOrigin::new(api))),
+        })
+    }
+
+    fn pick_sample_format(&self, ranking: &[SampleFormat]) -> Result<Option<SampleFormat>, Error> {
+        let output = self.device_info(self.output_device, Role::Output)?;
+        let input = self.device_info(self.input_device, Role::Input)?;
+
+        let output_formats = output.map(|d| d.native_formats);
+        let input_formats = input.map(|d| d.native_formats);
+
+        let common = match (output_formats, input_formats) {
+            (Some(out), Some(inp)) => (out & inp).best(ranking),
+            _ => None,
+        };
+
+        let format = common
+            .or_else(|| output_formats.and_then(|f| f.best(ranking)))
+            .or_else(|| input_formats.and_then(|f| f.best(ranking)));
+
+        if let Some(format) = format {
+            log::debug!("RtAudio: picked the {:?} sample format", format);
+        }
+
+        Ok(format)
+    }
+}
+
+/// The error returned by `StreamBuilder::open_with_timeout()`.
+#[derive(Debug)]
+pub enum OpenTimeoutError {
+    /// The stream took longer than the timeout to open.
+    ///
+    /// The `Host` has been consumed.
+    TimedOut { timeout: Duration },
+    /// The stream failed to open.
+    Failed { host: Host, error: Error },
+}
+
+impl std::error::Error for OpenTimeoutError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            OpenTimeoutError::TimedOut { .. } => None,
+            OpenTimeoutError::Failed { error, .. } => Some(error),
+        }
+    }
+}
+
+impl fmt::Display for OpenTimeoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OpenTimeoutError::TimedOut { timeout } => write!(
+                f,
+                "RtAudio: timed out after {:?} while opening stream",
+                timeout
+            ),
+            OpenTimeoutError::Failed { error, .. } => write!(f, "{}", error),
+        }
+    }
+}
+
+/// Used to move the RtAudio instance to a helper thread (see
+/// `StreamBuilder::open_with_timeout()` and
+/// `HostOptions::jack_connect_timeout`).
+pub(crate) struct AssertSend<T>(pub(crate) T);
+
+// Safe because the RtAudio instance is only ever used by one thread at a
+// time. The value is moved to the helper thread, and it is only moved back
+// once the helper thread is done with it.
+unsafe impl<T> Send for AssertSend<T> {}
+
+impl<T> AssertSend<T> {
+    // Using a method instead of destructuring makes the closure capture the
+    // whole wrapper rather than just the inner (non-`Send`) field.
+    pub(crate) fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Api, DeviceID};
+
+    fn missing_device() -> DeviceParams {
+        DeviceParams {
+            device_id: DeviceID::new(u32::MAX),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn failed_open_with_timeout_returns_the_host() {
+        let _lock = crate::stream::lock_streams_for_test();
+        let host = Host::new(Api::Unspecified).unwrap();
+
+        let res = host
+            .stream_builder()
+            .output_device(missing_device())
+            .open_with_timeout(Duration::from_secs(10), |_| {});
+
+        match res {
+            Err(OpenTimeoutError::Failed { host, error }) => {
+                assert!(!host.raw.is_null());
+                assert_eq!(
+                    OpenTimeoutError::Failed {
+                        host,
+                        error: error.clone()
+                    }
+                    .to_string(),
+                    error.to_string()
+                );
+            }
+            Err(e) => panic!("unexpected error: {}", e),
+            Ok(_) => panic!("opened a stream on a device which does not exist"),
+        }
+    }
+
+    #[test]
+    fn open_with_timeout_gives_up() {
+        let _lock = crate::stream::lock_streams_for_test();
+        let host = Host::new(Api::Unspecified).unwrap();
+
+        let res = host
+            .stream_builder()
+            .output_device(missing_device())
+            .open_with_timeout(Duration::ZERO, |_| {});
+
+        match res {
+            Err(e @ OpenTimeoutError::TimedOut { .. }) => {
+                assert_eq!(
+                    e.to_string(),
+                    "RtAudio: timed out after 0ns while opening stream"
+                );
+                assert!(std::error::Error::source(&e).is_none());
+            }
+            Err(e) => panic!("unexpected error: {}", e),
+            Ok(_) => panic!("opened a stream on a device which does not exist"),
+        }
+
+        // The helper thread closes everything once it is done.
+        crate::stream::wait_until_no_stream();
+    }
+
+    #[test]
+    fn only_an_explicit_num_buffers_overrides_the_options() {
+        let host = Host::new(Api::Unspecified).unwrap();
+        let api = host.api();
+        let api_default = StreamOptions::default_num_buffers(api);
+
+        let mut builder = host.stream_builder().options(StreamOptions::default());
+        builder.resolve_num_buffers();
+        assert_eq!(builder.config.options.num_buffers, None);
+        assert_eq!(builder.config.options.num_buffers_for(api), api_default);
+
+        let mut builder = builder.options(StreamOptions::low_latency());
+        builder.resolve_num_buffers();
+        assert_eq!(builder.config.options.num_buffers, Some(2));
+
+        // An explicit value equal to the blanket default is kept.
+        let mut builder = builder.options(StreamOptions {
+            num_buffers: Some(4),
+            ..Default::default()
+        });
+        builder.resolve_num_buffers();
+        assert_eq!(builder.config.options.num_buffers_for(api), 4);
+
+        let mut builder = builder.num_buffers(8).options(StreamOptions::default());
+        builder.resolve_num_buffers();
+        assert_eq!(builder.config.options.num_buffers, Some(8));
+    }
+
+    #[test]
+    fn interleave_mode_survives_replacing_the_options() {
+        let host = Host::new(Api::Unspecified).unwrap();
+
+        let mut builder = host
+            .stream_builder()
+            .interleave_mode(InterleaveMode::Planar)
+            .options(StreamOptions::default())
+            .config(StreamConfig::default());
+        builder.resolve_interleave_mode();
+        assert_eq!(
+            InterleaveMode::from_flags(builder.config.options.flags),
+            InterleaveMode::Planar
+        );
+
+        // Without it, the flags of the options are used.
+        let mut options = StreamOptions::default();
+        InterleaveMode::Planar.apply_to(&mut options.flags);
+        let mut builder = StreamBuilder::new(builder.host).options(options);
+        builder.resolve_interleave_mode();
+        assert_eq!(
+            InterleaveMode::from_flags(builder.config.options.flags),
+            InterleaveMode::Planar
+        );
+
+        let mut builder = builder.interleave_mode(InterleaveMode::Interleaved);
+        builder.resolve_interleave_mode();
+        assert_eq!(
+            InterleaveMode::from_flags(builder.config.options.flags),
+            InterleaveMode::Interleaved
+        );
+    }
+}
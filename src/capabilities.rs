@@ -0,0 +1,302 @@
+use std::fmt;
+
+use crate::{Api, CallbackPriority, StreamFlags, StreamOptions};
+
+/// Describes which stream features an API supports.
+///
+/// This is a hand-maintained table based on the behavior of each backend
+/// in RtAudio, and it is retrieved with `Api::capabilities()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ApiCapabilities {
+    /// Whether or not the `StreamFlags::HOG_DEVICE` flag is honored.
+    ///
+    /// Note that ASIO devices are always opened for exclusive use, so this
+    /// flag has no effect there.
+    pub supports_exclusive: bool,
+    /// Whether or not the `StreamFlags::SCHEDULE_REALTIME` flag (and the
+    /// `priority` stream option) is honored.
+    pub supports_realtime_priority: bool,
+    /// Whether or not the API reports the latency of the stream.
+    pub reports_latency: bool,
+    /// Whether or not the `StreamFlags::NONINTERLEAVED` flag is honored.
+    pub supports_noninterleaved: bool,
+    /// Whether or not an output device can be opened as an input in order
+    /// to capture what it is playing.
+    pub supports_loopback: bool,
+    /// Whether or not the `num_buffers` stream option is honored.
+    pub per_stream_buffers: bool,
+    /// Whether or not a duplex stream must use the same device for both
+    /// output and input.
+    pub duplex_requires_same_device: bool,
+}
+
+impl ApiCapabilities {
+    const NONE: Self = Self {
+        supports_exclusive: false,
+        supports_realtime_priority: false,
+        reports_latency: false,
+        supports_noninterleaved: false,
+        supports_loopback: false,
+        per_stream_buffers: false,
+        duplex_requires_same_device: false,
+    };
+}
+
+impl Api {
+    /// Retrieve which stream features this API supports.
+    ///
+    /// `Api::Unspecified` and `Api::Dummy` support no features.
+    pub fn capabilities(&self) -> ApiCapabilities {
+        match self {
+            Api::Unspecified | Api::Dummy => ApiCapabilities::NONE,
+            Api::MacOSXCore => ApiCapabilities {
+                supports_exclusive: true,
+                supports_realtime_priority: false,
+                reports_latency: true,
+                supports_noninterleaved: true,
+                supports_loopback: false,
+                per_stream_buffers: false,
+                duplex_requires_same_device: false,
+            },
+            Api::LinuxALSA => ApiCapabilities {
+                supports_exclusive: false,
+                supports_realtime_priority: true,
+                reports_latency: true,
+                supports_noninterleaved: true,
+                supports_loopback: false,
+                per_stream_buffers: true,
+                duplex_requires_same_device: false,
+            },
+            Api::UnixJack => ApiCapabilities {
+                supports_exclusive: false,
+                supports_realtime_priority: false,
+                reports_latency: true,
+                supports_noninterleaved: true,
+                supports_loopback: false,
+                per_stream_buffers: false,
+                duplex_requires_same_device: false,
+            },
+            Api::LinuxPulse => ApiCapabilities {
+                supports_exclusive: false,
+                supports_realtime_priority: true,
+                reports_latency: false,
+                supports_noninterleaved: true,
+                supports_loopback: false,
+                per_stream_buffers: true,
+                duplex_requires_same_device: false,
+            },
+            Api::LinuxOSS => ApiCapabilities {
+                supports_exclusive: true,
+                supports_realtime_priority: true,
+                reports_latency: false,
+                supports_noninterleaved: true,
+                supports_loopback: false,
+                per_stream_buffers: true,
+                duplex_requires_same_device: false,
+            },
+            Api::WindowsASIO => ApiCapabilities {
+                supports_exclusive: false,
+                supports_realtime_priority: false,
+                reports_latency: true,
+                supports_noninterleaved: true,
+                supports_loopback: false,
+                per_stream_buffers: false,
+                duplex_requires_same_device: true,
+            },
+            Api::WindowsWASAPI => ApiCapabilities {
+                supports_exclusive: false,
+                supports_realtime_priority: true,
+                reports_latency: true,
+                supports_noninterleaved: true,
+                supports_loopback: true,
+                per_stream_buffers: false,
+                duplex_requires_same_device: false,
+            },
+            Api::WindowsDS => ApiCapabilities {
+                supports_exclusive: false,
+                supports_realtime_priority: false,
+                reports_latency: false,
+                supports_noninterleaved: true,
+                supports_loopback: false,
+                per_stream_buffers: true,
+                duplex_requires_same_device: false,
+            },
+        }
+    }
+}
+
+impl Api {
+    /// Retrieve which of the given stream flags have no effect on this API,
+    /// according to `Api::capabilities()`.
+    ///
+    /// ```
+    /// use rtaudio::{Api, StreamFlags, StreamOptions};
+    ///
+    /// let options = StreamOptions::exclusive();
+    /// assert_eq!(
+    ///     Api::WindowsWASAPI.unsupported_flags(options.flags),
+    ///     StreamFlags::HOG_DEVICE
+    /// );
+    /// assert!(Api::MacOSXCore.unsupported_flags(options.flags).is_empty());
+    /// ```
+    pub fn unsupported_flags(&self, flags: StreamFlags) -> StreamFlags {
+        let caps = self.capabilities();
+        let mut unsupported = StreamFlags::empty();
+
+        unsupported.set(StreamFlags::HOG_DEVICE, !caps.supports_exclusive);
+        unsupported.set(
+            StreamFlags::SCHEDULE_REALTIME,
+            !caps.supports_realtime_priority,
+        );
+        unsupported.set(StreamFlags::NONINTERLEAVED, !caps.supports_noninterleaved);
+        // RtAudio never reads this flag, not even on ALSA.
+        unsupported.insert(StreamFlags::ALSA_USE_DEFAULT);
+        unsupported.set(StreamFlags::JACK_DONT_CONNECT, *self != Api::UnixJack);
+
+        flags & unsupported
+    }
+}
+
+/// A problem with a combination of stream options on a specific API.
+///
+/// See `StreamOptions::validate_for()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionsWarning {
+    /// The flag has no effect on the API (see `Api::unsupported_flags()`).
+    UnsupportedFlag { flag: StreamFlags, api: Api },
+    /// The API does not support realtime scheduling, so the callback
+    /// priority has no effect.
+    UnsupportedPriority {
+        priority: CallbackPriority,
+        api: Api,
+    },
+    /// A `CallbackPriority::Raw` priority was given without the
+    /// `StreamFlags::SCHEDULE_REALTIME` flag, so it has no effect.
+    PriorityWithoutRealtime { priority: i32 },
+}
+
+impl fmt::Display for OptionsWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OptionsWarning::UnsupportedFlag { flag, api } => {
+                write!(f, "RtAudio: the ")?;
+                bitflags::parser::to_writer(flag, &mut *f)?;
+                write!(f, " flag has no effect on the {} API", api)
+            }
+            OptionsWarning::UnsupportedPriority { priority, api } => write!(
+                f,
+                "RtAudio: the {:?} callback priority can't be honored on the {} API",
+                priority, api
+            ),
+            OptionsWarning::PriorityWithoutRealtime { priority } => write!(
+                f,
+                "RtAudio: the callback priority {} has no effect without the SCHEDULE_REALTIME flag",
+                priority
+            ),
+        }
+    }
+}
+
+impl StreamOptions {
+    /// Check these options for combinations which have no effect on the
+    /// given API.
+    ///
+    /// These are printed to the log when a stream is opened. Use
+    /// `StreamBuilder::strict_options()` to fail to open the stream instead.
+    ///
+    /// ```
+    /// use rtaudio::{Api, CallbackPriority, OptionsWarning, StreamFlags, StreamOptions};
+    ///
+    /// // Unsupported flags
+    /// let options = StreamOptions {
+    ///     flags: StreamFlags::HOG_DEVICE | StreamFlags::JACK_DONT_CONNECT,
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(
+    ///     options.validate_for(Api::MacOSXCore),
+    ///     [OptionsWarning::UnsupportedFlag {
+    ///         flag: StreamFlags::JACK_DONT_CONNECT,
+    ///         api: Api::MacOSXCore,
+    ///     }]
+    /// );
+    /// assert_eq!(options.validate_for(Api::WindowsWASAPI).len(), 2);
+    /// assert!(StreamOptions::default().validate_for(Api::WindowsWASAPI).is_empty());
+    ///
+    /// // Unsupported priority
+    /// let options = StreamOptions::low_latency();
+    /// assert!(options.validate_for(Api::LinuxALSA).is_empty());
+    /// assert_eq!(
+    ///     options.validate_for(Api::WindowsASIO),
+    ///     [OptionsWarning::UnsupportedPriority {
+    ///         priority: CallbackPriority::Realtime,
+    ///         api: Api::WindowsASIO,
+    ///     }]
+    /// );
+    ///
+    /// // Raw priority without realtime scheduling
+    /// let options = StreamOptions {
+    ///     priority: CallbackPriority::Raw(50),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(
+    ///     options.validate_for(Api::LinuxALSA),
+    ///     [OptionsWarning::PriorityWithoutRealtime { priority: 50 }]
+    /// );
+    /// ```
+    pub fn validate_for(&self, api: Api) -> Vec<OptionsWarning> {
+        let mut warnings: Vec<OptionsWarning> = api
+            .unsupported_flags(self.flags)
+            .iter()
+            .map(|flag| OptionsWarning::UnsupportedFlag { flag, api })
+            .collect();
+
+        match self.priority {
+            CallbackPriority::High | CallbackPriority::Realtime
+                if !api.capabilities().supports_realtime_priority =>
+            {
+                warnings.push(OptionsWarning::UnsupportedPriority {
+                    priority: self.priority,
+                    api,
+                });
+            }
+            CallbackPriority::Raw(priority)
+                if priority != -1 && !self.flags.contains(StreamFlags::SCHEDULE_REALTIME) =>
+            {
+                warnings.push(OptionsWarning::PriorityWithoutRealtime { priority });
+            }
+            _ => {}
+        }
+
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unsupported_flags_follow_the_backends() {
+        for &api in Api::ALL {
+            let unsupported = api.unsupported_flags(StreamFlags::all());
+
+            assert!(
+                unsupported.contains(StreamFlags::ALSA_USE_DEFAULT),
+                "{}",
+                api
+            );
+            assert_eq!(
+                unsupported.contains(StreamFlags::HOG_DEVICE),
+                !matches!(api, Api::MacOSXCore | Api::LinuxOSS),
+                "{}",
+                api
+            );
+            assert_eq!(
+                unsupported.contains(StreamFlags::JACK_DONT_CONNECT),
+                api != Api::UnixJack,
+                "{}",
+                api
+            );
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use crate::error::ErrorContext;
+use crate::rt::{Consumer, Producer, SpscRing};
+use crate::{Buffers, Error, StreamHandle, StreamInfo, StreamStatus};
+
+/// The reading end of an input capture started with
+/// `StreamHandle::start_input_capture()`.
+///
+/// The captured input is converted to interleaved `f32` samples. This can
+/// be sent to another thread, and reading from it never blocks.
+///
+/// If the reader falls behind and the buffer fills up, then the frames
+/// which do not fit are dropped and counted (see `InputReader::overruns()`).
+/// Once the stream is stopped or dropped, the samples which were already
+/// captured can still be read.
+pub struct InputReader {
+    consumer: Consumer<f32>,
+    channels: usize,
+    shared: Arc<CaptureShared>,
+}
+
+struct CaptureShared {
+    overruns: AtomicU64,
+}
+
+impl InputReader {
+    /// Read as many captured samples into `dest` as are available and fit,
+    /// in whole frames.
+    ///
+    /// Returns the number of samples (not frames) that were read.
+    pub fn read(&mut self, dest: &mut [f32]) -> usize {
+        let whole_frames = dest.len() - dest.len() % self.channels;
+
+        self.consumer.pop_slice(&mut dest[..whole_frames])
+    }
+
+    /// The number of samples (not frames) which can currently be read.
+    pub fn available(&self) -> usize {
+        self.consumer.len()
+    }
+
+    /// The number of channels in each frame.
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    /// The maximum number of samples the buffer can hold.
+    pub fn capacity(&self) -> usize {
+        self.consumer.capacity()
+    }
+
+    /// The number of frames which were dropped because the buffer was full.
+    pub fn overruns(&self) -> u64 {
+        self.shared.overruns.load(Ordering::Relaxed)
+    }
+}
+
+impl std::fmt::Debug for InputReader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InputReader")
+            .field("channels", &self.channels)
+            .field("available", &self.available())
+            .field("capacity", &self.capacity())
+            .field("overruns", &self.overruns())
+            .finish()
+    }
+}
+
+impl StreamHandle {
+    /// Start the stream, capturing its input into a lock-free buffer which
+    /// holds up to `capacity_frames` frames.
+    ///
+    /// The returned `InputReader` reads the captured input as interleaved
+    /// `f32` samples, whatever the sample format and layout of the stream.
+    /// The buffer is allocated here, so nothing is allocated on the audio
+    /// thread. The output of the stream (if any) is silent.
+    ///
+    /// ```no_run
+    /// use rtaudio::{Api, DeviceParams, SampleFormat};
+    ///
+    /// let host = rtaudio::Host::new(Api::Unspecified)?;
+    /// let mut stream = host
+    ///     .stream_builder()
+    ///     .input_device(DeviceParams::default())
+    ///     .sample_format(SampleFormat::SInt16)
+    ///     .sample_rate(48000)
+    ///     .open(|error| eprintln!("{}", error))
+    ///     .map_err(|e| e.error)?;
+    ///
+    /// // Room for one second of input.
+    /// let mut reader = stream.start_input_capture(48000)?;
+    ///
+    /// let mut recording = Vec::new();
+    /// let mut block = vec![0.0f32; 1024 * reader.channels()];
+    /// while recording.len() < 48000 * 5 * reader.channels() {
+    ///     let n = reader.read(&mut block);
+    ///     recording.extend_from_slice(&block[..n]);
+    ///     std::thread::sleep(std::time::Duration::from_millis(10));
+    /// }
+    ///
+    /// assert_eq!(reader.overruns(), 0);
+    /// # Ok::<(), rtaudio::Error>(())
+    /// ```
+    ///
+    /// Returns `Error::InvalidUse` if the stream has no input channels, and
+    /// `Error::InvalidParameter` if `capacity_frames` is `0`.
+    pub fn start_input_capture(&mut self, capacity_frames: usize) -> Result<InputReader, Error> {
+        let channels = self.info().in_channels;
+
+        if channels == 0 {
+            return Err(Error::InvalidUse {
+                msg: Some("the stream has no input channels to capture".into()),
+                context: Some(ErrorContext::StartStream),
+                origin: None,
+            });
+        }
+        if capacity_frames == 0 {
+            return Err(Error::InvalidParameter {
+                msg: Some("the capture buffer must hold at least one frame".into()),
+                context: Some(ErrorContext::StartStream),
+                origin: None,
+            });
+        }
+
+        let (mut producer, consumer) = SpscRing::new(capacity_frames * channels);
+        let shared = Arc::new(CaptureShared {
+            overruns: AtomicU64::new(0),
+        });
+        let shared_cb = Arc::clone(&shared);
+
+        self.start(
+            move |mut buffers: Buffers<'_>, info: &StreamInfo, _status: StreamStatus| {
+                buffers.fill_output_silence();
+
+                let dropped = push_input(&mut producer, &buffers, info);
+                if dropped > 0 {
+                    shared_cb
+                        .overruns
+                        .fetch_add(dropped as u64, Ordering::Relaxed);
+                }
+            },
+        )?;
+
+        Ok(InputReader {
+            consumer,
+            channels,
+            shared,
+        })
+    }
+}
+
+/// Push as many whole frames of the input as fit, converted to interleaved
+/// `f32` samples.
+///
+/// Returns the number of frames that did not fit.
+pub(crate) fn push_input(
+    producer: &mut Producer<f32>,
+    buffers: &Buffers<'_>,
+    info: &StreamInfo,
+) -> usize {
+    let channels = info.in_channels;
+    if channels == 0 {
+        return 0;
+    }
+
+    let num_samples = match buffers {
+        Buffers::SInt8 { input, .. } => input.len(),
+        Buffers::SInt16 { input, .. } => input.len(),
+        Buffers::SInt24 { input, .. } => input.len() / 3,
+        Buffers::SInt32 { input, .. } => input.len(),
+        Buffers::Float32 { input, .. } => input.len(),
+        Buffers::Float64 { input, .. } => input.len(),
+    };
+    let frames = num_samples / channels;
+    let fit = frames.min(producer.free_len() / channels);
+
+    // The index in the input of the `k`th interleaved sample.
+    let mode = info.interleave_mode();
+    let indexes = (0..fit * channels)
+        .map(move |k| mode.sample_index(k % channels, k / channels, channels, frames));
+
+    match buffers {
+        Buffers::SInt8 { input, .. } => {
+            producer.push_iter(indexes.map(|i| f32::from(input[i]) / 128.0));
+        }
+        Buffers::SInt16 { input, .. } => {
+            producer.push_iter(indexes.map(|i| f32::from(input[i]) / 32_768.0));
+        }
+        Buffers::SInt24 { input, .. } => {
+            producer.push_iter(
+                indexes.map(|i| i24_to_f32([input[i * 3], input[i * 3 + 1], input[i * 3 + 2]])),
+            );
+        }
+        Buffers::SInt32 { input, .. } => {
+            producer.push_iter(indexes.map(|i| (f64::from(input[i]) / 2_147_483_648.0) as f32));
+        }
+        Buffers::Float32 { input, .. } => {
+            producer.push_iter(indexes.map(|i| input[i]));
+        }
+        Buffers::Float64 { input, .. } => {
+            producer.push_iter(indexes.map(|i| input[i] as f32));
+        }
+    }
+
+    frames - fit
+}
+
+/// Convert a 24-bit sample in the host's native byte order.
+fn i24_to_f32(bytes: [u8; 3]) -> f32 {
+    // Put the sample in the upper three bytes, and shift it back down to
+    // extend the sign.
+    let value = if cfg!(target_endian = "little") {
+        i32::from_le_bytes([0, bytes[0], bytes[1], bytes[2]])
+    } else {
+        i32::from_be_bytes([bytes[0], bytes[1], bytes[2], 0])
+    } >> 8;
+
+    value as f32 / 8_388_608.0
+}
@@ -0,0 +1,262 @@
+use crate::{Api, DeviceID, DeviceParams};
+
+/// The speaker position of a channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChannelRole {
+    /// The only channel of a mono layout.
+    Mono,
+    FrontLeft,
+    FrontRight,
+    FrontCenter,
+    /// The low frequency effects (subwoofer) channel.
+    LowFrequency,
+    /// Rear left, behind the listener.
+    BackLeft,
+    /// Rear right, behind the listener.
+    BackRight,
+    /// Surround left, to the side of the listener.
+    SideLeft,
+    /// Surround right, to the side of the listener.
+    SideRight,
+}
+
+/// A standard speaker layout.
+///
+/// The canonical order of the channels (`ChannelLayout::channels()`) is the
+/// order used by WAV files and Windows (`WAVEFORMATEXTENSIBLE`), which is
+/// also the order used by WASAPI and DirectSound devices (and usually by ASIO
+/// and Core Audio devices):
+///
+/// | Layout       | Channels                                |
+/// |--------------|-----------------------------------------|
+/// | `Mono`       | M                                       |
+/// | `Stereo`     | FL, FR                                  |
+/// | `Quad`       | FL, FR, BL, BR                          |
+/// | `Surround51` | FL, FR, FC, LFE, BL, BR                 |
+/// | `Surround71` | FL, FR, FC, LFE, BL, BR, SL, SR         |
+///
+/// ALSA devices use a different order for 5.1 and 7.1, with the center and
+/// LFE channels after the back channels:
+///
+/// | Layout       | Channels                                |
+/// |--------------|-----------------------------------------|
+/// | `Surround51` | FL, FR, BL, BR, FC, LFE                 |
+/// | `Surround71` | FL, FR, BL, BR, FC, LFE, SL, SR         |
+///
+/// Other APIs (i.e. JACK, where each channel is a separately routed port)
+/// do not define an order, so the canonical order is used for them.
+///
+/// Use `ChannelLayout::channel_map()` to reorder between the two.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChannelLayout {
+    Mono,
+    Stereo,
+    Quad,
+    Surround51,
+    Surround71,
+}
+
+impl ChannelLayout {
+    /// The number of channels in this layout.
+    pub fn channel_count(&self) -> u32 {
+        self.channels().len() as u32
+    }
+
+    /// The channels of this layout in the canonical (WAV) order.
+    pub fn channels(&self) -> &'static [ChannelRole] {
+        use ChannelRole::*;
+
+        match self {
+            ChannelLayout::Mono => &[Mono],
+            ChannelLayout::Stereo => &[FrontLeft, FrontRight],
+            ChannelLayout::Quad => &[FrontLeft, FrontRight, BackLeft, BackRight],
+            ChannelLayout::Surround51 => &[
+                FrontLeft,
+                FrontRight,
+                FrontCenter,
+                LowFrequency,
+                BackLeft,
+                BackRight,
+            ],
+            ChannelLayout::Surround71 => &[
+                FrontLeft,
+                FrontRight,
+                FrontCenter,
+                LowFrequency,
+                BackLeft,
+                BackRight,
+                SideLeft,
+                SideRight,
+            ],
+        }
+    }
+
+    /// The channels of this layout in the native order of devices on the
+    /// given API.
+    pub fn native_channels(&self, api: Api) -> &'static [ChannelRole] {
+        use ChannelRole::*;
+
+        match (self, api) {
+            (ChannelLayout::Surround51, Api::LinuxALSA) => &[
+                FrontLeft,
+                FrontRight,
+                BackLeft,
+                BackRight,
+                FrontCenter,
+                LowFrequency,
+            ],
+            (ChannelLayout::Surround71, Api::LinuxALSA) => &[
+                FrontLeft,
+                FrontRight,
+                BackLeft,
+                BackRight,
+                FrontCenter,
+                LowFrequency,
+                SideLeft,
+                SideRight,
+            ],
+            _ => self.channels(),
+        }
+    }
+
+    /// Parameters for the given device (or the default device if `None`)
+    /// which use the first `ChannelLayout::channel_count()` channels.
+    pub fn device_params(&self, device_id: Option<DeviceID>) -> DeviceParams {
+        DeviceParams {
+            device_id,
+            num_channels: self.channel_count(),
+            first_channel: 0,
+        }
+    }
+
+    /// A map which reorders interleaved frames from the canonical order of
+    /// this layout to the native order of the given API.
+    ///
+    /// Use `ChannelMap::inverse()` to reorder from the native order to the
+    /// canonical order (i.e. for input).
+    ///
+    /// ```
+    /// use rtaudio::{Api, ChannelLayout};
+    ///
+    /// // FL, FR, FC, LFE, BL, BR
+    /// let canonical = [1, 2, 3, 4, 5, 6];
+    ///
+    /// let map = ChannelLayout::Surround51.channel_map(Api::LinuxALSA);
+    /// let mut native = [0; 6];
+    /// map.remap_interleaved(&canonical, &mut native);
+    /// // FL, FR, BL, BR, FC, LFE
+    /// assert_eq!(native, [1, 2, 5, 6, 3, 4]);
+    ///
+    /// let mut back = [0; 6];
+    /// map.inverse().remap_interleaved(&native, &mut back);
+    /// assert_eq!(back, canonical);
+    ///
+    /// // FL, FR, FC, LFE, BL, BR, SL, SR
+    /// let canonical = [1, 2, 3, 4, 5, 6, 7, 8];
+    ///
+    /// let map = ChannelLayout::Surround71.channel_map(Api::LinuxALSA);
+    /// let mut native = [0; 8];
+    /// map.remap_interleaved(&canonical, &mut native);
+    /// // FL, FR, BL, BR, FC, LFE, SL, SR
+    /// assert_eq!(native, [1, 2, 5, 6, 3, 4, 7, 8]);
+    ///
+    /// // WASAPI uses the canonical order.
+    /// assert!(ChannelLayout::Surround51.channel_map(Api::WindowsWASAPI).is_identity());
+    /// assert!(ChannelLayout::Surround71.channel_map(Api::WindowsWASAPI).is_identity());
+    /// ```
+    pub fn channel_map(&self, api: Api) -> ChannelMap {
+        // The native order is always a permutation of the canonical order.
+        ChannelMap::new(self.channels(), self.native_channels(api)).unwrap()
+    }
+}
+
+/// A reordering of the channels in interleaved frames.
+///
+/// This does not allocate, so it can be used in the data callback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChannelMap {
+    /// The source channel of each destination channel.
+    sources: [u8; ChannelMap::MAX_CHANNELS],
+    len: u8,
+}
+
+impl ChannelMap {
+    /// The maximum number of channels in a map.
+    pub const MAX_CHANNELS: usize = 8;
+
+    /// Create a map which reorders frames with the channels in the order of
+    /// `from` into frames with the channels in the order of `to`.
+    ///
+    /// Returns `None` if `to` is not a reordering of `from`, or if there
+    /// are more than `ChannelMap::MAX_CHANNELS` channels.
+    pub fn new(from: &[ChannelRole], to: &[ChannelRole]) -> Option<Self> {
+        if from.len() != to.len() || to.len() > Self::MAX_CHANNELS {
+            return None;
+        }
+
+        let mut sources = [0; Self::MAX_CHANNELS];
+        let mut used = [false; Self::MAX_CHANNELS];
+        for (dst, role) in to.iter().enumerate() {
+            let src = (0..from.len()).find(|&i| from[i] == *role && !used[i])?;
+
+            used[src] = true;
+            sources[dst] = src as u8;
+        }
+
+        Some(Self {
+            sources,
+            len: to.len() as u8,
+        })
+    }
+
+    /// The number of channels in a frame.
+    pub fn channels(&self) -> usize {
+        usize::from(self.len)
+    }
+
+    /// The channel in the source frame which the given channel in the
+    /// destination frame is copied from.
+    ///
+    /// # Panics
+    /// Panics if `channel` is not less than `ChannelMap::channels()`.
+    pub fn source_of(&self, channel: usize) -> usize {
+        usize::from(self.sources[..self.channels()][channel])
+    }
+
+    /// Whether this map leaves every channel where it is.
+    pub fn is_identity(&self) -> bool {
+        (0..self.channels()).all(|ch| self.source_of(ch) == ch)
+    }
+
+    /// The map which reverses this map.
+    pub fn inverse(&self) -> Self {
+        let mut sources = [0; Self::MAX_CHANNELS];
+        for dst in 0..self.channels() {
+            sources[self.source_of(dst)] = dst as u8;
+        }
+
+        Self {
+            sources,
+            len: self.len,
+        }
+    }
+
+    /// Reorder the interleaved frames in `src` into `dst`.
+    ///
+    /// Only the complete frames which fit in both buffers are copied.
+    pub fn remap_interleaved<T: Copy>(&self, src: &[T], dst: &mut [T]) {
+        let channels = self.channels();
+        if channels == 0 {
+            return;
+        }
+
+        for (src_frame, dst_frame) in src
+            .chunks_exact(channels)
+            .zip(dst.chunks_exact_mut(channels))
+        {
+            for (ch, s) in dst_frame.iter_mut().enumerate() {
+                *s = src_frame[self.source_of(ch)];
+            }
+        }
+    }
+}
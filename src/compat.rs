@@ -0,0 +1,573 @@
+//! Traits in the shape of cpal's `HostTrait`, `DeviceTrait` and
+//! `StreamTrait`, for applications which abstract over several audio
+//! libraries.
+//!
+//! This module is only available with the `compat` feature. It does not
+//! depend on cpal. The types map as follows:
+//!
+//! | cpal                         | `rtaudio::compat`                     |
+//! |------------------------------|---------------------------------------|
+//! | `Host`                       | `rtaudio::Host` (see `HostTrait`)     |
+//! | `Device`                     | `Device` (a `DeviceInfo` and its API) |
+//! | `Stream`                     | `Stream` (wraps a `StreamHandle`)     |
+//! | `StreamConfig`               | `Config`                              |
+//! | `SupportedStreamConfigRange` | `SupportedConfigRange`                |
+//! | `SupportedStreamConfig`      | `SupportedConfig`                     |
+//! | `SampleFormat`, `Sample`     | `SampleFormat`, `Sample`              |
+//! | `BufferSize::Default`        | `Config::buffer_frames` of `0`        |
+//!
+//! Differences to keep in mind:
+//!
+//! * `Host` already has inherent `devices()`, `default_output_device()`
+//!   and `default_input_device()` methods, which take precedence over the
+//!   trait methods. Generic code (`H: HostTrait`) always gets the trait
+//!   methods; otherwise call them as `HostTrait::devices(&host)`.
+//! * RtAudio can only have one stream open at a time, and opening a stream
+//!   consumes a `Host`. `DeviceTrait::build_output_stream()` therefore opens
+//!   the stream on a new `Host` of the same API, and finds the device again
+//!   with `DeviceInfo::matches()`. The warning handler of the original host
+//!   is not carried over.
+//! * The data callback receives interleaved samples of type `T` and the
+//!   `StreamInfo` of the stream. RtAudio converts to and from the native
+//!   format of the device, so any `Sample` type can be used.
+//! * `StreamTrait::play()` and `StreamTrait::pause()` take `&mut self`. The
+//!   stream is started by the first call to `play()`. `pause()` keeps the
+//!   stream running, but outputs silence and stops calling the data
+//!   callback until `play()` is called again.
+//!
+//! ```no_run
+//! use rtaudio::compat::{DeviceTrait, HostTrait, StreamTrait};
+//!
+//! fn play_noise<H: HostTrait>(host: &H) -> Result<<H::Device as DeviceTrait>::Stream, rtaudio::Error> {
+//!     let device = host.default_output_device().expect("no output device");
+//!     let config = device.default_output_config().expect("no output config").config();
+//!
+//!     let mut seed = 1u32;
+//!     let mut stream = device.build_output_stream(
+//!         &config,
+//!         move |output: &mut [f32], _info| {
+//!             for sample in output.iter_mut() {
+//!                 seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+//!                 *sample = (seed >> 8) as f32 / (1 << 24) as f32 * 0.2 - 0.1;
+//!             }
+//!         },
+//!         |error| eprintln!("{}", error),
+//!     )?;
+//!     stream.play()?;
+//!
+//!     Ok(stream)
+//! }
+//!
+//! let host = rtaudio::Host::new(rtaudio::Api::Unspecified)?;
+//! let _stream = play_noise(&host)?;
+//! std::thread::sleep(std::time::Duration::from_secs(1));
+//! # Ok::<(), rtaudio::Error>(())
+//! ```
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::{
+    Api, Buffers, DeviceInfo, DeviceParams, Error, ErrorContext, Host, InterleaveMode, Role,
+    Sample, SampleFormat, StreamHandle, StreamInfo, StreamState, StreamStatus,
+};
+
+/// The configuration of a stream, like cpal's `StreamConfig`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Config {
+    /// The number of channels, starting at the first channel of the device.
+    pub channels: u32,
+    /// The sample rate.
+    pub sample_rate: u32,
+    /// The maximum number of frames in each process call, or `0` to let
+    /// the backend pick the size.
+    pub buffer_frames: u32,
+}
+
+/// A range of configurations which a device supports, like cpal's
+/// `SupportedStreamConfigRange`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SupportedConfigRange {
+    /// The maximum number of channels.
+    pub channels: u32,
+    /// The lowest sample rate.
+    pub min_sample_rate: u32,
+    /// The highest sample rate.
+    ///
+    /// Note that the device does not necessarily support every sample rate
+    /// in between (see `DeviceInfo::sample_rates`).
+    pub max_sample_rate: u32,
+    /// The sample format.
+    pub sample_format: SampleFormat,
+}
+
+/// A configuration which a device supports, like cpal's
+/// `SupportedStreamConfig`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SupportedConfig {
+    /// The number of channels.
+    pub channels: u32,
+    /// The sample rate.
+    pub sample_rate: u32,
+    /// The sample format.
+    pub sample_format: SampleFormat,
+}
+
+impl SupportedConfig {
+    /// This configuration as a `Config` with the default buffer size.
+    pub fn config(&self) -> Config {
+        Config {
+            channels: self.channels,
+            sample_rate: self.sample_rate,
+            buffer_frames: 0,
+        }
+    }
+}
+
+/// The host side of the compatibility layer, like cpal's `HostTrait`.
+pub trait HostTrait {
+    /// The type of the devices of this host.
+    type Device: DeviceTrait;
+
+    /// All the devices of this host.
+    fn devices(&self) -> Vec<Self::Device>;
+
+    /// The devices which have outputs.
+    fn output_devices(&self) -> Vec<Self::Device>;
+
+    /// The devices which have inputs.
+    fn input_devices(&self) -> Vec<Self::Device>;
+
+    /// The default output device, if there is one.
+    fn default_output_device(&self) -> Option<Self::Device>;
+
+    /// The default input device, if there is one.
+    fn default_input_device(&self) -> Option<Self::Device>;
+}
+
+/// The device side of the compatibility layer, like cpal's `DeviceTrait`.
+pub trait DeviceTrait {
+    /// The type of the streams opened on this device.
+    type Stream: StreamTrait;
+
+    /// The name of the device.
+    fn name(&self) -> String;
+
+    /// The output configurations which this device supports.
+    fn supported_output_configs(&self) -> Vec<SupportedConfigRange>;
+
+    /// The input configurations which this device supports.
+    fn supported_input_configs(&self) -> Vec<SupportedConfigRange>;
+
+    /// The preferred output configuration of this device, or `None` if it
+    /// has no outputs.
+    fn default_output_config(&self) -> Option<SupportedConfig>;
+
+    /// The preferred input configuration of this device, or `None` if it
+    /// has no inputs.
+    fn default_input_config(&self) -> Option<SupportedConfig>;
+
+    /// Open an output stream on this device.
+    ///
+    /// The stream is not started until `StreamTrait::play()` is called.
+    fn build_output_stream<T, D, E>(
+        &self,
+        config: &Config,
+        data_callback: D,
+        error_callback: E,
+    ) -> Result<Self::Stream, Error>
+    where
+        T: Sample,
+        D: FnMut(&mut [T], &StreamInfo) + Send + 'static,
+        E: FnOnce(Error) + Send + 'static;
+
+    /// Open an input stream on this device.
+    ///
+    /// The stream is not started until `StreamTrait::play()` is called.
+    fn build_input_stream<T, D, E>(
+        &self,
+        config: &Config,
+        data_callback: D,
+        error_callback: E,
+    ) -> Result<Self::Stream, Error>
+    where
+        T: Sample,
+        D: FnMut(&[T], &StreamInfo) + Send + 'static,
+        E: FnOnce(Error) + Send + 'static;
+}
+
+/// The stream side of the compatibility layer, like cpal's `StreamTrait`.
+pub trait StreamTrait {
+    /// Start or resume the stream.
+    fn play(&mut self) -> Result<(), Error>;
+
+    /// Pause the stream.
+    fn pause(&mut self) -> Result<(), Error>;
+}
+
+impl HostTrait for Host {
+    type Device = Device;
+
+    fn devices(&self) -> Vec<Device> {
+        let api = self.api();
+        self.iter_devices()
+            .map(|info| Device::new(api, info))
+            .collect()
+    }
+
+    fn output_devices(&self) -> Vec<Device> {
+        let api = self.api();
+        self.iter_output_devices()
+            .map(|info| Device::new(api, info))
+            .collect()
+    }
+
+    fn input_devices(&self) -> Vec<Device> {
+        let api = self.api();
+        self.iter_input_devices()
+            .map(|info| Device::new(api, info))
+            .collect()
+    }
+
+    fn default_output_device(&self) -> Option<Device> {
+        Host::default_output_device(self)
+            .ok()
+            .map(|info| Device::new(self.api(), info))
+    }
+
+    fn default_input_device(&self) -> Option<Device> {
+        Host::default_input_device(self)
+            .ok()
+            .map(|info| Device::new(self.api(), info))
+    }
+}
+
+/// A device, along with the API it belongs to.
+///
+/// ```
+/// use rtaudio::compat::{DeviceTrait, HostTrait};
+/// use rtaudio::{Api, Host};
+///
+/// let host = Host::new(Api::Unspecified).unwrap();
+///
+/// if let Some(device) = HostTrait::default_output_device(&host) {
+///     println!("{}: {:?}", device.name(), device.default_output_config());
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Device {
+    api: Api,
+    info: DeviceInfo,
+}
+
+impl Device {
+    /// A device with the given info, which belongs to a host of the given
+    /// API.
+    pub fn new(api: Api, info: DeviceInfo) -> Self {
+        Self { api, info }
+    }
+
+    /// The API of the host which this device belongs to.
+    pub fn api(&self) -> Api {
+        self.api
+    }
+
+    /// The info of this device.
+    pub fn info(&self) -> &DeviceInfo {
+        &self.info
+    }
+
+    fn channels(&self, role: Role) -> u32 {
+        match role {
+            Role::Output => self.info.max_output_channels(),
+            Role::Input => self.info.max_input_channels(),
+        }
+    }
+
+    /// The sample formats to list. Devices which do not report their
+    /// native formats can still be used with any format, since RtAudio
+    /// converts the samples.
+    fn formats(&self) -> Vec<SampleFormat> {
+        if self.info.native_formats.is_empty() {
+            vec![SampleFormat::Float32]
+        } else {
+            self.info.native_formats.iter_formats().collect()
+        }
+    }
+
+    fn supported_configs(&self, role: Role) -> Vec<SupportedConfigRange> {
+        let channels = self.channels(role);
+        if channels == 0 {
+            return Vec::new();
+        }
+
+        let (min_sample_rate, max_sample_rate) = self.info.sample_rate_range().unwrap_or((
+            self.info.preferred_sample_rate,
+            self.info.preferred_sample_rate,
+        ));
+
+        self.formats()
+            .into_iter()
+            .map(|sample_format| SupportedConfigRange {
+                channels,
+                min_sample_rate,
+                max_sample_rate,
+                sample_format,
+            })
+            .collect()
+    }
+
+    fn default_config(&self, role: Role) -> Option<SupportedConfig> {
+        let channels = self.channels(role);
+        if channels == 0 {
+            return None;
+        }
+
+        let sample_format = self
+            .info
+            .native_formats
+            .best(&SampleFormat::AUTO_RANKING)
+            .unwrap_or(SampleFormat::Float32);
+
+        Some(SupportedConfig {
+            channels,
+            sample_rate: self.info.preferred_sample_rate,
+            sample_format,
+        })
+    }
+
+    fn open<E>(
+        &self,
+        role: Role,
+        config: &Config,
+        sample_format: SampleFormat,
+        error_callback: E,
+    ) -> Result<StreamHandle, Error>
+    where
+        E: FnOnce(Error) + Send + 'static,
+    {
+        let host = Host::new(self.api)?;
+
+        let Some(current) = host.iter_devices().find(|d| self.info.matches(d)) else {
+            return Err(Error::InvalidDevice {
+                msg: Some(format!("device \"{}\" not found", self.info.name).into()),
+                context: Some(ErrorContext::OpenStream),
+                origin: None,
+            });
+        };
+
+        let params = DeviceParams {
+            device_id: Some(current.id),
+            num_channels: config.channels,
+            first_channel: 0,
+        };
+
+        let builder = match role {
+            Role::Output => host.stream_builder().output_device(params),
+            Role::Input => host.stream_builder().input_device(params),
+        };
+
+        builder
+            .sample_format(sample_format)
+            .sample_rate(config.sample_rate)
+            .buffer_frames(config.buffer_frames)
+            .interleave_mode(InterleaveMode::Interleaved)
+            .open(error_callback)
+            .map_err(|e| e.error)
+    }
+}
+
+impl DeviceTrait for Device {
+    type Stream = Stream;
+
+    fn name(&self) -> String {
+        self.info.name.clone()
+    }
+
+    fn supported_output_configs(&self) -> Vec<SupportedConfigRange> {
+        self.supported_configs(Role::Output)
+    }
+
+    fn supported_input_configs(&self) -> Vec<SupportedConfigRange> {
+        self.supported_configs(Role::Input)
+    }
+
+    fn default_output_config(&self) -> Option<SupportedConfig> {
+        self.default_config(Role::Output)
+    }
+
+    fn default_input_config(&self) -> Option<SupportedConfig> {
+        self.default_config(Role::Input)
+    }
+
+    fn build_output_stream<T, D, E>(
+        &self,
+        config: &Config,
+        mut data_callback: D,
+        error_callback: E,
+    ) -> Result<Stream, Error>
+    where
+        T: Sample,
+        D: FnMut(&mut [T], &StreamInfo) + Send + 'static,
+        E: FnOnce(Error) + Send + 'static,
+    {
+        let handle = self.open(Role::Output, config, T::FORMAT, error_callback)?;
+
+        let paused = Arc::new(AtomicBool::new(false));
+        let paused_cb = Arc::clone(&paused);
+
+        Ok(Stream::new(
+            handle,
+            paused,
+            Box::new(
+                move |mut buffers: Buffers<'_>, info: &StreamInfo, _status: StreamStatus| {
+                    if paused_cb.load(Ordering::Relaxed) {
+                        buffers.fill_output_silence();
+                        return;
+                    }
+
+                    if let Some(output) = buffers.output_as::<T>(info, InterleaveMode::Interleaved)
+                    {
+                        data_callback(output, info);
+                    }
+                },
+            ),
+        ))
+    }
+
+    fn build_input_stream<T, D, E>(
+        &self,
+        config: &Config,
+        mut data_callback: D,
+        error_callback: E,
+    ) -> Result<Stream, Error>
+    where
+        T: Sample,
+        D: FnMut(&[T], &StreamInfo) + Send + 'static,
+        E: FnOnce(Error) + Send + 'static,
+    {
+        let handle = self.open(Role::Input, config, T::FORMAT, error_callback)?;
+
+        let paused = Arc::new(AtomicBool::new(false));
+        let paused_cb = Arc::clone(&paused);
+
+        Ok(Stream::new(
+            handle,
+            paused,
+            Box::new(
+                move |buffers: Buffers<'_>, info: &StreamInfo, _status: StreamStatus| {
+                    if paused_cb.load(Ordering::Relaxed) {
+                        return;
+                    }
+
+                    if let Some(input) = buffers.input_as::<T>(info, InterleaveMode::Interleaved) {
+                        data_callback(input, info);
+                    }
+                },
+            ),
+        ))
+    }
+}
+
+type DataCallback = Box<dyn FnMut(Buffers<'_>, &StreamInfo, StreamStatus) + Send + 'static>;
+
+/// A stream opened with `DeviceTrait::build_output_stream()` or
+/// `DeviceTrait::build_input_stream()`.
+///
+/// The stream is closed when this is dropped.
+pub struct Stream {
+    handle: StreamHandle,
+    /// The data callback, until the stream is started.
+    callback: Option<DataCallback>,
+    paused: Arc<AtomicBool>,
+}
+
+impl Stream {
+    fn new(handle: StreamHandle, paused: Arc<AtomicBool>, callback: DataCallback) -> Self {
+        Self {
+            handle,
+            callback: Some(callback),
+            paused,
+        }
+    }
+
+    /// The underlying stream handle.
+    pub fn handle(&self) -> &StreamHandle {
+        &self.handle
+    }
+
+    /// The underlying stream handle, for the methods which are not part of
+    /// `StreamTrait`.
+    ///
+    /// Note that stopping the stream through the handle drops the data
+    /// callback, after which `StreamTrait::play()` cannot start it again.
+    pub fn handle_mut(&mut self) -> &mut StreamHandle {
+        &mut self.handle
+    }
+}
+
+impl StreamTrait for Stream {
+    fn play(&mut self) -> Result<(), Error> {
+        self.paused.store(false, Ordering::Relaxed);
+
+        if self.handle.state() == StreamState::Running {
+            return Ok(());
+        }
+
+        let Some(callback) = self.callback.take() else {
+            return Err(Error::InvalidUse {
+                msg: Some("the stream was stopped and cannot be started again".into()),
+                context: Some(ErrorContext::StartStream),
+                origin: None,
+            });
+        };
+
+        self.handle.start(callback)
+    }
+
+    fn pause(&mut self) -> Result<(), Error> {
+        self.paused.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DeviceInfo;
+
+    #[test]
+    fn device_configs_follow_the_device_info() {
+        let info = DeviceInfo {
+            input_channels: 0,
+            duplex_channels: 0,
+            is_default_input: false,
+            name: String::from("Speakers"),
+            ..crate::device_info::test_device()
+        };
+        let device = Device::new(Api::WindowsWASAPI, info);
+        assert_eq!(device.name(), "Speakers");
+
+        let range = |sample_format| SupportedConfigRange {
+            channels: 2,
+            min_sample_rate: 44100,
+            max_sample_rate: 96000,
+            sample_format,
+        };
+        assert_eq!(
+            device.supported_output_configs(),
+            [range(SampleFormat::SInt16), range(SampleFormat::Float32)]
+        );
+        assert!(device.supported_input_configs().is_empty());
+
+        assert_eq!(
+            device.default_output_config(),
+            Some(SupportedConfig {
+                channels: 2,
+                sample_rate: 48000,
+                sample_format: SampleFormat::Float32,
+            })
+        );
+        assert_eq!(device.default_input_config(), None);
+    }
+}
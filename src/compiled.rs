@@ -0,0 +1,77 @@
+//! Which backends are compiled into RtAudio, known at compile time.
+//!
+//! These are derived from the cargo features of this crate and the target
+//! OS, the same way that the `rtaudio-sys` build script picks the backends
+//! to build. If another crate enables features on `rtaudio-sys` directly,
+//! more backends may be compiled in than these report, so
+//! `rtaudio::compiled_apis()` remains the source of truth at runtime.
+
+use crate::Api;
+
+/// Whether the macOS Core Audio backend is compiled in.
+pub const COMPILED_WITH_CORE_AUDIO: bool = cfg!(all(target_os = "macos", feature = "coreaudio"));
+/// Whether the ALSA backend is compiled in.
+pub const COMPILED_WITH_ALSA: bool = cfg!(all(target_os = "linux", feature = "alsa"));
+/// Whether the JACK backend is compiled in.
+pub const COMPILED_WITH_JACK: bool = cfg!(all(target_os = "linux", feature = "jack_linux"));
+/// Whether the PulseAudio backend is compiled in.
+pub const COMPILED_WITH_PULSE: bool = cfg!(all(target_os = "linux", feature = "pulse"));
+/// Whether the OSS backend is compiled in.
+pub const COMPILED_WITH_OSS: bool = cfg!(all(
+    any(
+        target_os = "freebsd",
+        target_os = "openbsd",
+        target_os = "netbsd"
+    ),
+    feature = "oss"
+));
+/// Whether the ASIO backend is compiled in.
+pub const COMPILED_WITH_ASIO: bool = cfg!(all(target_os = "windows", feature = "asio"));
+/// Whether the WASAPI backend is compiled in.
+pub const COMPILED_WITH_WASAPI: bool = cfg!(all(target_os = "windows", feature = "wasapi"));
+/// Whether the DirectSound backend is compiled in.
+pub const COMPILED_WITH_DS: bool = cfg!(all(target_os = "windows", feature = "ds"));
+/// Whether the (non-functional) dummy backend is compiled in. RtAudio only
+/// compiles it in when there is no other backend.
+pub const COMPILED_WITH_DUMMY: bool = !(COMPILED_WITH_CORE_AUDIO
+    || COMPILED_WITH_ALSA
+    || COMPILED_WITH_JACK
+    || COMPILED_WITH_PULSE
+    || COMPILED_WITH_OSS
+    || COMPILED_WITH_ASIO
+    || COMPILED_WITH_WASAPI
+    || COMPILED_WITH_DS);
+
+/// Every backend with the constant that says whether it is compiled in, in
+/// the same order as `rtaudio::compiled_apis()`.
+const BACKENDS: [(Api, bool); 9] = [
+    (Api::MacOSXCore, COMPILED_WITH_CORE_AUDIO),
+    (Api::LinuxALSA, COMPILED_WITH_ALSA),
+    (Api::UnixJack, COMPILED_WITH_JACK),
+    (Api::LinuxPulse, COMPILED_WITH_PULSE),
+    (Api::LinuxOSS, COMPILED_WITH_OSS),
+    (Api::WindowsASIO, COMPILED_WITH_ASIO),
+    (Api::WindowsWASAPI, COMPILED_WITH_WASAPI),
+    (Api::WindowsDS, COMPILED_WITH_DS),
+    (Api::Dummy, COMPILED_WITH_DUMMY),
+];
+
+/// The APIs which are compiled in according to the `COMPILED_WITH_*`
+/// constants, in the same order as `rtaudio::compiled_apis()`.
+///
+/// Every one of these is also reported at runtime:
+///
+/// ```
+/// let compiled = rtaudio::compiled_apis();
+///
+/// for api in rtaudio::compiled_backends() {
+///     assert!(compiled.contains(&api), "{}", api);
+/// }
+/// ```
+pub fn compiled_backends() -> Vec<Api> {
+    BACKENDS
+        .into_iter()
+        .filter(|(_, compiled)| *compiled)
+        .map(|(api, _)| api)
+        .collect()
+}
@@ -1,17 +1,77 @@
 use std::ffi::CStr;
+use std::fmt;
+use std::num::NonZeroU32;
+use std::os::raw::c_char;
 
-use crate::NativeFormats;
+use crate::{DeviceParams, Error, NativeFormats, Role, SampleFormat};
+
+/// The conventional sample rates from 8 kHz to 192 kHz, in ascending order.
+pub const STANDARD_SAMPLE_RATES: &[u32] = &[
+    8000, 11025, 16000, 22050, 32000, 44100, 48000, 88200, 96000, 176400, 192000,
+];
+
+/// Whether or not the given sample rate is one of the `STANDARD_SAMPLE_RATES`.
+///
+/// ```
+/// assert!(rtaudio::is_standard_rate(44100));
+/// assert!(rtaudio::is_standard_rate(192000));
+/// assert!(!rtaudio::is_standard_rate(47999));
+/// assert!(!rtaudio::is_standard_rate(384000));
+/// ```
+pub fn is_standard_rate(sample_rate: u32) -> bool {
+    STANDARD_SAMPLE_RATES.binary_search(&sample_rate).is_ok()
+}
 
 /// A unique identifier for a device.
+///
+/// RtAudio never uses `0` as a device ID, so it cannot be represented:
+///
+/// ```compile_fail
+/// let id = rtaudio::DeviceID(0);
+/// ```
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct DeviceID(pub u32);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct DeviceID(NonZeroU32);
+
+impl DeviceID {
+    /// Create a device ID from its raw value.
+    ///
+    /// Returns `None` if `id` is `0`, which RtAudio uses to mean "no device".
+    pub fn new(id: u32) -> Option<Self> {
+        NonZeroU32::new(id).map(Self)
+    }
+
+    /// The raw value of this device ID.
+    pub fn get(&self) -> u32 {
+        self.0.get()
+    }
+}
+
+impl fmt::Display for DeviceID {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
 
 /// Queried information about a device.
+///
+/// With the `serde` feature, this can be serialized (i.e. to attach the
+/// device list to a bug report, or to cache it between runs). Note that
+/// deserialized info is a stale snapshot: the device may have been removed
+/// or changed since, and its `id` may no longer resolve (or may refer to a
+/// different device). Use `DeviceInfo::matches()` to reconcile cached info
+/// with a fresh scan.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DeviceInfo {
     /// The unique identifier of this device.
     pub id: DeviceID,
     /// The number of output channels on this device.
+    ///
+    /// RtAudio does not report the names of individual channels (even for
+    /// backends which have them, like JACK), only how many there are. See
+    /// `ChannelLayout` for the speaker positions of common channel counts.
     pub output_channels: u32,
     /// The number of input channels on this device.
     pub input_channels: u32,
@@ -32,6 +92,10 @@ pub struct DeviceInfo {
     /// The device's preferred sample rate.
     pub preferred_sample_rate: u32,
     /// The available sample rates for this device.
+    ///
+    /// When the info comes from RtAudio (`DeviceInfo::from_raw()`), this is
+    /// sorted in ascending order without duplicates. An empty list means
+    /// that the device did not report its sample rates.
     pub sample_rates: Vec<u32>,
 
     /// The display name of the device.
@@ -39,7 +103,14 @@ pub struct DeviceInfo {
 }
 
 impl DeviceInfo {
-    pub fn from_raw(d: rtaudio_sys::rtaudio_device_info_t) -> Self {
+    /// Returns `None` if the raw info does not have a valid device ID
+    /// (RtAudio returns zero-filled info for unknown devices).
+    ///
+    /// The sample rates are sorted, since RtAudio does not guarantee their
+    /// order.
+    pub fn from_raw(d: rtaudio_sys::rtaudio_device_info_t) -> Option<Self> {
+        let id = DeviceID::new(d.id as u32)?;
+
         let mut sample_rates = Vec::new();
         for sr in d.sample_rates.iter() {
             if *sr <= 0 {
@@ -48,14 +119,11 @@ impl DeviceInfo {
 
             sample_rates.push(*sr as u32);
         }
+        sample_rates.sort_unstable();
+        sample_rates.dedup();
 
-        // Safe because i8 and u8 have the same size, and we are correctly
-        // using the length of the array `d.name`.
-        let name_slice: &[u8] =
-            unsafe { std::slice::from_raw_parts(d.name.as_ptr() as *const u8, d.name.len()) };
-
-        let name = match CStr::from_bytes_until_nul(&name_slice) {
-            Ok(n) => n.to_string_lossy().to_string(),
+        let name = match name_from_raw(&d.name) {
+            Ok(n) => n,
             Err(e) => {
                 log::error!("RtAudio: Failed to parse audio device name: {}", e);
 
@@ -63,8 +131,8 @@ impl DeviceInfo {
             }
         };
 
-        Self {
-            id: DeviceID(d.id as u32),
+        Some(Self {
+            id,
             output_channels: d.output_channels as u32,
             input_channels: d.input_channels as u32,
             duplex_channels: d.duplex_channels as u32,
@@ -74,6 +142,464 @@ impl DeviceInfo {
             preferred_sample_rate: d.preferred_sample_rate as u32,
             sample_rates,
             name,
+        })
+    }
+
+    /// Whether or not this device supports the given sample rate.
+    ///
+    /// A device which reports no sample rates is treated as supporting any
+    /// sample rate, the same as in `DeviceInfo::common_sample_rates()` and
+    /// `Host::try_open_stream()`.
+    ///
+    /// ```
+    /// use rtaudio::{Api, Host};
+    ///
+    /// let host = Host::new(Api::Unspecified).unwrap();
+    ///
+    /// for device in host.iter_devices().filter(|d| d.supports_sample_rate(48000)) {
+    ///     println!("{} supports 48 kHz", device.name);
+    /// }
+    /// ```
+    pub fn supports_sample_rate(&self, sample_rate: u32) -> bool {
+        self.sample_rates.is_empty() || self.sample_rates.contains(&sample_rate)
+    }
+
+    /// Whether or not the given sample format is one of the native formats
+    /// of this device (so RtAudio does not need to convert the samples).
+    pub fn supports_native_format(&self, format: SampleFormat) -> bool {
+        self.native_formats.contains_format(format)
+    }
+
+    /// The maximum number of output channels a stream can use on this
+    /// device (`0` if it has no outputs).
+    pub fn max_output_channels(&self) -> u32 {
+        self.output_channels
+    }
+
+    /// The maximum number of input channels a stream can use on this
+    /// device (`0` if it has no inputs).
+    pub fn max_input_channels(&self) -> u32 {
+        self.input_channels
+    }
+
+    /// Whether or not this device can be used for both output and input in
+    /// the same stream.
+    pub fn can_duplex(&self) -> bool {
+        self.duplex_channels > 0
+    }
+
+    /// The lowest and highest sample rates which this device reports, or
+    /// `None` if it does not report any.
+    ///
+    /// Note that the device does not necessarily support every sample rate
+    /// in between. The rates do not need to be sorted.
+    pub fn sample_rate_range(&self) -> Option<(u32, u32)> {
+        let min = self.sample_rates.iter().copied().min()?;
+        let max = self.sample_rates.iter().copied().max()?;
+
+        Some((min, max))
+    }
+
+    /// The standard sample rates (see `STANDARD_SAMPLE_RATES`) which this
+    /// device supports, in ascending order.
+    ///
+    /// A device which reports no sample rates is treated as supporting any
+    /// sample rate, so all standard sample rates are returned.
+    pub fn standard_sample_rates(&self) -> Vec<u32> {
+        STANDARD_SAMPLE_RATES
+            .iter()
+            .copied()
+            .filter(|&sr| self.supports_sample_rate(sr))
+            .collect()
+    }
+
+    /// Parameters for opening a stream on the outputs of this device.
+    ///
+    /// `channels` is the number of channels to use, starting at the first
+    /// channel. It is clamped to the number of output channels of the
+    /// device (see `DeviceParams::clamp_to()`). If it is `None`, then up to
+    /// two channels are used (see `DeviceParams::output_from()`).
+    ///
+    /// Returns `None` if the device has no output channels.
+    ///
+    /// ```
+    /// use rtaudio::{Api, Host};
+    ///
+    /// let host = Host::new(Api::Unspecified).unwrap();
+    ///
+    /// if let Ok(device) = host.default_output_device() {
+    ///     let params = device.output_params(Some(2));
+    ///     println!("{:?}", params);
+    /// }
+    /// ```
+    pub fn output_params(&self, channels: Option<u32>) -> Option<DeviceParams> {
+        self.params_for(Role::Output, channels)
+    }
+
+    /// Parameters for opening a stream on the inputs of this device.
+    ///
+    /// The same as `DeviceInfo::output_params()`, but for the input
+    /// channels. Returns `None` if the device has no input channels.
+    pub fn input_params(&self, channels: Option<u32>) -> Option<DeviceParams> {
+        self.params_for(Role::Input, channels)
+    }
+
+    fn params_for(&self, role: Role, channels: Option<u32>) -> Option<DeviceParams> {
+        let params = DeviceParams::from_device(self, role)?;
+
+        match channels {
+            Some(channels) => params.with_channels(channels).clamp_to(self, role),
+            None => Some(params),
+        }
+    }
+
+    /// Whether or not this info and `current` (i.e. from a fresh scan)
+    /// describe the same device.
+    ///
+    /// The name and the channel counts are compared, but not the ID, since
+    /// IDs are not stable between runs.
+    pub fn matches(&self, current: &DeviceInfo) -> bool {
+        self.name == current.name
+            && self.output_channels == current.output_channels
+            && self.input_channels == current.input_channels
+            && self.duplex_channels == current.duplex_channels
+    }
+
+    /// The sample rates supported by both this device and the other device,
+    /// in ascending order.
+    ///
+    /// A device which reports no sample rates is treated as supporting any
+    /// sample rate.
+    pub fn common_sample_rates(&self, other: &DeviceInfo) -> Vec<u32> {
+        let mut rates: Vec<u32> = if self.sample_rates.is_empty() {
+            other.sample_rates.clone()
+        } else if other.sample_rates.is_empty() {
+            self.sample_rates.clone()
+        } else {
+            self.sample_rates
+                .iter()
+                .copied()
+                .filter(|sr| other.sample_rates.contains(sr))
+                .collect()
+        };
+
+        rates.sort_unstable();
+        rates.dedup();
+        rates
+    }
+
+    /// Returns `false` if this info does not describe an actual device
+    /// (RtAudio returns zero-filled info for devices that are no longer
+    /// present).
+    pub(crate) fn is_present(&self) -> bool {
+        !self.name.is_empty()
+            || self.output_channels > 0
+            || self.input_channels > 0
+            || self.duplex_channels > 0
+    }
+}
+
+/// A compact, single line description of the device, i.e. for CLI tools.
+///
+/// The alternate form (`{:#}`) also lists every sample rate.
+///
+/// ```text
+/// "Speakers" (ID 3): 2 out / 1 in / 1 duplex [default output], 48000 Hz, formats: s16 f32
+/// ```
+impl fmt::Display for DeviceInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "\"{}\" (ID {}): {} out / {} in",
+            self.name, self.id, self.output_channels, self.input_channels
+        )?;
+        if self.duplex_channels > 0 {
+            write!(f, " / {} duplex", self.duplex_channels)?;
+        }
+
+        if self.is_default_output {
+            write!(f, " [default output]")?;
+        }
+        if self.is_default_input {
+            write!(f, " [default input]")?;
+        }
+
+        write!(f, ", {} Hz, formats:", self.preferred_sample_rate)?;
+        if self.native_formats.is_empty() {
+            write!(f, " none")?;
+        }
+        for format in self.native_formats.iter_formats() {
+            write!(f, " {}", format)?;
+        }
+
+        if f.alternate() {
+            write!(f, ", rates:")?;
+            if self.sample_rates.is_empty() {
+                write!(f, " unknown")?;
+            }
+            for rate in &self.sample_rates {
+                write!(f, " {}", rate)?;
+            }
         }
+
+        Ok(())
+    }
+}
+
+/// A duplex device for tests, with 2 output, 2 input and 2 duplex channels,
+/// the native formats `FLOAT32` and `SINT16`, and the sample rates 44100,
+/// 48000 and 96000. Use struct update syntax to change it.
+#[cfg(test)]
+pub(crate) fn test_device() -> DeviceInfo {
+    DeviceInfo {
+        id: DeviceID::new(1).unwrap(),
+        output_channels: 2,
+        input_channels: 2,
+        duplex_channels: 2,
+        is_default_output: true,
+        is_default_input: true,
+        native_formats: NativeFormats::FLOAT32 | NativeFormats::SINT16,
+        preferred_sample_rate: 48000,
+        sample_rates: vec![44100, 48000, 96000],
+        name: String::from("Test Device"),
+    }
+}
+
+pub(crate) fn name_from_raw(
+    raw_name: &[c_char],
+) -> Result<String, std::ffi::FromBytesUntilNulError> {
+    // Safe because i8 and u8 have the same size, and we are correctly
+    // using the length of the array `raw_name`.
+    let name_slice: &[u8] =
+        unsafe { std::slice::from_raw_parts(raw_name.as_ptr() as *const u8, raw_name.len()) };
+
+    CStr::from_bytes_until_nul(name_slice).map(|n| n.to_string_lossy().to_string())
+}
+
+/// An error that occurred while scanning a device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceScanError {
+    /// The index of the device that failed to scan.
+    pub index: usize,
+    /// The ID of the device that failed to scan, if it could be obtained.
+    pub id: Option<DeviceID>,
+    /// The name of the device that failed to scan, if it could be obtained.
+    pub name: Option<String>,
+    /// The error that occurred.
+    pub error: Error,
+}
+
+impl std::error::Error for DeviceScanError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl fmt::Display for DeviceScanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to scan device at index {}", self.index)?;
+
+        if let Some(id) = self.id {
+            write!(f, " (id {}", id)?;
+
+            if let Some(name) = &self.name {
+                write!(f, ", \"{}\"", name)?;
+            }
+
+            write!(f, ")")?;
+        }
+
+        write!(f, ": {}", self.error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scan_error(id: Option<u32>, name: Option<&str>) -> DeviceScanError {
+        DeviceScanError {
+            index: 3,
+            id: id.and_then(DeviceID::new),
+            name: name.map(String::from),
+            error: Error::new(
+                crate::RtAudioErrorType::DriverError,
+                Some("probe failed".into()),
+            ),
+        }
+    }
+
+    #[test]
+    fn scan_error_names_the_failing_device() {
+        let e = scan_error(Some(131), Some("USB Audio"));
+        assert_eq!(
+            e.to_string(),
+            format!(
+                "failed to scan device at index 3 (id 131, \"USB Audio\"): {}",
+                e.error
+            )
+        );
+
+        let e = scan_error(Some(131), None);
+        assert_eq!(
+            e.to_string(),
+            format!("failed to scan device at index 3 (id 131): {}", e.error)
+        );
+
+        let e = scan_error(None, None);
+        assert_eq!(
+            e.to_string(),
+            format!("failed to scan device at index 3: {}", e.error)
+        );
+    }
+
+    #[test]
+    fn capability_queries() {
+        let device = DeviceInfo {
+            input_channels: 0,
+            duplex_channels: 0,
+            ..test_device()
+        };
+
+        assert!(device.supports_sample_rate(44100));
+        assert!(!device.supports_sample_rate(22050));
+
+        assert!(device.supports_native_format(SampleFormat::SInt16));
+        assert!(!device.supports_native_format(SampleFormat::SInt24));
+
+        assert_eq!(device.max_output_channels(), 2);
+        assert_eq!(device.max_input_channels(), 0);
+        assert!(!device.can_duplex());
+    }
+
+    #[test]
+    fn unknown_sample_rates_are_all_supported() {
+        let device = DeviceInfo {
+            sample_rates: Vec::new(),
+            ..test_device()
+        };
+
+        assert!(device.supports_sample_rate(22050));
+        assert_eq!(device.sample_rate_range(), None);
+        assert_eq!(device.standard_sample_rates(), STANDARD_SAMPLE_RATES);
+    }
+
+    #[test]
+    fn sample_rate_range_does_not_need_sorted_rates() {
+        let unsorted = DeviceInfo {
+            sample_rates: vec![96000, 44100, 192000, 48000],
+            ..test_device()
+        };
+        assert_eq!(unsorted.sample_rate_range(), Some((44100, 192000)));
+
+        let single = DeviceInfo {
+            sample_rates: vec![48000],
+            ..test_device()
+        };
+        assert_eq!(single.sample_rate_range(), Some((48000, 48000)));
+    }
+
+    #[test]
+    fn standard_sample_rates_are_sorted() {
+        let unsorted = DeviceInfo {
+            sample_rates: vec![96000, 44100, 47999, 48000],
+            ..test_device()
+        };
+        assert_eq!(unsorted.standard_sample_rates(), vec![44100, 48000, 96000]);
+
+        let nonstandard = DeviceInfo {
+            sample_rates: vec![47999],
+            ..test_device()
+        };
+        assert!(nonstandard.standard_sample_rates().is_empty());
+    }
+
+    #[test]
+    fn output_params_clamp_the_channels() {
+        let device = DeviceInfo {
+            output_channels: 4,
+            input_channels: 0,
+            duplex_channels: 0,
+            ..test_device()
+        };
+
+        let params = device.output_params(Some(2)).unwrap();
+        assert_eq!(params.device_id, Some(device.id));
+        assert_eq!((params.num_channels, params.first_channel), (2, 0));
+
+        // Too many channels are clamped.
+        assert_eq!(device.output_params(Some(8)).unwrap().num_channels, 4);
+        // At least one channel is used.
+        assert_eq!(device.output_params(Some(0)).unwrap().num_channels, 1);
+        assert_eq!(device.output_params(None).unwrap().num_channels, 2);
+
+        assert_eq!(device.input_params(Some(2)), None);
+        assert_eq!(device.input_params(None), None);
+    }
+
+    #[test]
+    fn matches_ignores_the_id_and_default_flags() {
+        let cached = DeviceInfo {
+            id: DeviceID::new(3).unwrap(),
+            name: String::from("Haut-parleurs (Réaltek)"),
+            ..test_device()
+        };
+
+        let current = DeviceInfo {
+            id: DeviceID::new(129).unwrap(),
+            is_default_output: false,
+            ..cached.clone()
+        };
+        assert!(cached.matches(&current));
+
+        let fewer_channels = DeviceInfo {
+            output_channels: 1,
+            ..current
+        };
+        assert!(!cached.matches(&fewer_channels));
+    }
+
+    #[test]
+    fn display_is_a_single_line() {
+        let device = DeviceInfo {
+            id: DeviceID::new(3).unwrap(),
+            input_channels: 1,
+            duplex_channels: 1,
+            is_default_input: false,
+            name: String::from("Speakers"),
+            ..test_device()
+        };
+
+        assert_eq!(
+            device.to_string(),
+            "\"Speakers\" (ID 3): 2 out / 1 in / 1 duplex [default output], 48000 Hz, formats: s16 f32"
+        );
+        assert_eq!(
+            format!("{:#}", device),
+            "\"Speakers\" (ID 3): 2 out / 1 in / 1 duplex [default output], 48000 Hz, formats: s16 f32, \
+             rates: 44100 48000 96000"
+        );
+
+        let device = DeviceInfo {
+            output_channels: 0,
+            duplex_channels: 0,
+            is_default_output: false,
+            is_default_input: true,
+            native_formats: NativeFormats::empty(),
+            sample_rates: Vec::new(),
+            name: String::from("Mic"),
+            ..device
+        };
+        assert_eq!(
+            format!("{:#}", device),
+            "\"Mic\" (ID 3): 0 out / 1 in [default input], 48000 Hz, formats: none, rates: unknown"
+        );
+    }
+
+    #[test]
+    fn scan_error_source_is_the_backend_error() {
+        let e = scan_error(Some(131), None);
+        let source = std::error::Error::source(&e).unwrap();
+        assert_eq!(source.to_string(), e.error.to_string());
     }
 }
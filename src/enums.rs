@@ -1,6 +1,9 @@
 use bitflags::bitflags;
+use std::error::Error;
 use std::ffi::{CStr, CString};
+use std::fmt;
 use std::os::raw::c_char;
+use std::str::FromStr;
 
 bitflags! {
     /// The native formats this device supports.
@@ -13,6 +16,9 @@ bitflags! {
     ///
     /// Note you can still start a stream with any format. RtAudio will just
     /// automatically convert to/from the best native format.
+    ///
+    /// Empty native formats (see `NativeFormats::is_empty()`) mean that the
+    /// device did not report its formats, not that it cannot be used.
     #[repr(C)]
     #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
     pub struct NativeFormats: rtaudio_sys::rtaudio_format_t {
@@ -39,7 +45,8 @@ bitflags! {
 /// and the soundcard. Thus, endian-ness is not a concern in the following format
 /// definitions.
 #[repr(usize)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SampleFormat {
     /// 8-bit signed integer.
     SInt8 = rtaudio_sys::RTAUDIO_FORMAT_SINT8 as usize,
@@ -52,12 +59,289 @@ pub enum SampleFormat {
     /// 32-bit signed integer.
     SInt32 = rtaudio_sys::RTAUDIO_FORMAT_SINT32 as usize,
     /// 32-bit floating point number, normalized between plus/minus 1.0.
+    #[default]
     Float32 = rtaudio_sys::RTAUDIO_FORMAT_FLOAT32 as usize,
     /// 64-bit floating point number, normalized between plus/minus 1.0.
     Float64 = rtaudio_sys::RTAUDIO_FORMAT_FLOAT64 as usize,
 }
 
+/// Every sample format, in the order of their flags in `NativeFormats`.
+const ALL_FORMATS: [SampleFormat; 6] = [
+    SampleFormat::SInt8,
+    SampleFormat::SInt16,
+    SampleFormat::SInt24,
+    SampleFormat::SInt32,
+    SampleFormat::Float32,
+    SampleFormat::Float64,
+];
+
+impl NativeFormats {
+    /// Iterate over the sample formats in these formats, from `SInt8` to
+    /// `Float64`.
+    ///
+    /// (`NativeFormats::iter()` iterates over the flags instead.)
+    ///
+    /// ```
+    /// use rtaudio::{NativeFormats, SampleFormat};
+    ///
+    /// let formats = NativeFormats::FLOAT32 | NativeFormats::SINT16;
+    /// assert_eq!(
+    ///     formats.iter_formats().collect::<Vec<_>>(),
+    ///     [SampleFormat::SInt16, SampleFormat::Float32]
+    /// );
+    ///
+    /// // Every flag maps to exactly one sample format, and back.
+    /// for (name, flag) in NativeFormats::all().iter_names() {
+    ///     let formats: Vec<_> = flag.iter_formats().collect();
+    ///     assert_eq!(formats.len(), 1, "{}", name);
+    ///     assert_eq!(NativeFormats::from(formats[0]), flag);
+    /// }
+    /// // Every sample format maps to exactly one flag, and back.
+    /// for format in SampleFormat::AUTO_RANKING {
+    ///     let flag = NativeFormats::from(format);
+    ///     assert_eq!(flag.iter().count(), 1, "{:?}", format);
+    ///     assert_eq!(flag.iter_formats().collect::<Vec<_>>(), [format]);
+    /// }
+    /// assert_eq!(
+    ///     NativeFormats::all().iter_formats().count(),
+    ///     NativeFormats::all().iter().count()
+    /// );
+    /// ```
+    pub fn iter_formats(&self) -> impl Iterator<Item = SampleFormat> {
+        let formats = *self;
+
+        ALL_FORMATS
+            .into_iter()
+            .filter(move |format| formats.contains_format(*format))
+    }
+
+    /// Whether or not the given sample format is in these formats.
+    pub fn contains_format(&self, format: SampleFormat) -> bool {
+        self.contains(NativeFormats::from(format))
+    }
+
+    /// The names of the sample formats in these formats, from `SInt8` to
+    /// `Float64`.
+    ///
+    /// These are the same names as in the `Display` output of
+    /// `SampleFormat`.
+    ///
+    /// ```
+    /// use rtaudio::NativeFormats;
+    ///
+    /// let formats = NativeFormats::FLOAT32 | NativeFormats::SINT16 | NativeFormats::SINT24;
+    /// assert_eq!(formats.names(), ["s16", "s24", "f32"]);
+    /// assert_eq!(formats.to_string(), "s16, s24, f32");
+    ///
+    /// assert_eq!(
+    ///     NativeFormats::all().to_string(),
+    ///     "s8, s16, s24, s32, f32, f64"
+    /// );
+    ///
+    /// // The device did not report its formats.
+    /// assert!(NativeFormats::empty().names().is_empty());
+    /// assert_eq!(NativeFormats::empty().to_string(), "none");
+    /// ```
+    pub fn names(&self) -> Vec<&'static str> {
+        self.iter_formats()
+            .map(|format| format.short_name())
+            .collect()
+    }
+
+    /// The first format in `preference` which is in these formats.
+    ///
+    /// `SampleFormat::AUTO_RANKING` is a sensible order of preference.
+    ///
+    /// ```
+    /// use rtaudio::{NativeFormats, SampleFormat};
+    ///
+    /// let formats = NativeFormats::SINT16 | NativeFormats::SINT32;
+    /// assert_eq!(
+    ///     formats.best(&SampleFormat::AUTO_RANKING),
+    ///     Some(SampleFormat::SInt32)
+    /// );
+    /// assert_eq!(NativeFormats::empty().best(&SampleFormat::AUTO_RANKING), None);
+    /// ```
+    pub fn best(&self, preference: &[SampleFormat]) -> Option<SampleFormat> {
+        preference
+            .iter()
+            .copied()
+            .find(|format| self.contains_format(*format))
+    }
+}
+
+impl TryFrom<NativeFormats> for SampleFormat {
+    type Error = NotSingleFormatError;
+
+    /// Fails unless exactly one format is set.
+    fn try_from(formats: NativeFormats) -> Result<Self, Self::Error> {
+        SampleFormat::from_raw(formats.bits()).ok_or(NotSingleFormatError { formats })
+    }
+}
+
+/// The error returned when converting `NativeFormats` which do not contain
+/// exactly one format into a `SampleFormat`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NotSingleFormatError {
+    /// The formats that could not be converted.
+    pub formats: NativeFormats,
+}
+
+impl Error for NotSingleFormatError {}
+
+impl fmt::Display for NotSingleFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "expected exactly one sample format, got {}",
+            self.formats
+        )
+    }
+}
+
+/// A comma-separated list of the sample formats (see
+/// `NativeFormats::names()`), or `none` if it is empty.
+impl fmt::Display for NativeFormats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return f.write_str("none");
+        }
+
+        for (i, format) in self.iter_formats().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", format)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl From<SampleFormat> for NativeFormats {
+    fn from(format: SampleFormat) -> Self {
+        match format {
+            SampleFormat::SInt8 => NativeFormats::SINT8,
+            SampleFormat::SInt16 => NativeFormats::SINT16,
+            SampleFormat::SInt24 => NativeFormats::SINT24,
+            SampleFormat::SInt32 => NativeFormats::SINT32,
+            SampleFormat::Float32 => NativeFormats::FLOAT32,
+            SampleFormat::Float64 => NativeFormats::FLOAT64,
+        }
+    }
+}
+
 impl SampleFormat {
+    /// The order in which sample formats are preferred by
+    /// `StreamBuilder::format_auto()`.
+    ///
+    /// `Float32` is preferred since it is the most convenient to process,
+    /// followed by the integer formats from the highest to the lowest
+    /// resolution. `Float64` is ranked below them since few devices
+    /// support it natively, and it only doubles the bandwidth.
+    pub const AUTO_RANKING: [SampleFormat; 6] = [
+        SampleFormat::Float32,
+        SampleFormat::SInt32,
+        SampleFormat::SInt24,
+        SampleFormat::SInt16,
+        SampleFormat::Float64,
+        SampleFormat::SInt8,
+    ];
+
+    /// The number of bytes in a single sample.
+    ///
+    /// `SInt24` samples are packed, so they are `3` bytes long.
+    ///
+    /// ```
+    /// use rtaudio::SampleFormat;
+    ///
+    /// let formats = [
+    ///     (SampleFormat::SInt8, 1, false, true, "s8"),
+    ///     (SampleFormat::SInt16, 2, false, true, "s16"),
+    ///     (SampleFormat::SInt24, 3, false, true, "s24"),
+    ///     (SampleFormat::SInt32, 4, false, true, "s32"),
+    ///     (SampleFormat::Float32, 4, true, false, "f32"),
+    ///     (SampleFormat::Float64, 8, true, false, "f64"),
+    /// ];
+    /// // Every format is listed above.
+    /// assert_eq!(formats.len(), SampleFormat::AUTO_RANKING.len());
+    ///
+    /// for (format, bytes, is_float, is_signed_int, name) in formats {
+    ///     assert_eq!(format.bytes_per_sample(), bytes);
+    ///     assert_eq!(format.is_float(), is_float);
+    ///     assert_eq!(format.is_signed_int(), is_signed_int);
+    ///     assert_eq!(format.to_string(), name);
+    ///     assert_eq!(name.parse(), Ok(format));
+    ///     assert_eq!(name.to_uppercase().parse(), Ok(format));
+    /// }
+    ///
+    /// assert!("i24".parse::<SampleFormat>().is_err());
+    /// ```
+    pub const fn bytes_per_sample(&self) -> usize {
+        match self {
+            SampleFormat::SInt8 => 1,
+            SampleFormat::SInt16 => 2,
+            SampleFormat::SInt24 => 3,
+            SampleFormat::SInt32 => 4,
+            SampleFormat::Float32 => 4,
+            SampleFormat::Float64 => 8,
+        }
+    }
+
+    /// Whether or not this is a floating point format.
+    pub const fn is_float(&self) -> bool {
+        matches!(self, SampleFormat::Float32 | SampleFormat::Float64)
+    }
+
+    /// Whether or not this is a signed integer format.
+    pub const fn is_signed_int(&self) -> bool {
+        !self.is_float()
+    }
+
+    /// The short name of this format, which is used by `Display` and
+    /// `FromStr`.
+    fn short_name(&self) -> &'static str {
+        match self {
+            SampleFormat::SInt8 => "s8",
+            SampleFormat::SInt16 => "s16",
+            SampleFormat::SInt24 => "s24",
+            SampleFormat::SInt32 => "s32",
+            SampleFormat::Float32 => "f32",
+            SampleFormat::Float64 => "f64",
+        }
+    }
+
+    /// The sample format with the given raw value, or `None` if the value
+    /// is not exactly one of the `RTAUDIO_FORMAT_*` constants.
+    ///
+    /// ```
+    /// use rtaudio::{NativeFormats, SampleFormat};
+    ///
+    /// for format in SampleFormat::AUTO_RANKING {
+    ///     assert_eq!(SampleFormat::from_raw(format.to_raw()), Some(format));
+    /// }
+    ///
+    /// // Every single format round trips through `NativeFormats`, and any
+    /// // other combination of formats is an error.
+    /// for bits in 0..=NativeFormats::all().bits() {
+    ///     let formats = NativeFormats::from_bits_truncate(bits);
+    ///     let res = SampleFormat::try_from(formats);
+    ///
+    ///     if formats.bits().count_ones() == 1 {
+    ///         assert_eq!(NativeFormats::from(res.unwrap()), formats);
+    ///         assert_eq!(SampleFormat::from_raw(bits), res.ok());
+    ///     } else {
+    ///         assert_eq!(res.unwrap_err().formats, formats);
+    ///         assert_eq!(SampleFormat::from_raw(bits), None);
+    ///     }
+    /// }
+    /// ```
+    pub fn from_raw(raw: rtaudio_sys::rtaudio_format_t) -> Option<SampleFormat> {
+        ALL_FORMATS
+            .into_iter()
+            .find(|format| format.to_raw() == raw)
+    }
+
     pub fn to_raw(&self) -> rtaudio_sys::rtaudio_format_t {
         match self {
             SampleFormat::SInt8 => rtaudio_sys::RTAUDIO_FORMAT_SINT8,
@@ -70,9 +354,90 @@ impl SampleFormat {
     }
 }
 
-impl Default for SampleFormat {
-    fn default() -> Self {
-        SampleFormat::Float32
+impl fmt::Display for SampleFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.short_name())
+    }
+}
+
+impl FromStr for SampleFormat {
+    type Err = ParseSampleFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        ALL_FORMATS
+            .iter()
+            .find(|format| s.eq_ignore_ascii_case(format.short_name()))
+            .copied()
+            .ok_or_else(|| ParseSampleFormatError {
+                name: s.to_string(),
+            })
+    }
+}
+
+/// The error returned when parsing a `SampleFormat` from a string fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseSampleFormatError {
+    /// The name that could not be parsed.
+    pub name: String,
+}
+
+impl Error for ParseSampleFormatError {}
+
+impl fmt::Display for ParseSampleFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown sample format \"{}\", expected one of s8, s16, s24, s32, f32, f64",
+            self.name
+        )
+    }
+}
+
+/// How the samples of each channel are laid out in the buffers.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum InterleaveMode {
+    /// The samples of each frame are next to each other
+    /// (`L0 R0 L1 R1 ...`).
+    #[default]
+    Interleaved,
+    /// All of the samples of one channel come before the samples of the
+    /// next channel (`L0 L1 ... R0 R1 ...`). This is the same as the
+    /// `StreamFlags::NONINTERLEAVED` flag.
+    Planar,
+}
+
+impl InterleaveMode {
+    /// The mode which is selected by the given stream flags.
+    pub fn from_flags(flags: StreamFlags) -> Self {
+        if flags.contains(StreamFlags::NONINTERLEAVED) {
+            InterleaveMode::Planar
+        } else {
+            InterleaveMode::Interleaved
+        }
+    }
+
+    /// Set or clear the `StreamFlags::NONINTERLEAVED` flag in `flags` to
+    /// select this mode.
+    pub fn apply_to(&self, flags: &mut StreamFlags) {
+        flags.set(StreamFlags::NONINTERLEAVED, *self == InterleaveMode::Planar);
+    }
+
+    /// The index of the sample of `channel` in `frame` in a buffer with
+    /// this layout, which holds `frames` frames of `channels` channels.
+    pub(crate) fn sample_index(
+        &self,
+        channel: usize,
+        frame: usize,
+        channels: usize,
+        frames: usize,
+    ) -> usize {
+        match self {
+            InterleaveMode::Interleaved => frame * channels + channel,
+            InterleaveMode::Planar => channel * frames + frame,
+        }
     }
 }
 
@@ -94,6 +459,9 @@ bitflags! {
         const SCHEDULE_REALTIME = rtaudio_sys::RTAUDIO_FLAGS_SCHEDULE_REALTIME;
         /// Attempt to open the "default" PCM device when using the ALSA API. Note that
         /// this will override any specified input or output device index.
+        ///
+        /// The bundled version of RtAudio ignores this flag, so it currently has
+        /// no effect. Open the "Default ALSA Device" by its ID instead.
         const ALSA_USE_DEFAULT = rtaudio_sys::RTAUDIO_FLAGS_ALSA_USE_DEFAULT;
         /// Do not automatically connect ports (JACK only).
         const JACK_DONT_CONNECT = rtaudio_sys::RTAUDIO_FLAGS_JACK_DONT_CONNECT;
@@ -112,6 +480,35 @@ bitflags! {
     }
 }
 
+impl StreamStatus {
+    /// A short description of this status.
+    ///
+    /// This returns `"ok"` if no flags are set.
+    pub fn describe(&self) -> &'static str {
+        let input_overflow = self.contains(StreamStatus::INPUT_OVERFLOW);
+        let output_underflow = self.contains(StreamStatus::OUTPUT_UNDERFLOW);
+
+        match (input_overflow, output_underflow) {
+            (false, false) => "ok",
+            (true, false) => "input overflow",
+            (false, true) => "output underflow",
+            (true, true) => "input overflow, output underflow",
+        }
+    }
+}
+
+impl fmt::Display for StreamStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.describe())
+    }
+}
+
+/// An audio API (backend) supported by RtAudio.
+///
+/// The `Display` implementation prints the short lower-case name of the API
+/// (the same name as returned by `Api::get_name()`), and the `FromStr`
+/// implementation accepts either the short name or the display name (case
+/// insensitive). Neither of these require the API to be compiled in.
 #[repr(i32)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Api {
@@ -197,15 +594,7 @@ impl Api {
         // Safe because we have constructed a valid C String.
         let index = unsafe { rtaudio_sys::rtaudio_compiled_api_by_name(c_name.as_ptr()) };
 
-        if let Some(a) = Self::from_raw(index) {
-            if a == Api::Unspecified {
-                None
-            } else {
-                Some(a)
-            }
-        } else {
-            None
-        }
+        Self::from_raw(index).filter(|&a| a != Api::Unspecified)
     }
 
     pub fn from_raw(a: rtaudio_sys::rtaudio_api_t) -> Option<Api> {
@@ -227,4 +616,179 @@ impl Api {
     pub fn to_raw(&self) -> rtaudio_sys::rtaudio_api_t {
         *self as rtaudio_sys::rtaudio_api_t
     }
+
+    /// Every API, in the order of their raw values.
+    ///
+    /// This does not depend on which APIs are compiled in (see
+    /// `compiled_apis()`), so it can be used to list every API, i.e. with
+    /// the ones which are not available greyed out.
+    pub const ALL: &'static [Api] = &ALL_APIS;
+
+    /// The short lower-case name of the API, which is the same as
+    /// `Api::get_name()` but does not call into RtAudio.
+    ///
+    /// This mirrors RtAudio's internal table, and it is what `Display`
+    /// writes and `FromStr` accepts.
+    ///
+    /// ```
+    /// use rtaudio::Api;
+    ///
+    /// assert_eq!(Api::WindowsWASAPI.name_static(), "wasapi");
+    /// assert_eq!(Api::WindowsWASAPI.display_name_static(), "WASAPI");
+    ///
+    /// // The table matches the one in RtAudio.
+    /// for api in Api::ALL {
+    ///     assert_eq!(api.name_static(), api.get_name());
+    ///     assert_eq!(api.display_name_static(), api.get_display_name());
+    /// }
+    /// for api in rtaudio::compiled_apis() {
+    ///     assert!(Api::ALL.contains(&api));
+    ///     assert_eq!(Api::from_name(api.name_static()), Some(api));
+    /// }
+    /// ```
+    pub fn name_static(&self) -> &'static str {
+        match self {
+            Api::Unspecified => "unspecified",
+            Api::MacOSXCore => "core",
+            Api::LinuxALSA => "alsa",
+            Api::UnixJack => "jack",
+            Api::LinuxPulse => "pulse",
+            Api::LinuxOSS => "oss",
+            Api::WindowsASIO => "asio",
+            Api::WindowsWASAPI => "wasapi",
+            Api::WindowsDS => "ds",
+            Api::Dummy => "dummy",
+        }
+    }
+
+    /// The display name of the API, which is the same as
+    /// `Api::get_display_name()` but does not call into RtAudio.
+    pub fn display_name_static(&self) -> &'static str {
+        match self {
+            Api::Unspecified => "Unknown",
+            Api::MacOSXCore => "CoreAudio",
+            Api::LinuxALSA => "ALSA",
+            Api::UnixJack => "Jack",
+            Api::LinuxPulse => "Pulse",
+            Api::LinuxOSS => "OpenSoundSystem",
+            Api::WindowsASIO => "ASIO",
+            Api::WindowsWASAPI => "WASAPI",
+            Api::WindowsDS => "DirectSound",
+            Api::Dummy => "Dummy",
+        }
+    }
+}
+
+const ALL_APIS: [Api; 10] = [
+    Api::Unspecified,
+    Api::MacOSXCore,
+    Api::LinuxALSA,
+    Api::UnixJack,
+    Api::LinuxPulse,
+    Api::LinuxOSS,
+    Api::WindowsASIO,
+    Api::WindowsWASAPI,
+    Api::WindowsDS,
+    Api::Dummy,
+];
+
+impl fmt::Display for Api {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name_static())
+    }
+}
+
+impl FromStr for Api {
+    type Err = ParseApiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        ALL_APIS
+            .iter()
+            .find(|api| {
+                s.eq_ignore_ascii_case(api.name_static())
+                    || s.eq_ignore_ascii_case(api.display_name_static())
+            })
+            .copied()
+            .ok_or_else(|| ParseApiError {
+                name: s.to_string(),
+            })
+    }
+}
+
+/// The error returned when parsing an `Api` from a string fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseApiError {
+    /// The name that could not be parsed.
+    pub name: String,
+}
+
+impl Error for ParseApiError {}
+
+impl fmt::Display for ParseApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown audio API \"{}\"", self.name)
+    }
+}
+
+/// Whether a device is being used for output or input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Role {
+    Output,
+    Input,
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Role::Output => f.write_str("output"),
+            Role::Input => f.write_str("input"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn api_display_round_trips() {
+        for &api in Api::ALL {
+            let name = api.to_string();
+            assert_eq!(name, api.name_static());
+            assert_eq!(name.parse::<Api>(), Ok(api));
+        }
+    }
+
+    #[test]
+    fn api_from_str_accepts_display_names() {
+        assert_eq!("CoreAudio".parse::<Api>(), Ok(Api::MacOSXCore));
+        assert_eq!("directsound".parse::<Api>(), Ok(Api::WindowsDS));
+        assert_eq!("  ALSA ".parse::<Api>(), Ok(Api::LinuxALSA));
+        assert_eq!("OpenSoundSystem".parse::<Api>(), Ok(Api::LinuxOSS));
+    }
+
+    #[test]
+    fn api_from_str_rejects_unknown_names() {
+        let e = " coreaudio2 ".parse::<Api>().unwrap_err();
+        assert_eq!(e.name, "coreaudio2");
+        assert_eq!(e.to_string(), "unknown audio API \"coreaudio2\"");
+    }
+
+    #[test]
+    fn sample_index_follows_the_interleave_mode() {
+        // 2 channels of 3 frames.
+        let interleaved: Vec<_> = (0..3)
+            .flat_map(|f| (0..2).map(move |c| (c, f)))
+            .map(|(c, f)| InterleaveMode::Interleaved.sample_index(c, f, 2, 3))
+            .collect();
+        assert_eq!(interleaved, [0, 1, 2, 3, 4, 5]);
+
+        let planar: Vec<_> = (0..3)
+            .flat_map(|f| (0..2).map(move |c| (c, f)))
+            .map(|(c, f)| InterleaveMode::Planar.sample_index(c, f, 2, 3))
+            .collect();
+        assert_eq!(planar, [0, 3, 1, 4, 2, 5]);
+    }
 }
@@ -1,21 +1,624 @@
-use std::error::Error;
 use std::ffi::CStr;
 use std::fmt;
 use std::os::raw::c_char;
 
+use crate::{Api, DeviceID, Host, Role};
+
+/// An error reported by RtAudio.
+///
+/// Each variant carries the message reported by the backend, if there was
+/// one, the operation that failed (see [`ErrorContext`]), if it is known,
+/// and the API and devices involved (see [`ErrorOrigin`]), if they are
+/// known. Use `Error::kind()` to retrieve the `RtAudioErrorType` of the
+/// error, and `Error::raw_code()` to retrieve the numeric RtAudio error code.
+///
+/// Non-critical warnings are never returned as an `Error`. Those are
+/// reported separately as an [`RtAudioWarning`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// An unspecified error.
+    ///
+    /// `code` is the raw RtAudio error code. This is usually
+    /// `RTAUDIO_ERROR_UNKNOWN`, but it is also used for codes which this
+    /// crate does not recognize.
+    Unknown {
+        code: i32,
+        msg: Option<ErrorMessage>,
+        context: Option<ErrorContext>,
+        origin: Option<Box<ErrorOrigin>>,
+    },
+    /// No devices found on system.
+    NoDevicesFound {
+        msg: Option<ErrorMessage>,
+        context: Option<ErrorContext>,
+        origin: Option<Box<ErrorOrigin>>,
+    },
+    /// An invalid device ID was specified.
+    InvalidDevice {
+        msg: Option<ErrorMessage>,
+        context: Option<ErrorContext>,
+        origin: Option<Box<ErrorOrigin>>,
+    },
+    /// A device in use was disconnected.
+    DeviceDisconnect {
+        msg: Option<ErrorMessage>,
+        context: Option<ErrorContext>,
+        origin: Option<Box<ErrorOrigin>>,
+    },
+    /// An error occurred during memory allocation.
+    MemoryError {
+        msg: Option<ErrorMessage>,
+        context: Option<ErrorContext>,
+        origin: Option<Box<ErrorOrigin>>,
+    },
+    /// An invalid parameter was specified to a function.
+    InvalidParameter {
+        msg: Option<ErrorMessage>,
+        context: Option<ErrorContext>,
+        origin: Option<Box<ErrorOrigin>>,
+    },
+    /// The function was called incorrectly.
+    InvalidUse {
+        msg: Option<ErrorMessage>,
+        context: Option<ErrorContext>,
+        origin: Option<Box<ErrorOrigin>>,
+    },
+    /// A system driver error occurred.
+    DriverError {
+        msg: Option<ErrorMessage>,
+        context: Option<ErrorContext>,
+        origin: Option<Box<ErrorOrigin>>,
+    },
+    /// A system error occurred.
+    SystemError {
+        msg: Option<ErrorMessage>,
+        context: Option<ErrorContext>,
+        origin: Option<Box<ErrorOrigin>>,
+    },
+    /// A thread error occurred.
+    ThreadError {
+        msg: Option<ErrorMessage>,
+        context: Option<ErrorContext>,
+        origin: Option<Box<ErrorOrigin>>,
+    },
+}
+
+impl Error {
+    /// Create an error of the given type.
+    pub fn new(kind: RtAudioErrorType, msg: Option<String>) -> Self {
+        Self::with_message(kind, msg.map(ErrorMessage::from))
+    }
+
+    /// Create an error from a raw RtAudio error code and the raw bytes of
+    /// the message reported by the backend.
+    ///
+    /// This will return `None` if the code does not represent an error
+    /// (`RTAUDIO_ERROR_NONE` or `RTAUDIO_ERROR_WARNING`). Codes which are
+    /// not recognized are returned as `Error::Unknown` with the original
+    /// code. An empty message is treated as no message.
+    ///
+    /// The message does not need to be valid UTF-8:
+    ///
+    /// ```
+    /// use rtaudio::{Error, RtAudioErrorType};
+    ///
+    /// let e = Error::from_raw(RtAudioErrorType::DriverError as i32, Some(b"caf\xe9")).unwrap();
+    /// assert_eq!(e.raw_code(), RtAudioErrorType::DriverError as i32);
+    /// assert_eq!(e.raw_message(), Some(&b"caf\xe9"[..]));
+    /// assert_eq!(e.msg(), Some("caf\u{FFFD}"));
+    ///
+    /// let e = Error::from_raw(1234, None).unwrap();
+    /// assert_eq!(e.kind(), RtAudioErrorType::Unknown);
+    /// assert_eq!(e.raw_code(), 1234);
+    /// ```
+    pub fn from_raw(code: rtaudio_sys::rtaudio_error_t, msg: Option<&[u8]>) -> Option<Self> {
+        let msg = msg
+            .filter(|msg| !msg.is_empty())
+            .map(|msg| ErrorMessage::from_bytes(msg.to_vec()));
+
+        let kind = RtAudioErrorType::from_raw(code)?;
+
+        Some(match kind {
+            RtAudioErrorType::Unknown => Error::Unknown {
+                code,
+                msg,
+                context: None,
+                origin: None,
+            },
+            kind => Self::with_message(kind, msg),
+        })
+    }
+
+    fn with_message(kind: RtAudioErrorType, msg: Option<ErrorMessage>) -> Self {
+        let context = None;
+        let origin = None;
+
+        match kind {
+            RtAudioErrorType::Unknown => Error::Unknown {
+                code: rtaudio_sys::RTAUDIO_ERROR_UNKNOWN,
+                msg,
+                context,
+                origin,
+            },
+            RtAudioErrorType::NoDevicesFound => Error::NoDevicesFound {
+                msg,
+                context,
+                origin,
+            },
+            RtAudioErrorType::InvalidDevice => Error::InvalidDevice {
+                msg,
+                context,
+                origin,
+            },
+            RtAudioErrorType::DeviceDisconnect => Error::DeviceDisconnect {
+                msg,
+                context,
+                origin,
+            },
+            RtAudioErrorType::MemoryError => Error::MemoryError {
+                msg,
+                context,
+                origin,
+            },
+            RtAudioErrorType::InvalidParameter => Error::InvalidParameter {
+                msg,
+                context,
+                origin,
+            },
+            RtAudioErrorType::InvalidUse => Error::InvalidUse {
+                msg,
+                context,
+                origin,
+            },
+            RtAudioErrorType::DriverError => Error::DriverError {
+                msg,
+                context,
+                origin,
+            },
+            RtAudioErrorType::SystemError => Error::SystemError {
+                msg,
+                context,
+                origin,
+            },
+            RtAudioErrorType::ThreadError => Error::ThreadError {
+                msg,
+                context,
+                origin,
+            },
+        }
+    }
+
+    /// Set the operation that failed.
+    ///
+    /// The context is included in the `Display` output:
+    ///
+    /// ```
+    /// use rtaudio::{Error, ErrorContext, RtAudioErrorType};
+    ///
+    /// let e = Error::new(RtAudioErrorType::SystemError, Some("device busy".into()))
+    ///     .with_context(ErrorContext::OpenStream);
+    ///
+    /// assert_eq!(e.context(), Some(&ErrorContext::OpenStream));
+    /// assert_eq!(
+    ///     e.to_string(),
+    ///     "RtAudio: a system error occurred while opening the stream | device busy"
+    /// );
+    /// ```
+    pub fn with_context(mut self, context: ErrorContext) -> Self {
+        *self.context_mut() = Some(context);
+        self
+    }
+
+    /// Set the API and devices involved.
+    ///
+    /// The origin is included in the `Display` output:
+    ///
+    /// ```
+    /// use rtaudio::{Api, DeviceID, Error, ErrorContext, ErrorDevice, ErrorOrigin, RtAudioErrorType};
+    ///
+    /// let e = Error::new(RtAudioErrorType::DriverError, None)
+    ///     .with_context(ErrorContext::StartStream)
+    ///     .with_origin(ErrorOrigin {
+    ///         api: Api::WindowsWASAPI,
+    ///         output_device: Some(ErrorDevice {
+    ///             id: DeviceID::new(3).unwrap(),
+    ///             name: Some("Speakers (Realtek)".into()),
+    ///         }),
+    ///         input_device: None,
+    ///     });
+    ///
+    /// assert_eq!(e.api(), Some(Api::WindowsWASAPI));
+    /// assert_eq!(
+    ///     e.to_string(),
+    ///     "RtAudio: a system driver error occurred while starting the stream [WASAPI, output device \"Speakers (Realtek)\" (ID 3)]"
+    /// );
+    /// ```
+    pub fn with_origin(mut self, origin: ErrorOrigin) -> Self {
+        *self.origin_mut() = Some(Box::new(origin));
+        self
+    }
+
+    /// The type of this error.
+    pub fn kind(&self) -> RtAudioErrorType {
+        match self {
+            Error::Unknown { .. } => RtAudioErrorType::Unknown,
+            Error::NoDevicesFound { .. } => RtAudioErrorType::NoDevicesFound,
+            Error::InvalidDevice { .. } => RtAudioErrorType::InvalidDevice,
+            Error::DeviceDisconnect { .. } => RtAudioErrorType::DeviceDisconnect,
+            Error::MemoryError { .. } => RtAudioErrorType::MemoryError,
+            Error::InvalidParameter { .. } => RtAudioErrorType::InvalidParameter,
+            Error::InvalidUse { .. } => RtAudioErrorType::InvalidUse,
+            Error::DriverError { .. } => RtAudioErrorType::DriverError,
+            Error::SystemError { .. } => RtAudioErrorType::SystemError,
+            Error::ThreadError { .. } => RtAudioErrorType::ThreadError,
+        }
+    }
+
+    /// The default severity of this error.
+    ///
+    /// See `RtAudioErrorType::severity()` for how each type of error is
+    /// classified.
+    ///
+    /// ```
+    /// use rtaudio::{Error, ErrorSeverity, RtAudioErrorType};
+    ///
+    /// let e = Error::new(RtAudioErrorType::DeviceDisconnect, None);
+    /// assert_eq!(e.severity(), ErrorSeverity::Recoverable);
+    ///
+    /// let e = Error::new(RtAudioErrorType::ThreadError, None);
+    /// assert_eq!(e.severity(), ErrorSeverity::Fatal);
+    /// ```
+    pub fn severity(&self) -> ErrorSeverity {
+        self.kind().severity()
+    }
+
+    /// The raw RtAudio error code (`rtaudio_error_t`) of this error.
+    pub fn raw_code(&self) -> i32 {
+        match self {
+            Error::Unknown { code, .. } => *code,
+            _ => self.kind() as i32,
+        }
+    }
+
+    /// The message reported by the backend, if there was one.
+    ///
+    /// Any bytes which are not valid UTF-8 are replaced with
+    /// `U+FFFD REPLACEMENT CHARACTER`. Use `Error::raw_message()` to
+    /// retrieve the original bytes.
+    pub fn msg(&self) -> Option<&str> {
+        self.message().map(ErrorMessage::as_str)
+    }
+
+    /// The unmodified bytes of the message reported by the backend, if
+    /// there was one.
+    pub fn raw_message(&self) -> Option<&[u8]> {
+        self.message().map(ErrorMessage::as_bytes)
+    }
+
+    /// The operation that failed, if it is known.
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            Error::Unknown { context, .. }
+            | Error::NoDevicesFound { context, .. }
+            | Error::InvalidDevice { context, .. }
+            | Error::DeviceDisconnect { context, .. }
+            | Error::MemoryError { context, .. }
+            | Error::InvalidParameter { context, .. }
+            | Error::InvalidUse { context, .. }
+            | Error::DriverError { context, .. }
+            | Error::SystemError { context, .. }
+            | Error::ThreadError { context, .. } => context.as_ref(),
+        }
+    }
+
+    /// The API and devices involved, if they are known.
+    pub fn origin(&self) -> Option<&ErrorOrigin> {
+        match self {
+            Error::Unknown { origin, .. }
+            | Error::NoDevicesFound { origin, .. }
+            | Error::InvalidDevice { origin, .. }
+            | Error::DeviceDisconnect { origin, .. }
+            | Error::MemoryError { origin, .. }
+            | Error::InvalidParameter { origin, .. }
+            | Error::InvalidUse { origin, .. }
+            | Error::DriverError { origin, .. }
+            | Error::SystemError { origin, .. }
+            | Error::ThreadError { origin, .. } => origin.as_deref(),
+        }
+    }
+
+    /// The API that was in use when this error occurred, if it is known.
+    pub fn api(&self) -> Option<Api> {
+        self.origin().map(|origin| origin.api)
+    }
+
+    fn context_mut(&mut self) -> &mut Option<ErrorContext> {
+        match self {
+            Error::Unknown { context, .. }
+            | Error::NoDevicesFound { context, .. }
+            | Error::InvalidDevice { context, .. }
+            | Error::DeviceDisconnect { context, .. }
+            | Error::MemoryError { context, .. }
+            | Error::InvalidParameter { context, .. }
+            | Error::InvalidUse { context, .. }
+            | Error::DriverError { context, .. }
+            | Error::SystemError { context, .. }
+            | Error::ThreadError { context, .. } => context,
+        }
+    }
+
+    fn origin_mut(&mut self) -> &mut Option<Box<ErrorOrigin>> {
+        match self {
+            Error::Unknown { origin, .. }
+            | Error::NoDevicesFound { origin, .. }
+            | Error::InvalidDevice { origin, .. }
+            | Error::DeviceDisconnect { origin, .. }
+            | Error::MemoryError { origin, .. }
+            | Error::InvalidParameter { origin, .. }
+            | Error::InvalidUse { origin, .. }
+            | Error::DriverError { origin, .. }
+            | Error::SystemError { origin, .. }
+            | Error::ThreadError { origin, .. } => origin,
+        }
+    }
+
+    fn message(&self) -> Option<&ErrorMessage> {
+        match self {
+            Error::Unknown { msg, .. }
+            | Error::NoDevicesFound { msg, .. }
+            | Error::InvalidDevice { msg, .. }
+            | Error::DeviceDisconnect { msg, .. }
+            | Error::MemoryError { msg, .. }
+            | Error::InvalidParameter { msg, .. }
+            | Error::InvalidUse { msg, .. }
+            | Error::DriverError { msg, .. }
+            | Error::SystemError { msg, .. }
+            | Error::ThreadError { msg, .. } => msg.as_ref(),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RtAudio: {}", self.kind().description())?;
+
+        if let Some(context) = self.context() {
+            write!(f, " while {}", context)?;
+        }
+
+        if let Some(msg) = self.msg() {
+            write!(f, " | {}", msg)?;
+        }
+
+        if let Some(origin) = self.origin() {
+            write!(f, " [{}]", origin)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The operation that failed when an [`Error`] occurred.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorContext {
+    /// Creating the `Host`.
+    CreateHost,
+    /// Getting the information about a device.
+    ///
+    /// `index` is the index of the device when the devices were being
+    /// scanned, and `id` is the ID of the device if it was known.
+    GetDeviceInfo {
+        index: Option<usize>,
+        id: Option<DeviceID>,
+    },
+    /// Opening the stream.
+    OpenStream,
+    /// Querying the latency of the newly opened stream.
+    QueryLatency,
+    /// Querying the sample rate of the newly opened stream.
+    QuerySampleRate,
+    /// Starting the stream.
+    StartStream,
+    /// Stopping the stream.
+    StopStream,
+    /// Closing the stream.
+    CloseStream,
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorContext::CreateHost => write!(f, "creating the host"),
+            ErrorContext::GetDeviceInfo { index, id } => {
+                write!(f, "getting device info")?;
+
+                match (index, id) {
+                    (Some(index), Some(id)) => write!(f, " (index {}, ID {})", index, id),
+                    (Some(index), None) => write!(f, " (index {})", index),
+                    (None, Some(id)) => write!(f, " (ID {})", id),
+                    (None, None) => Ok(()),
+                }
+            }
+            ErrorContext::OpenStream => write!(f, "opening the stream"),
+            ErrorContext::QueryLatency => write!(f, "querying the stream latency"),
+            ErrorContext::QuerySampleRate => write!(f, "querying the stream sample rate"),
+            ErrorContext::StartStream => write!(f, "starting the stream"),
+            ErrorContext::StopStream => write!(f, "stopping the stream"),
+            ErrorContext::CloseStream => write!(f, "closing the stream"),
+        }
+    }
+}
+
+/// The API and devices involved when an [`Error`] occurred.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorOrigin {
+    /// The API that was in use.
+    pub api: Api,
+    /// The output device of the stream, if there is one.
+    pub output_device: Option<ErrorDevice>,
+    /// The input device of the stream, if there is one.
+    pub input_device: Option<ErrorDevice>,
+}
+
+impl ErrorOrigin {
+    /// An origin with the given API and no devices.
+    pub fn new(api: Api) -> Self {
+        Self {
+            api,
+            output_device: None,
+            input_device: None,
+        }
+    }
+}
+
+impl fmt::Display for ErrorOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.api.display_name_static())?;
+
+        for (device, role) in [
+            (&self.output_device, Role::Output),
+            (&self.input_device, Role::Input),
+        ] {
+            if let Some(device) = device {
+                write!(f, ", {} device {}", role, device)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A device involved when an [`Error`] occurred.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorDevice {
+    /// The ID of the device.
+    pub id: DeviceID,
+    /// The name of the device, if it could be obtained.
+    pub name: Option<String>,
+}
+
+impl fmt::Display for ErrorDevice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "\"{}\" (ID {})", name, self.id),
+            None => write!(f, "(ID {})", self.id),
+        }
+    }
+}
+
+/// A message reported by the backend.
+///
+/// RtAudio reports messages as C strings, which are not guaranteed to be
+/// valid UTF-8. The original bytes are kept alongside a lossy UTF-8 copy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorMessage {
+    text: Box<str>,
+    /// The original bytes, only stored if they are not valid UTF-8 (in
+    /// which case they differ from `text`).
+    raw: Option<Box<[u8]>>,
+}
+
+impl ErrorMessage {
+    /// Create a message from the raw bytes reported by the backend.
+    pub fn from_bytes(raw: Vec<u8>) -> Self {
+        match String::from_utf8(raw) {
+            Ok(text) => Self::from(text),
+            Err(e) => {
+                let raw = e.into_bytes();
+
+                Self {
+                    text: String::from_utf8_lossy(&raw).into(),
+                    raw: Some(raw.into_boxed_slice()),
+                }
+            }
+        }
+    }
+
+    /// The message as a string.
+    ///
+    /// Any bytes which are not valid UTF-8 are replaced with
+    /// `U+FFFD REPLACEMENT CHARACTER`.
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// The unmodified bytes of the message.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.raw.as_deref().unwrap_or(self.text.as_bytes())
+    }
+}
+
+impl From<String> for ErrorMessage {
+    fn from(text: String) -> Self {
+        Self {
+            text: text.into_boxed_str(),
+            raw: None,
+        }
+    }
+}
+
+impl From<&str> for ErrorMessage {
+    fn from(text: &str) -> Self {
+        Self::from(String::from(text))
+    }
+}
+
+impl AsRef<str> for ErrorMessage {
+    fn as_ref(&self) -> &str {
+        &self.text
+    }
+}
+
+impl fmt::Display for ErrorMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// The old struct representation of an error reported by RtAudio.
+#[deprecated(note = "use `rtaudio::Error` instead")]
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RtAudioError {
     pub type_: RtAudioErrorType,
     pub msg: Option<String>,
 }
 
+#[allow(deprecated)]
+impl From<Error> for RtAudioError {
+    fn from(e: Error) -> Self {
+        Self {
+            type_: e.kind(),
+            msg: e.msg().map(String::from),
+        }
+    }
+}
+
+#[allow(deprecated)]
+impl From<RtAudioError> for Error {
+    fn from(e: RtAudioError) -> Self {
+        Error::new(e.type_, e.msg)
+    }
+}
+
+#[allow(deprecated)]
+impl std::error::Error for RtAudioError {}
+
+#[allow(deprecated)]
+impl fmt::Display for RtAudioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&Error::new(self.type_.clone(), self.msg.clone()), f)
+    }
+}
+
+/// The type of an error reported by RtAudio.
 #[repr(i32)]
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum RtAudioErrorType {
-    /// A non-critical error.
-    Warning = rtaudio_sys::RTAUDIO_ERROR_WARNING as i32,
     /// An unspecified error type.
-    Unkown = rtaudio_sys::RTAUDIO_ERROR_UNKNOWN as i32,
+    Unknown = rtaudio_sys::RTAUDIO_ERROR_UNKNOWN as i32,
     /// No devices found on system.
     NoDevicesFound = rtaudio_sys::RTAUDIO_ERROR_NO_DEVICES_FOUND as i32,
     /// An invalid device ID was specified.
@@ -25,7 +628,7 @@ pub enum RtAudioErrorType {
     /// An error occurred during memory allocation.
     MemoryError = rtaudio_sys::RTAUDIO_ERROR_MEMORY_ERROR as i32,
     /// An invalid parameter was specified to a function.
-    InvalidParamter = rtaudio_sys::RTAUDIO_ERROR_INVALID_PARAMETER as i32,
+    InvalidParameter = rtaudio_sys::RTAUDIO_ERROR_INVALID_PARAMETER as i32,
     /// The function was called incorrectly.
     InvalidUse = rtaudio_sys::RTAUDIO_ERROR_INVALID_USE as i32,
     /// A system driver error occurred.
@@ -37,55 +640,104 @@ pub enum RtAudioErrorType {
 }
 
 impl RtAudioErrorType {
+    /// The old, misspelled name of `RtAudioErrorType::Unknown`.
+    #[deprecated(note = "use `RtAudioErrorType::Unknown` instead")]
+    #[allow(non_upper_case_globals)]
+    pub const Unkown: Self = Self::Unknown;
+
+    /// The old, misspelled name of `RtAudioErrorType::InvalidParameter`.
+    #[deprecated(note = "use `RtAudioErrorType::InvalidParameter` instead")]
+    #[allow(non_upper_case_globals)]
+    pub const InvalidParamter: Self = Self::InvalidParameter;
+
+    /// Convert a raw RtAudio error code into an error type.
+    ///
+    /// This will return `None` if the code does not represent an error
+    /// (`RTAUDIO_ERROR_NONE` or `RTAUDIO_ERROR_WARNING`). Codes which are not
+    /// recognized are converted into `RtAudioErrorType::Unknown`. Use
+    /// `Error::from_raw()` to keep the original code.
     pub fn from_raw(e: rtaudio_sys::rtaudio_error_t) -> Option<RtAudioErrorType> {
         match e {
             rtaudio_sys::RTAUDIO_ERROR_NONE => None,
-            rtaudio_sys::RTAUDIO_ERROR_WARNING => Some(RtAudioErrorType::Warning),
-            rtaudio_sys::RTAUDIO_ERROR_UNKNOWN => Some(RtAudioErrorType::Unkown),
+            rtaudio_sys::RTAUDIO_ERROR_WARNING => None,
+            rtaudio_sys::RTAUDIO_ERROR_UNKNOWN => Some(RtAudioErrorType::Unknown),
             rtaudio_sys::RTAUDIO_ERROR_NO_DEVICES_FOUND => Some(RtAudioErrorType::NoDevicesFound),
             rtaudio_sys::RTAUDIO_ERROR_INVALID_DEVICE => Some(RtAudioErrorType::InvalidDevice),
             rtaudio_sys::RTAUDIO_ERROR_DEVICE_DISCONNECT => {
                 Some(RtAudioErrorType::DeviceDisconnect)
             }
             rtaudio_sys::RTAUDIO_ERROR_MEMORY_ERROR => Some(RtAudioErrorType::MemoryError),
-            rtaudio_sys::RTAUDIO_ERROR_INVALID_PARAMETER => Some(RtAudioErrorType::InvalidParamter),
+            rtaudio_sys::RTAUDIO_ERROR_INVALID_PARAMETER => {
+                Some(RtAudioErrorType::InvalidParameter)
+            }
             rtaudio_sys::RTAUDIO_ERROR_INVALID_USE => Some(RtAudioErrorType::InvalidUse),
             rtaudio_sys::RTAUDIO_ERROR_DRIVER_ERROR => Some(RtAudioErrorType::DriverError),
             rtaudio_sys::RTAUDIO_ERROR_SYSTEM_ERROR => Some(RtAudioErrorType::SystemError),
             rtaudio_sys::RTAUDIO_ERROR_THREAD_ERROR => Some(RtAudioErrorType::ThreadError),
-            _ => Some(RtAudioErrorType::Unkown),
+            _ => Some(RtAudioErrorType::Unknown),
+        }
+    }
+
+    /// The default severity of an error of this type.
+    ///
+    /// | Type               | Severity      |
+    /// |--------------------|---------------|
+    /// | `NoDevicesFound`   | `Recoverable` |
+    /// | `InvalidDevice`    | `Recoverable` |
+    /// | `DeviceDisconnect` | `Recoverable` |
+    /// | `InvalidParameter` | `Recoverable` |
+    /// | `DriverError`      | `Recoverable` |
+    /// | `SystemError`      | `Recoverable` |
+    /// | `Unknown`          | `Fatal`       |
+    /// | `MemoryError`      | `Fatal`       |
+    /// | `InvalidUse`       | `Fatal`       |
+    /// | `ThreadError`      | `Fatal`       |
+    pub fn severity(&self) -> ErrorSeverity {
+        match self {
+            RtAudioErrorType::NoDevicesFound
+            | RtAudioErrorType::InvalidDevice
+            | RtAudioErrorType::DeviceDisconnect
+            | RtAudioErrorType::InvalidParameter
+            | RtAudioErrorType::DriverError
+            | RtAudioErrorType::SystemError => ErrorSeverity::Recoverable,
+            RtAudioErrorType::Unknown
+            | RtAudioErrorType::MemoryError
+            | RtAudioErrorType::InvalidUse
+            | RtAudioErrorType::ThreadError => ErrorSeverity::Fatal,
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            RtAudioErrorType::Unknown => "unspecified error",
+            RtAudioErrorType::NoDevicesFound => "no devices found on system",
+            RtAudioErrorType::InvalidDevice => "an invalid device ID was specified",
+            RtAudioErrorType::DeviceDisconnect => "a device in use was disconnected",
+            RtAudioErrorType::MemoryError => "an error occurred during memory allocation",
+            RtAudioErrorType::InvalidParameter => {
+                "an invalid parameter was specified to a function"
+            }
+            RtAudioErrorType::InvalidUse => "the function was called incorrectly",
+            RtAudioErrorType::DriverError => "a system driver error occurred",
+            RtAudioErrorType::SystemError => "a system error occurred",
+            RtAudioErrorType::ThreadError => "a thread error occurred",
         }
     }
 }
 
-impl Error for RtAudioError {}
+/// A non-critical warning reported by RtAudio.
+///
+/// Warnings do not cause an operation to fail. By default they are printed
+/// to the log, but they can be received directly by setting a warning
+/// handler with `Host::set_warning_handler()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RtAudioWarning {
+    pub msg: Option<String>,
+}
 
-impl fmt::Display for RtAudioError {
+impl fmt::Display for RtAudioWarning {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self.type_ {
-            RtAudioErrorType::Warning => write!(f, "RtAudio: warning"),
-            RtAudioErrorType::Unkown => write!(f, "RtAudio: unspecified error"),
-            RtAudioErrorType::NoDevicesFound => write!(f, "RtAudio: no devices found on system"),
-            RtAudioErrorType::InvalidDevice => {
-                write!(f, "RtAudio: an invalid device ID was specified")
-            }
-            RtAudioErrorType::DeviceDisconnect => {
-                write!(f, "RtAudio: a device in use was disconnected")
-            }
-            RtAudioErrorType::MemoryError => {
-                write!(f, "RtAudio: an error occurred during memory allocation")
-            }
-            RtAudioErrorType::InvalidParamter => write!(
-                f,
-                "RtAudio: an invalid parameter was specified to a function"
-            ),
-            RtAudioErrorType::InvalidUse => {
-                write!(f, "RtAudio: the function was called incorrectly")
-            }
-            RtAudioErrorType::DriverError => write!(f, "RtAudio: a system driver error occurred"),
-            RtAudioErrorType::SystemError => write!(f, "RtAudio: a system error occurred"),
-            RtAudioErrorType::ThreadError => write!(f, "RtAudio: a thread error occurred"),
-        }?;
+        write!(f, "RtAudio: warning")?;
 
         if let Some(msg) = &self.msg {
             write!(f, " | {}", msg)?;
@@ -95,42 +747,300 @@ impl fmt::Display for RtAudioError {
     }
 }
 
-pub(crate) fn check_for_error(raw: rtaudio_sys::rtaudio_t) -> Result<(), RtAudioError> {
-    assert!(!raw.is_null());
+/// The handler that receives warnings reported by RtAudio.
+pub(crate) type WarningHandler = std::sync::Arc<dyn Fn(RtAudioWarning) + Send + Sync + 'static>;
 
-    // Safe because we checked that the pointer is not null.
-    let raw_type = unsafe { rtaudio_sys::rtaudio_error_type(raw) };
+/// An error or warning that was reported by RtAudio and has not been
+/// checked yet.
+///
+/// This is returned by `Host::take_last_error()` and
+/// `StreamHandle::take_last_error()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LastError {
+    /// An error.
+    Error(Error),
+    /// A non-critical warning.
+    Warning(RtAudioWarning),
+}
 
-    if let Some(type_) = RtAudioErrorType::from_raw(raw_type) {
-        // Safe because this C string will always be valid, we check
-        // for the null case, and we don't free the pointer.
-        let msg = unsafe {
-            let raw_s = rtaudio_sys::rtaudio_error(raw);
-            if raw_s.is_null() {
-                None
-            } else {
-                let msg = CStr::from_ptr(raw_s as *mut c_char)
-                    .to_string_lossy()
-                    .to_string();
-
-                if msg.is_empty() {
-                    None
-                } else {
-                    Some(msg)
-                }
-            }
-        };
+impl fmt::Display for LastError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LastError::Error(e) => fmt::Display::fmt(e, f),
+            LastError::Warning(w) => fmt::Display::fmt(w, f),
+        }
+    }
+}
+
+impl LastError {
+    /// The severity of this error or warning.
+    pub fn severity(&self) -> ErrorSeverity {
+        match self {
+            LastError::Error(e) => e.severity(),
+            LastError::Warning(_) => ErrorSeverity::Warning,
+        }
+    }
+}
+
+/// How serious an error is, and whether it is worth trying again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ErrorSeverity {
+    /// Not an error. The operation succeeded.
+    Warning,
+    /// The operation failed, but it may succeed if it is tried again (i.e.
+    /// once a device is plugged back in), possibly with a different
+    /// configuration. Use `Error::kind()` to decide what to change.
+    Recoverable,
+    /// The operation failed, and trying again is unlikely to help.
+    Fatal,
+}
+
+/// An error which gives back the `Host` that was consumed by the failed
+/// operation (i.e. `Host::open_stream()`), so that it can be used again.
+///
+/// This implements `std::error::Error`, and it converts into an [`Error`],
+/// so it can be propagated with `?`. Note that `Host` is not `Send`, so use
+/// `HostError::into_error()` (or `?` into an [`Error`]) before passing it to
+/// an error type which requires `Send` (i.e. `anyhow::Error`).
+///
+/// ```no_run
+/// use rtaudio::{Api, Host, SampleFormat, StreamOptions};
+///
+/// fn open() -> Result<rtaudio::StreamHandle, rtaudio::Error> {
+///     let host = Host::new(Api::Unspecified)?;
+///
+///     let open = |host: Host, sample_rate| {
+///         host.open_output_stream(
+///             None,
+///             2,
+///             SampleFormat::Float32,
+///             sample_rate,
+///             256,
+///             StreamOptions::default(),
+///             |error| eprintln!("{}", error),
+///         )
+///     };
+///
+///     // If the first attempt fails, try again with the recovered host.
+///     match open(host, 48000) {
+///         Ok(stream) => Ok(stream),
+///         Err(e) => Ok(open(e.host, 44100)?),
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct HostError<E = Error> {
+    /// The `Host` that was consumed by the failed operation.
+    pub host: Host,
+    /// The error that occurred.
+    pub error: E,
+}
+
+impl<E> HostError<E> {
+    /// Discard the `Host` and return the error.
+    pub fn into_error(self) -> E {
+        self.error
+    }
+
+    /// Split this into a `(Host, error)` tuple.
+    pub fn into_parts(self) -> (Host, E) {
+        (self.host, self.error)
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for HostError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl<E> std::error::Error for HostError<E>
+where
+    E: std::error::Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl<E: Into<Error>> From<HostError<E>> for Error {
+    fn from(e: HostError<E>) -> Self {
+        e.error.into()
+    }
+}
 
-        let e = RtAudioError { type_, msg };
+/// Take the error or warning reported by the given RtAudio instance (if
+/// any), clearing the error state of the instance.
+///
+/// A null instance has no error state, so this returns `None` for it.
+pub(crate) fn take_last_error(raw: rtaudio_sys::rtaudio_t) -> Option<LastError> {
+    if raw.is_null() {
+        return None;
+    }
 
-        if let RtAudioErrorType::Warning = e.type_ {
-            log::warn!("{}", e);
+    // Safe because we checked that the pointer is not null.
+    let raw_type = unsafe { rtaudio_sys::rtaudio_error_type(raw) };
 
-            Ok(())
+    if raw_type == rtaudio_sys::RTAUDIO_ERROR_NONE {
+        return None;
+    }
+
+    // Safe because this C string will always be valid, we check
+    // for the null case, and we don't free the pointer.
+    let msg = unsafe {
+        let raw_s = rtaudio_sys::rtaudio_error(raw);
+        if raw_s.is_null() {
+            None
         } else {
-            Err(e)
+            Some(CStr::from_ptr(raw_s as *mut c_char).to_bytes())
         }
+    };
+
+    let last_error = if let Some(e) = Error::from_raw(raw_type, msg) {
+        LastError::Error(e)
     } else {
-        Ok(())
+        let msg = msg
+            .filter(|msg| !msg.is_empty())
+            .map(|msg| String::from_utf8_lossy(msg).into_owned());
+
+        LastError::Warning(RtAudioWarning { msg })
+    };
+
+    clear_error(raw);
+
+    Some(last_error)
+}
+
+/// Check the given RtAudio instance for an error, attaching `context` to it
+/// if there was one.
+///
+/// If the instance only reported a warning, then that warning is returned
+/// instead. A null instance is reported as `Error::InvalidUse` rather than
+/// causing a panic.
+pub(crate) fn check_for_error(
+    raw: rtaudio_sys::rtaudio_t,
+    context: ErrorContext,
+) -> Result<Option<RtAudioWarning>, Error> {
+    if raw.is_null() {
+        return Err(Error::InvalidUse {
+            msg: Some("the RtAudio instance has already been destroyed".into()),
+            context: Some(context),
+            origin: None,
+        });
+    }
+
+    match take_last_error(raw) {
+        None => Ok(None),
+        Some(LastError::Error(e)) => Err(e.with_context(context)),
+        Some(LastError::Warning(w)) => Ok(Some(w)),
+    }
+}
+
+/// Reset the error state of the given RtAudio instance.
+///
+/// Some RtAudio functions (such as `rtaudio_close_stream()` and
+/// `rtaudio_device_count()`) don't reset the error state before running, so
+/// without this an error would be reported again by every following check.
+/// For example, a `Host` returned after a failed `Host::open_stream()` or
+/// after `StreamHandle::close()` would fail to enumerate devices.
+fn clear_error(raw: rtaudio_sys::rtaudio_t) {
+    // There is no dedicated function for this in the C API, but this one
+    // resets the error state and never reports an error itself.
+    //
+    // Safe because the caller checked that the pointer is not null.
+    unsafe {
+        rtaudio_sys::rtaudio_get_stream_sample_rate(raw);
+    }
+}
+
+/// Send the warning to the given handler, or print it to the log if there
+/// is no handler.
+pub(crate) fn handle_warning(warning: RtAudioWarning, handler: Option<&WarningHandler>) {
+    if let Some(handler) = handler {
+        (handler)(warning);
+    } else {
+        log::warn!("{}", warning);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KINDS: [RtAudioErrorType; 10] = [
+        RtAudioErrorType::Unknown,
+        RtAudioErrorType::NoDevicesFound,
+        RtAudioErrorType::InvalidDevice,
+        RtAudioErrorType::DeviceDisconnect,
+        RtAudioErrorType::MemoryError,
+        RtAudioErrorType::InvalidParameter,
+        RtAudioErrorType::InvalidUse,
+        RtAudioErrorType::DriverError,
+        RtAudioErrorType::SystemError,
+        RtAudioErrorType::ThreadError,
+    ];
+
+    #[test]
+    fn every_kind_round_trips_through_its_raw_code() {
+        for kind in KINDS {
+            let e = Error::new(kind.clone(), Some("backend message".into()));
+            assert_eq!(e.kind(), kind);
+            assert_eq!(e.msg(), Some("backend message"));
+            assert_eq!(e.context(), None);
+            assert_eq!(e.origin(), None);
+
+            let raw = Error::from_raw(e.raw_code(), Some(b"backend message")).unwrap();
+            assert_eq!(raw, e);
+        }
+    }
+
+    #[test]
+    fn non_errors_are_not_converted() {
+        assert_eq!(Error::from_raw(rtaudio_sys::RTAUDIO_ERROR_NONE, None), None);
+        assert_eq!(
+            Error::from_raw(rtaudio_sys::RTAUDIO_ERROR_WARNING, Some(b"xrun")),
+            None
+        );
+    }
+
+    #[test]
+    fn empty_messages_are_dropped() {
+        let e = Error::from_raw(rtaudio_sys::RTAUDIO_ERROR_SYSTEM_ERROR, Some(b"")).unwrap();
+        assert_eq!(e.msg(), None);
+        assert_eq!(e.to_string(), "RtAudio: a system error occurred");
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn old_error_struct_converts_both_ways() {
+        let e = Error::new(RtAudioErrorType::DriverError, Some("no driver".into()));
+
+        let old = RtAudioError::from(e.clone());
+        assert_eq!(old.type_, RtAudioErrorType::DriverError);
+        assert_eq!(old.msg.as_deref(), Some("no driver"));
+        assert_eq!(old.to_string(), e.to_string());
+
+        assert_eq!(Error::from(old), e);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn misspelled_kinds_are_aliases() {
+        assert_eq!(RtAudioErrorType::Unkown, RtAudioErrorType::Unknown);
+        assert_eq!(
+            RtAudioErrorType::InvalidParamter,
+            RtAudioErrorType::InvalidParameter
+        );
+        assert_eq!(
+            RtAudioErrorType::from_raw(rtaudio_sys::RTAUDIO_ERROR_INVALID_PARAMETER),
+            Some(RtAudioErrorType::InvalidParamter)
+        );
+        assert!(matches!(
+            Error::new(RtAudioErrorType::Unkown, None),
+            Error::Unknown {
+                code: rtaudio_sys::RTAUDIO_ERROR_UNKNOWN,
+                ..
+            }
+        ));
     }
 }
@@ -0,0 +1,81 @@
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, TryRecvError};
+use std::time::Duration;
+
+use crate::{DeviceParams, Error, Host, HostError, StreamConfig, StreamHandle};
+
+/// The receiving end of the error channel returned by
+/// `Host::open_stream_with_error_channel()`.
+///
+/// The error callback of a stream is only called once, so at most one error
+/// is ever received. An error received from this channel means that the
+/// stream has stopped (for example because a device was unplugged), and the
+/// `StreamHandle` should be manually closed or dropped.
+#[derive(Debug)]
+pub struct ErrorReceiver {
+    rx: Receiver<Error>,
+}
+
+impl ErrorReceiver {
+    /// Receive the error if it has occurred, without blocking.
+    pub fn try_recv(&self) -> Option<Error> {
+        match self.rx.try_recv() {
+            Ok(e) => Some(e),
+            Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => None,
+        }
+    }
+
+    /// Wait up to `timeout` for the error to be received.
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<Error> {
+        match self.rx.recv_timeout(timeout) {
+            Ok(e) => Some(e),
+            Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => None,
+        }
+    }
+
+    /// Block until the error is received.
+    ///
+    /// Returns `None` once the stream has been closed or dropped without an
+    /// error occurring.
+    pub fn recv(&self) -> Option<Error> {
+        self.rx.recv().ok()
+    }
+}
+
+/// Create the error callback and the receiving end of the error channel.
+pub(crate) fn error_channel() -> (impl FnOnce(Error) + Send + 'static, ErrorReceiver) {
+    // One slot is enough since the callback is only called once, and it
+    // means that sending never blocks, so closing the stream (which waits
+    // for the notifier thread) is never held up.
+    let (tx, rx) = mpsc::sync_channel(1);
+
+    let error_callback = move |error: Error| {
+        let _ = tx.try_send(error);
+    };
+
+    (error_callback, ErrorReceiver { rx })
+}
+
+impl Host {
+    /// Open a new audio stream, receiving stream errors through a channel
+    /// instead of an error callback.
+    ///
+    /// This is the same as `Host::open_stream_with_config()`, except that
+    /// errors are sent to the returned `ErrorReceiver`. Sending never blocks.
+    /// The receiver stays valid after the stream is closed, so an error which
+    /// occurs while the stream is being torn down is still delivered.
+    ///
+    /// Only one stream can be opened at a time (this is a limitation with RtAudio).
+    pub fn open_stream_with_error_channel(
+        self,
+        output_device: Option<DeviceParams>,
+        input_device: Option<DeviceParams>,
+        config: &StreamConfig,
+    ) -> Result<(StreamHandle, ErrorReceiver), HostError> {
+        let (error_callback, error_rx) = error_channel();
+
+        let stream =
+            self.open_stream_with_config(output_device, input_device, config, error_callback)?;
+
+        Ok((stream, error_rx))
+    }
+}
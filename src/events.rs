@@ -0,0 +1,328 @@
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::task::{Context, Poll, Waker};
+
+use crate::{Error, RtAudioWarning, StatusStats, StreamHandle, StreamState};
+
+/// The number of events that can be waiting in an `EventStream` at once.
+const CAPACITY: usize = 64;
+
+/// An event of a stream, received from an `EventStream`.
+#[derive(Debug, Clone)]
+pub enum StreamEvent {
+    /// An error occurred which caused the stream to stop. This is the same
+    /// error that is sent to the error callback of the stream.
+    Error(Error),
+    /// A warning from RtAudio. These are only sent if
+    /// `StreamOptions::deliver_warnings` is set.
+    Warning(RtAudioWarning),
+    /// One or more xruns occurred, with the statistics of the stream at the
+    /// time they were noticed.
+    Xrun(StatusStats),
+    /// The state of the stream changed.
+    ///
+    /// The last event of a stream is always `StreamState::Closed` (unless
+    /// the `EventStream` was full).
+    StateChanged(StreamState),
+}
+
+/// A stream of the events of an audio stream, created with
+/// `StreamHandle::events()`.
+///
+/// This implements `futures_core::Stream`, so it can be used with any async
+/// runtime (i.e. with `StreamExt` from the `futures` crate), and
+/// `EventStream::next()` can also be awaited directly. The stream
+/// ends once the audio stream has been closed or dropped and all waiting
+/// events have been received.
+///
+/// The events are sent from the notifier thread of the stream (never from
+/// the audio thread). Errors and warnings are sent as soon as they are
+/// reported, and xruns and state changes are noticed within about 100 ms.
+/// Sending never blocks, so if the events are not received fast enough then
+/// the newest ones are dropped (see `EventStream::dropped_events()`).
+pub struct EventStream {
+    queue: Arc<EventQueue>,
+}
+
+impl EventStream {
+    pub(crate) fn new() -> (Self, Arc<EventQueue>) {
+        let queue = Arc::new(EventQueue {
+            inner: Mutex::new(QueueInner {
+                events: VecDeque::with_capacity(CAPACITY),
+                waker: None,
+                closed: false,
+                dropped: 0,
+            }),
+        });
+
+        (
+            Self {
+                queue: Arc::clone(&queue),
+            },
+            queue,
+        )
+    }
+
+    /// Poll for the next event.
+    ///
+    /// Returns `Poll::Ready(None)` once the stream has ended.
+    pub fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<StreamEvent>> {
+        let mut inner = self.queue.lock();
+
+        if let Some(event) = inner.events.pop_front() {
+            return Poll::Ready(Some(event));
+        }
+        if inner.closed {
+            return Poll::Ready(None);
+        }
+
+        match &mut inner.waker {
+            Some(waker) if waker.will_wake(cx.waker()) => {}
+            waker => *waker = Some(cx.waker().clone()),
+        }
+
+        Poll::Pending
+    }
+
+    /// A future which resolves to the next event, or to `None` once the
+    /// stream has ended.
+    // This is the async counterpart of `Iterator::next()`, like
+    // `StreamExt::next()` in the `futures` crate.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Next<'_> {
+        Next { stream: self }
+    }
+
+    /// Receive an event if there is one waiting, without blocking.
+    pub fn try_next(&mut self) -> Option<StreamEvent> {
+        self.queue.lock().events.pop_front()
+    }
+
+    /// Whether the stream has ended, i.e. the audio stream was closed and
+    /// all waiting events have been received.
+    pub fn is_terminated(&self) -> bool {
+        let inner = self.queue.lock();
+        inner.closed && inner.events.is_empty()
+    }
+
+    /// The number of events that were dropped because the stream was full.
+    pub fn dropped_events(&self) -> usize {
+        self.queue.lock().dropped
+    }
+}
+
+impl futures_core::Stream for EventStream {
+    type Item = StreamEvent;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<StreamEvent>> {
+        EventStream::poll_next(self.get_mut(), cx)
+    }
+}
+
+impl futures_core::FusedStream for EventStream {
+    fn is_terminated(&self) -> bool {
+        EventStream::is_terminated(self)
+    }
+}
+
+impl Drop for EventStream {
+    fn drop(&mut self) {
+        // Stop queueing events which nobody will receive.
+        self.queue.close();
+    }
+}
+
+impl std::fmt::Debug for EventStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let inner = self.queue.lock();
+
+        f.debug_struct("EventStream")
+            .field("waiting", &inner.events.len())
+            .field("closed", &inner.closed)
+            .field("dropped", &inner.dropped)
+            .finish()
+    }
+}
+
+/// The future returned by `EventStream::next()`.
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled"]
+pub struct Next<'a> {
+    stream: &'a mut EventStream,
+}
+
+impl Future for Next<'_> {
+    type Output = Option<StreamEvent>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.stream.poll_next(cx)
+    }
+}
+
+impl StreamHandle {
+    /// Receive the events of this stream (errors, warnings, xruns, and state
+    /// changes) as an async stream.
+    ///
+    /// Only one `EventStream` receives events at a time, so calling this
+    /// again ends the previous one. The error callback of the stream is
+    /// still called as well.
+    ///
+    /// ```no_run
+    /// use rtaudio::{Api, DeviceParams, StreamEvent};
+    /// use std::future::Future;
+    /// use std::sync::Arc;
+    /// use std::task::{Context, Poll, Wake, Waker};
+    ///
+    /// // A minimal executor. Any async runtime can be used instead.
+    /// fn block_on<F: Future>(fut: F) -> F::Output {
+    ///     struct ThreadWaker(std::thread::Thread);
+    ///
+    ///     impl Wake for ThreadWaker {
+    ///         fn wake(self: Arc<Self>) {
+    ///             self.0.unpark();
+    ///         }
+    ///     }
+    ///
+    ///     let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+    ///     let mut cx = Context::from_waker(&waker);
+    ///     let mut fut = Box::pin(fut);
+    ///     loop {
+    ///         if let Poll::Ready(output) = fut.as_mut().poll(&mut cx) {
+    ///             return output;
+    ///         }
+    ///         std::thread::park();
+    ///     }
+    /// }
+    ///
+    /// let host = rtaudio::Host::new(Api::Unspecified)?;
+    /// let mut stream = host
+    ///     .stream_builder()
+    ///     .output_device(DeviceParams::default())
+    ///     .open(|_error| {})
+    ///     .map_err(|e| e.error)?;
+    ///
+    /// let mut events = stream.events();
+    /// stream.start(|mut buffers, _info, _status| buffers.fill_output_silence())?;
+    ///
+    /// block_on(async {
+    ///     while let Some(event) = events.next().await {
+    ///         match event {
+    ///             StreamEvent::Error(e) => {
+    ///                 // The stream has stopped, so shut down.
+    ///                 eprintln!("{}", e);
+    ///                 break;
+    ///             }
+    ///             StreamEvent::Warning(warning) => eprintln!("{}", warning),
+    ///             StreamEvent::Xrun(stats) => eprintln!("xrun ({} so far)", stats.xruns),
+    ///             StreamEvent::StateChanged(state) => println!("{:?}", state),
+    ///         }
+    ///     }
+    /// });
+    ///
+    /// drop(stream);
+    /// # Ok::<(), rtaudio::Error>(())
+    /// ```
+    pub fn events(&self) -> EventStream {
+        let (stream, queue) = EventStream::new();
+        self.set_event_queue(queue);
+        stream
+    }
+}
+
+/// The sending end of an `EventStream`.
+pub(crate) struct EventQueue {
+    inner: Mutex<QueueInner>,
+}
+
+struct QueueInner {
+    events: VecDeque<StreamEvent>,
+    waker: Option<Waker>,
+    /// Set once no more events will be sent.
+    closed: bool,
+    dropped: usize,
+}
+
+impl EventQueue {
+    fn lock(&self) -> MutexGuard<'_, QueueInner> {
+        // The queue is never left in an inconsistent state, so a panic while
+        // it was locked does not matter.
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Send an event without blocking, dropping it if the queue is full.
+    pub(crate) fn push(&self, event: StreamEvent) {
+        let mut inner = self.lock();
+
+        if inner.closed {
+            return;
+        }
+        if inner.events.len() >= CAPACITY {
+            inner.dropped += 1;
+            return;
+        }
+
+        inner.events.push_back(event);
+        let waker = inner.waker.take();
+        drop(inner);
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    /// End the stream once the waiting events have been received.
+    pub(crate) fn close(&self) {
+        let mut inner = self.lock();
+        inner.closed = true;
+        let waker = inner.waker.take();
+        drop(inner);
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::task::Wake;
+
+    use futures_core::{FusedStream, Stream};
+
+    #[derive(Default)]
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn event_stream_is_a_futures_stream() {
+        let (mut events, queue) = EventStream::new();
+        let wakes = Arc::new(CountingWaker::default());
+        let waker = Waker::from(Arc::clone(&wakes));
+        let mut cx = Context::from_waker(&waker);
+
+        assert!(Stream::poll_next(Pin::new(&mut events), &mut cx).is_pending());
+
+        queue.push(StreamEvent::StateChanged(StreamState::Running));
+        assert_eq!(wakes.0.load(Ordering::SeqCst), 1);
+        assert!(matches!(
+            Stream::poll_next(Pin::new(&mut events), &mut cx),
+            Poll::Ready(Some(StreamEvent::StateChanged(StreamState::Running)))
+        ));
+
+        queue.close();
+        assert!(FusedStream::is_terminated(&events));
+        assert!(matches!(
+            Stream::poll_next(Pin::new(&mut events), &mut cx),
+            Poll::Ready(None)
+        ));
+    }
+}
@@ -1,12 +1,39 @@
-use crate::error::{RtAudioError, RtAudioErrorType};
-use crate::{Api, DeviceID, DeviceInfo, DeviceParams, SampleFormat, StreamHandle, StreamOptions};
+use crate::builder::AssertSend;
+use crate::error::{
+    Error, ErrorContext, ErrorOrigin, HostError, LastError, RtAudioWarning, WarningHandler,
+};
+use crate::{
+    Api, BufferSizePolicy, DeviceID, DeviceInfo, DeviceParams, DeviceScanError, HostOptions,
+    ProbeReport, RatePolicy, Role, SampleFormat, StreamBuilder, StreamConfig, StreamHandle,
+    StreamOptions,
+};
+use std::fmt;
 use std::os::raw::{c_int, c_uint};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::time::Duration;
 
 /// An RtAudio Host instance. This is used to enumerate audio devices before
 /// opening a stream.
-#[derive(Debug)]
 pub struct Host {
     pub(crate) raw: rtaudio_sys::rtaudio_t,
+    pub(crate) warning_handler: Option<WarningHandler>,
+    // A boxed slice keeps `Host` (and so `HostError`) small.
+    pub(crate) device_cache: Option<Box<[DeviceInfo]>>,
+    /// Whether or not the RtAudio instance is destroyed when this is
+    /// dropped (see `Host::from_raw()`).
+    pub(crate) owned: bool,
+}
+
+impl fmt::Debug for Host {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Host")
+            .field("raw", &self.raw)
+            .field("warning_handler", &self.warning_handler.is_some())
+            .field("device_cache", &self.device_cache)
+            .field("owned", &self.owned)
+            .finish()
+    }
 }
 
 impl Host {
@@ -15,27 +42,206 @@ impl Host {
     ///
     /// If `Api::Unspecified` is used, then the best one for the system will
     /// automatically be chosen.
-    pub fn new(api: Api) -> Result<Self, RtAudioError> {
+    pub fn new(api: Api) -> Result<Self, Error> {
+        Self::new_with_options(api, HostOptions::default())
+    }
+
+    /// Create a new RtAudio Host with the given API and options. This host is
+    /// used to enumerate audio devices before opening a stream.
+    ///
+    /// If `Api::Unspecified` is used, then the best one for the system will
+    /// automatically be chosen.
+    ///
+    /// The options are applied immediately after the RtAudio instance is
+    /// created, before any devices are enumerated.
+    pub fn new_with_options(api: Api, options: HostOptions) -> Result<Self, Error> {
         // Safe because we check for the null case.
         let raw = unsafe { rtaudio_sys::rtaudio_create(api.to_raw()) };
 
         if raw.is_null() {
-            return Err(RtAudioError {
-                type_: RtAudioErrorType::Unkown,
+            return Err(Error::Unknown {
+                code: rtaudio_sys::RTAUDIO_ERROR_UNKNOWN,
                 msg: Some("failed to create RtAudio instance".into()),
+                context: Some(ErrorContext::CreateHost),
+                origin: None,
             });
         }
 
-        let new_self = Self { raw };
+        let new_self = Self {
+            raw,
+            warning_handler: None,
+            device_cache: None,
+            owned: true,
+        };
+
+        new_self.show_warnings(options.show_warnings);
+
+        new_self.check_for_error(ErrorContext::CreateHost)?;
+
+        if options.jack_no_start_server && new_self.api() == Api::UnixJack {
+            if let Some(timeout) = options.jack_connect_timeout {
+                return new_self.check_jack_server_with_timeout(timeout);
+            }
 
-        crate::check_for_error(new_self.raw)?;
+            new_self.check_jack_server()?;
+        }
 
         Ok(new_self)
     }
 
-    /// Whether or not to print extra warnings to the terminal output.
+    /// Wrap an existing RtAudio instance, i.e. one which is shared with C or
+    /// C++ code.
+    ///
+    /// If `owned` is `true`, then the instance is destroyed when this host
+    /// (or the stream opened from it) is dropped. Otherwise it is left for
+    /// the other code to destroy.
+    ///
+    /// # Safety
+    ///
+    /// * `raw` must be a valid instance created by `rtaudio_create()`, which
+    ///   stays valid for as long as this host or any stream opened from it
+    ///   exists. If `owned` is `true`, nothing else may destroy it.
+    /// * The instance must not have an open stream. RtAudio only supports one
+    ///   stream per instance, and this crate assumes that it owns that
+    ///   stream.
+    /// * The instance must not be used by other code while this host (or a
+    ///   stream opened from it) is in use. The error state of the instance
+    ///   is shared, so an error caused by the other code may be reported by
+    ///   this host, or the other way around.
+    pub unsafe fn from_raw(raw: rtaudio_sys::rtaudio_t, owned: bool) -> Self {
+        Self {
+            raw,
+            warning_handler: None,
+            device_cache: None,
+            owned,
+        }
+    }
+
+    /// Release the RtAudio instance without destroying it.
+    ///
+    /// The caller is then responsible for destroying it with
+    /// `rtaudio_destroy()` (unless the host was not owned in the first
+    /// place, see `Host::from_raw()`).
+    pub fn into_raw(self) -> rtaudio_sys::rtaudio_t {
+        let mut host = std::mem::ManuallyDrop::new(self);
+
+        host.warning_handler = None;
+        host.device_cache = None;
+
+        host.raw
+    }
+
+    /// The raw RtAudio instance.
+    ///
+    /// It must not be destroyed, and a stream must not be opened on it
+    /// directly. See the safety notes of `Host::from_raw()`.
+    pub fn as_raw(&self) -> rtaudio_sys::rtaudio_t {
+        self.raw
+    }
+
+    fn check_jack_server(&self) -> Result<(), Error> {
+        // RtAudio connects to the Jack server when it first enumerates the
+        // devices. If the server is not running, then it only reports a
+        // warning and no devices.
+        let num_devices = self.num_devices();
+
+        self.check_for_error(ErrorContext::CreateHost)?;
+
+        if num_devices == 0 {
+            return Err(Error::SystemError {
+                msg: Some("JACK server not running".into()),
+                context: Some(ErrorContext::CreateHost),
+                origin: None,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn check_jack_server_with_timeout(self, timeout: Duration) -> Result<Self, Error> {
+        let (tx, rx) = mpsc::channel();
+
+        let host = AssertSend(self);
+
+        std::thread::spawn(move || {
+            let host = host.into_inner();
+
+            let res = host.check_jack_server();
+
+            // If the timeout has elapsed, then the receiver has been dropped
+            // and the host is destroyed here instead.
+            let _ = tx.send(AssertSend((host, res)));
+        });
+
+        match rx.recv_timeout(timeout) {
+            Ok(AssertSend((host, res))) => res.map(|()| host),
+            Err(_) => Err(Error::SystemError {
+                msg: Some(
+                    format!(
+                        "timed out after {:?} while connecting to the JACK server",
+                        timeout
+                    )
+                    .into(),
+                ),
+                context: Some(ErrorContext::CreateHost),
+                origin: None,
+            }),
+        }
+    }
+
+    /// Set the handler that receives non-critical warnings reported by
+    /// RtAudio.
+    ///
+    /// Warnings never cause an operation to fail. If no handler is set,
+    /// then warnings are printed to the log.
     ///
-    /// By default this is set to `false`.
+    /// The handler is kept when this host is used to open a stream, and it
+    /// is returned along with the host when the stream is closed. Note that
+    /// warnings which occur inside the audio thread are not sent to this
+    /// handler.
+    pub fn set_warning_handler<F>(&mut self, handler: F)
+    where
+        F: Fn(RtAudioWarning) + Send + Sync + 'static,
+    {
+        self.warning_handler = Some(Arc::new(handler));
+    }
+
+    /// Remove the warning handler, so that warnings are printed to the log
+    /// again.
+    pub fn clear_warning_handler(&mut self) {
+        self.warning_handler = None;
+    }
+
+    /// Take the error or warning that RtAudio has reported but which has not
+    /// been checked yet, if there is one.
+    ///
+    /// Reading the error clears it, so a second call will return `None`
+    /// until RtAudio reports something new. Warnings are returned here
+    /// instead of being sent to the warning handler or printed to the log.
+    ///
+    /// Errors which were already returned by another method of `Host` have
+    /// been cleared, so they are not returned again.
+    pub fn take_last_error(&self) -> Option<LastError> {
+        crate::error::take_last_error(self.raw)
+    }
+
+    pub(crate) fn check_for_error(&self, context: ErrorContext) -> Result<(), Error> {
+        let res = crate::check_for_error(self.raw, context)
+            .map_err(|e| e.with_origin(ErrorOrigin::new(self.api())));
+
+        if let Some(warning) = res? {
+            crate::error::handle_warning(warning, self.warning_handler.as_ref());
+        }
+
+        Ok(())
+    }
+
+    /// Whether or not RtAudio should report warnings. If this is `false`,
+    /// then warnings are discarded by RtAudio and they will not be printed
+    /// to the log or sent to the warning handler.
+    ///
+    /// By default this is set to `true`. This can also be set when the host
+    /// is created with `Host::new_with_options()`.
     pub fn show_warnings(&self, show: bool) {
         let show_int: c_int = if show { 1 } else { 0 };
 
@@ -60,35 +266,132 @@ impl Host {
     }
 
     /// Retrieve information about an audio device by its index.
-    pub fn get_device_info_by_index(&self, index: usize) -> Result<DeviceInfo, RtAudioError> {
+    pub fn get_device_info_by_index(&self, index: usize) -> Result<DeviceInfo, Error> {
+        match self.scan_device(index) {
+            Ok(Some(d)) => Ok(d),
+            Ok(None) => Err(Error::InvalidParameter {
+                msg: Some(format!("Could not find device at index {}", index).into()),
+                context: Some(ErrorContext::GetDeviceInfo {
+                    index: Some(index),
+                    id: None,
+                }),
+                origin: None,
+            }),
+            Err(e) => Err(e.error),
+        }
+    }
+
+    /// Retrieve information about an audio device by its index.
+    ///
+    /// Unlike `Host::get_device_info_by_index()`, this returns `Ok(None)`
+    /// if there is no device at the given index, or if the device is no
+    /// longer present. An error is only returned if the backend reported
+    /// one.
+    pub fn device_by_index(&self, index: usize) -> Result<Option<DeviceInfo>, Error> {
+        self.scan_device(index).map_err(|e| e.error)
+    }
+
+    /// Retrieve information about an audio device by its ID.
+    ///
+    /// Unlike `Host::get_device_info_by_id()`, this returns `Ok(None)` if
+    /// there is no device with the given ID (for example if the device was
+    /// unplugged after its ID was retrieved). An error is only returned if
+    /// the backend reported one.
+    pub fn device_by_id(&self, id: DeviceID) -> Result<Option<DeviceInfo>, Error> {
         // Safe because `self.raw` is gauranteed to not be null.
-        let id = unsafe { rtaudio_sys::rtaudio_get_device_id(self.raw, index as c_int) };
+        let device_info_raw =
+            unsafe { rtaudio_sys::rtaudio_get_device_info(self.raw, id.get() as c_uint) };
 
-        if id == 0 {
-            return Err(RtAudioError {
-                type_: RtAudioErrorType::InvalidParamter,
-                msg: Some(format!("Could not find device at index {}", index)),
-            });
+        match self.check_for_error(ErrorContext::GetDeviceInfo {
+            index: None,
+            id: Some(id),
+        }) {
+            Ok(()) => {}
+            // RtAudio reports an unknown device ID as an invalid parameter.
+            Err(Error::InvalidParameter { .. }) => return Ok(None),
+            Err(e) => return Err(e),
         }
 
-        crate::check_for_error(self.raw)?;
+        Ok(DeviceInfo::from_raw(device_info_raw).filter(|info| info.is_present()))
+    }
+
+    fn scan_device(&self, index: usize) -> Result<Option<DeviceInfo>, DeviceScanError> {
+        // Safe because `self.raw` is gauranteed to not be null.
+        let id = unsafe { rtaudio_sys::rtaudio_get_device_id(self.raw, index as c_int) };
+
+        let Some(id) = DeviceID::new(id as u32) else {
+            return Ok(None);
+        };
+
+        let context = ErrorContext::GetDeviceInfo {
+            index: Some(index),
+            id: Some(id),
+        };
 
-        self.get_device_info_by_id(DeviceID(id as u32))
+        self.check_for_error(context)
+            .map_err(|error| DeviceScanError {
+                index,
+                id: Some(id),
+                name: None,
+                error,
+            })?;
+
+        // Safe because `self.raw` is gauranteed to not be null.
+        let device_info_raw =
+            unsafe { rtaudio_sys::rtaudio_get_device_info(self.raw, id.get() as c_uint) };
+
+        self.check_for_error(context)
+            .map_err(|error| DeviceScanError {
+                index,
+                id: Some(id),
+                name: crate::device_info::name_from_raw(&device_info_raw.name)
+                    .ok()
+                    .filter(|n| !n.is_empty()),
+                error,
+            })?;
+
+        Ok(DeviceInfo::from_raw(device_info_raw).filter(|info| info.is_present()))
     }
 
     /// Retrieve info about an audio device by its ID.
-    pub fn get_device_info_by_id(&self, id: DeviceID) -> Result<DeviceInfo, RtAudioError> {
+    pub fn get_device_info_by_id(&self, id: DeviceID) -> Result<DeviceInfo, Error> {
         // Safe because `self.raw` is gauranteed to not be null.
         let device_info_raw =
-            unsafe { rtaudio_sys::rtaudio_get_device_info(self.raw, id.0 as c_uint) };
+            unsafe { rtaudio_sys::rtaudio_get_device_info(self.raw, id.get() as c_uint) };
 
-        crate::check_for_error(self.raw)?;
+        self.check_for_error(ErrorContext::GetDeviceInfo {
+            index: None,
+            id: Some(id),
+        })?;
 
-        Ok(DeviceInfo::from_raw(device_info_raw))
+        DeviceInfo::from_raw(device_info_raw).ok_or_else(|| Error::InvalidParameter {
+            msg: Some(format!("Could not find device with ID {}", id).into()),
+            context: Some(ErrorContext::GetDeviceInfo {
+                index: None,
+                id: Some(id),
+            }),
+            origin: None,
+        })
     }
 
     /// Retrieve an iterator over all the available audio devices (including ones
     /// that have failed to scan properly).
+    ///
+    /// Devices that failed to scan are returned as a `DeviceScanError`, which
+    /// includes the index (and ID if possible) of the device that failed.
+    ///
+    /// ```no_run
+    /// use rtaudio::{Api, Host};
+    ///
+    /// let host = Host::new(Api::Unspecified).unwrap();
+    ///
+    /// for device in host.iter_devices_complete() {
+    ///     match device {
+    ///         Ok(info) => println!("{}: {}", info.id, info.name),
+    ///         Err(e) => eprintln!("device at index {} failed to scan: {}", e.index, e.error),
+    ///     }
+    /// }
+    /// ```
     pub fn iter_devices_complete<'a>(&'a self) -> DeviceIter<'a> {
         let num_devices = self.num_devices();
         DeviceIter {
@@ -120,7 +423,7 @@ impl Host {
     pub fn iter_output_devices<'a>(&'a self) -> impl Iterator<Item = DeviceInfo> + 'a {
         self.iter_devices_complete().filter_map(|d| match d {
             Ok(d) => {
-                if d.output_channels > 0 {
+                if d.max_output_channels() > 0 {
                     Some(d)
                 } else {
                     None
@@ -141,7 +444,7 @@ impl Host {
     pub fn iter_input_devices<'a>(&'a self) -> impl Iterator<Item = DeviceInfo> + 'a {
         self.iter_devices_complete().filter_map(|d| match d {
             Ok(d) => {
-                if d.input_channels > 0 {
+                if d.max_input_channels() > 0 {
                     Some(d)
                 } else {
                     None
@@ -162,7 +465,7 @@ impl Host {
     pub fn iter_duplex_devices<'a>(&'a self) -> impl Iterator<Item = DeviceInfo> + 'a {
         self.iter_devices_complete().filter_map(|d| match d {
             Ok(d) => {
-                if d.duplex_channels > 0 {
+                if d.can_duplex() {
                     Some(d)
                 } else {
                     None
@@ -198,16 +501,51 @@ impl Host {
     }
     */
 
+    /// Retrieve the list of available audio devices, using a cached list if
+    /// one exists.
+    ///
+    /// The devices are scanned the first time this is called, and the
+    /// result is cached until `Host::rescan_devices()` or
+    /// `Host::invalidate_device_cache()` is called. This is useful for UIs
+    /// which need to retrieve the list of devices often, since scanning
+    /// devices can be slow on some APIs. Use `Host::iter_devices()` to
+    /// always get fresh data.
+    ///
+    /// If there was a problem scanning a device, a warning will be printed
+    /// to the log and that device will be skipped.
+    pub fn devices_cached(&mut self) -> &[DeviceInfo] {
+        if self.device_cache.is_none() {
+            self.device_cache = Some(self.iter_devices().collect());
+        }
+
+        self.device_cache.as_deref().unwrap_or(&[])
+    }
+
+    /// Scan the available audio devices again and update the cached list of
+    /// devices.
+    pub fn rescan_devices(&mut self) -> &[DeviceInfo] {
+        self.invalidate_device_cache();
+        self.devices_cached()
+    }
+
+    /// Clear the cached list of devices, so that they are scanned again the
+    /// next time `Host::devices_cached()` is called.
+    pub fn invalidate_device_cache(&mut self) {
+        self.device_cache = None;
+    }
+
+    /// Retrieve information about an audio device by its ID from the cached
+    /// list of devices (see `Host::devices_cached()`).
+    pub fn cached_device_by_id(&mut self, id: DeviceID) -> Option<&DeviceInfo> {
+        self.devices_cached().iter().find(|d| d.id == id)
+    }
+
     /// Returns the device ID (not index) of the default output device.
     pub fn default_output_device_id(&self) -> Option<DeviceID> {
         // Safe because `self.raw` is gauranteed to not be null.
         let res = unsafe { rtaudio_sys::rtaudio_get_default_output_device(self.raw) };
 
-        if res == 0 {
-            None
-        } else {
-            Some(DeviceID(res as u32))
-        }
+        DeviceID::new(res as u32)
     }
 
     /// Returns the device ID (not index) of the default input device.
@@ -215,57 +553,211 @@ impl Host {
         // Safe because `self.raw` is gauranteed to not be null.
         let res = unsafe { rtaudio_sys::rtaudio_get_default_input_device(self.raw) };
 
-        if res == 0 {
-            None
-        } else {
-            Some(DeviceID(res as u32))
-        }
+        DeviceID::new(res as u32)
     }
 
     /// Returns information about the default output device.
-    pub fn default_output_device(&self) -> Result<DeviceInfo, RtAudioError> {
+    pub fn default_output_device(&self) -> Result<DeviceInfo, Error> {
         if let Some(id) = self.default_output_device_id() {
             self.get_device_info_by_id(id)
         } else {
-            Err(RtAudioError {
-                type_: RtAudioErrorType::NoDevicesFound,
+            Err(Error::NoDevicesFound {
                 msg: Some("No default output device found".into()),
+                context: None,
+                origin: None,
             })
         }
     }
 
     /// Returns information about the default input device.
-    pub fn default_input_device(&self) -> Result<DeviceInfo, RtAudioError> {
+    pub fn default_input_device(&self) -> Result<DeviceInfo, Error> {
         if let Some(id) = self.default_input_device_id() {
             self.get_device_info_by_id(id)
         } else {
-            Err(RtAudioError {
-                type_: RtAudioErrorType::NoDevicesFound,
+            Err(Error::NoDevicesFound {
                 msg: Some("No default input device found".into()),
+                context: None,
+                origin: None,
             })
         }
     }
 
+    /// Returns information about both the default output device and the
+    /// default input device.
+    ///
+    /// Unlike `Host::default_output_device()` and
+    /// `Host::default_input_device()`, a missing default device is not an
+    /// error. If there was a problem retrieving a default device, then a
+    /// warning will be printed to the log and that device will be `None`.
+    pub fn default_devices(&self) -> DefaultDevices {
+        let output =
+            self.default_output_device_id()
+                .and_then(|id| match self.get_device_info_by_id(id) {
+                    Ok(d) => Some(d),
+                    Err(e) => {
+                        log::warn!("Failed to get default output device: {}", e);
+                        None
+                    }
+                });
+        let input =
+            self.default_input_device_id()
+                .and_then(|id| match self.get_device_info_by_id(id) {
+                    Ok(d) => Some(d),
+                    Err(e) => {
+                        log::warn!("Failed to get default input device: {}", e);
+                        None
+                    }
+                });
+
+        DefaultDevices { output, input }
+    }
+
+    /// Replace a `device_id` of `None` with the ID of the default device
+    /// for the given role.
+    pub(crate) fn resolve_device_params(
+        &self,
+        params: DeviceParams,
+        role: Role,
+    ) -> Result<DeviceParams, Error> {
+        if params.device_id.is_some() {
+            return Ok(params);
+        }
+
+        let device_id = match role {
+            Role::Output => self.default_output_device_id(),
+            Role::Input => self.default_input_device_id(),
+        };
+
+        if device_id.is_none() {
+            return Err(Error::NoDevicesFound {
+                msg: Some(format!("No default {} device found", role).into()),
+                context: None,
+                origin: None,
+            });
+        }
+
+        Ok(DeviceParams {
+            device_id,
+            ..params
+        })
+    }
+
+    /// Check whether the given pair of devices could plausibly be opened
+    /// together as a duplex stream.
+    ///
+    /// If `requested_rate` is `None`, then the preferred sample rate of the
+    /// output device is picked if both devices support it, followed by that
+    /// of the input device, followed by the highest common sample rate.
+    ///
+    /// Note that a result of `DuplexSupport::Supported` does not guarantee
+    /// that the stream will open successfully.
+    pub fn supports_duplex(
+        &self,
+        out: DeviceID,
+        inp: DeviceID,
+        requested_rate: Option<u32>,
+    ) -> DuplexSupport {
+        if out != inp && self.api().capabilities().duplex_requires_same_device {
+            return DuplexSupport::ApiRequiresSameDevice;
+        }
+
+        let out_info = match self.device_by_id(out) {
+            Ok(Some(d)) => d,
+            Ok(None) => return DuplexSupport::DeviceNotFound(out),
+            Err(e) => return DuplexSupport::Error(e),
+        };
+        let in_info = match self.device_by_id(inp) {
+            Ok(Some(d)) => d,
+            Ok(None) => return DuplexSupport::DeviceNotFound(inp),
+            Err(e) => return DuplexSupport::Error(e),
+        };
+
+        if out_info.max_output_channels() == 0 || in_info.max_input_channels() == 0 {
+            return DuplexSupport::MissingChannels {
+                output_channels: out_info.output_channels,
+                input_channels: in_info.input_channels,
+            };
+        }
+
+        let common = out_info.common_sample_rates(&in_info);
+
+        let sample_rate = if common.is_empty() {
+            // Neither device reports any sample rates.
+            if out_info.sample_rates.is_empty() && in_info.sample_rates.is_empty() {
+                requested_rate.or(Some(out_info.preferred_sample_rate))
+            } else {
+                None
+            }
+        } else if let Some(rate) = requested_rate {
+            common.contains(&rate).then_some(rate)
+        } else if common.contains(&out_info.preferred_sample_rate) {
+            Some(out_info.preferred_sample_rate)
+        } else if common.contains(&in_info.preferred_sample_rate) {
+            Some(in_info.preferred_sample_rate)
+        } else {
+            common.last().copied()
+        };
+
+        match sample_rate {
+            Some(sample_rate) => DuplexSupport::Supported { sample_rate },
+            None => DuplexSupport::NoCommonSampleRate {
+                out_rates: out_info.sample_rates,
+                in_rates: in_info.sample_rates,
+            },
+        }
+    }
+
+    /// Scan everything RtAudio knows about the system into a single
+    /// diagnostic report.
+    ///
+    /// Unlike `Host::iter_devices()`, devices which failed to scan are
+    /// included in the report along with the error that occurred.
+    pub fn probe_all(&self) -> ProbeReport {
+        ProbeReport {
+            version: crate::version(),
+            api: self.api(),
+            default_output_device_id: self.default_output_device_id(),
+            default_input_device_id: self.default_input_device_id(),
+            devices: self.iter_devices_complete().collect(),
+        }
+    }
+
+    /// Create a builder for opening a new audio stream.
+    ///
+    /// This is an alternative to `Host::open_stream()` which provides
+    /// additional options for how the stream is opened.
+    pub fn stream_builder(self) -> StreamBuilder {
+        StreamBuilder::new(self)
+    }
+
     /// Open a new audio stream.
     ///
     /// * `output_device` - The parameters for the output device to use. If you do
-    /// not wish to use an output device, set this to `None`.
+    ///   not wish to use an output device, set this to `None`.
     /// * `input_device` - The parameters for the input device to use. If you do not
-    /// wish to use an input device, set this to `None`.
+    ///   wish to use an input device, set this to `None`.
     /// * `sample_format` - The sample format to use. If the device doesn't natively
-    /// support the given format, then it will automatically be converted to/from
-    /// that format.
+    ///   support the given format, then it will automatically be converted to/from
+    ///   that format.
     /// * `sample_rate` - The sample rate to use. The stream may decide to use a
-    /// different sample rate if it's not supported.
+    ///   different sample rate if it's not supported.
     /// * `buffer_frames` - The desired maximum number of frames that can appear in a
-    /// single process call. The stream may decide to use a different value if it's
-    /// not supported. The given value should be a power of 2.
+    ///   single process call. The stream may decide to use a different value if it's
+    ///   not supported. The given value should be a power of 2.
     /// * `options` - Additional options for the stream.
     /// * `error_callback` - This will be called if there was an error that caused the
-    /// stream to close. If this happens, the returned `Stream` struct should be
-    /// manually closed or dropped.
+    ///   stream to close. If this happens, the returned `Stream` struct should be
+    ///   manually closed or dropped.
+    ///
+    /// The error callback is called from a separate notifier thread, never from
+    /// the audio thread. If it panics, then the panic is caught and logged, and
+    /// it does not affect closing the stream or opening another one.
+    ///
+    /// If the stream fails to open, then the `Host` is given back in the
+    /// returned `HostError`, so that it can be used to try again.
     ///
     /// Only one stream can be opened at a time (this is a limitation with RtAudio).
+    #[allow(clippy::too_many_arguments)]
     pub fn open_stream<E>(
         self,
         output_device: Option<DeviceParams>,
@@ -275,14 +767,125 @@ impl Host {
         buffer_frames: u32,
         options: StreamOptions,
         error_callback: E,
-    ) -> Result<StreamHandle, (Self, RtAudioError)>
+    ) -> Result<StreamHandle, HostError>
     where
-        E: FnOnce(RtAudioError) + Send + 'static,
+        E: FnOnce(Error) + Send + 'static,
     {
         StreamHandle::new(
             self,
             output_device,
             input_device,
+            StreamConfig {
+                sample_format,
+                sample_rate,
+                buffer_frames,
+                buffer_size_policy: BufferSizePolicy::default(),
+                rate_policy: RatePolicy::default(),
+                options,
+            },
+            error_callback,
+        )
+    }
+
+    /// The same as `Host::open_stream()`, except that the error is returned
+    /// as a `(Host, Error)` tuple like in previous versions.
+    #[deprecated(note = "use `Host::open_stream()`, which returns a `HostError`")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn open_stream_tuple<E>(
+        self,
+        output_device: Option<DeviceParams>,
+        input_device: Option<DeviceParams>,
+        sample_format: SampleFormat,
+        sample_rate: u32,
+        buffer_frames: u32,
+        options: StreamOptions,
+        error_callback: E,
+    ) -> Result<StreamHandle, (Self, Error)>
+    where
+        E: FnOnce(Error) + Send + 'static,
+    {
+        self.open_stream(
+            output_device,
+            input_device,
+            sample_format,
+            sample_rate,
+            buffer_frames,
+            options,
+            error_callback,
+        )
+        .map_err(HostError::into_parts)
+    }
+
+    /// Open a new output-only audio stream.
+    ///
+    /// This is the same as `Host::open_stream()` with no input device.
+    ///
+    /// * `device` - The ID of the output device to use. If this is `None`,
+    ///   then the default output device is used.
+    /// * `num_channels` - The number of output channels to use, starting
+    ///   from the first channel of the device.
+    ///
+    /// See `Host::open_stream()` for a description of the other arguments.
+    #[allow(clippy::too_many_arguments)]
+    pub fn open_output_stream<E>(
+        self,
+        device: Option<DeviceID>,
+        num_channels: u32,
+        sample_format: SampleFormat,
+        sample_rate: u32,
+        buffer_frames: u32,
+        options: StreamOptions,
+        error_callback: E,
+    ) -> Result<StreamHandle, HostError>
+    where
+        E: FnOnce(Error) + Send + 'static,
+    {
+        self.open_stream(
+            Some(DeviceParams {
+                device_id: device,
+                num_channels,
+                first_channel: 0,
+            }),
+            None,
+            sample_format,
+            sample_rate,
+            buffer_frames,
+            options,
+            error_callback,
+        )
+    }
+
+    /// Open a new input-only audio stream.
+    ///
+    /// This is the same as `Host::open_stream()` with no output device.
+    ///
+    /// * `device` - The ID of the input device to use. If this is `None`,
+    ///   then the default input device is used.
+    /// * `num_channels` - The number of input channels to use, starting
+    ///   from the first channel of the device.
+    ///
+    /// See `Host::open_stream()` for a description of the other arguments.
+    #[allow(clippy::too_many_arguments)]
+    pub fn open_input_stream<E>(
+        self,
+        device: Option<DeviceID>,
+        num_channels: u32,
+        sample_format: SampleFormat,
+        sample_rate: u32,
+        buffer_frames: u32,
+        options: StreamOptions,
+        error_callback: E,
+    ) -> Result<StreamHandle, HostError>
+    where
+        E: FnOnce(Error) + Send + 'static,
+    {
+        self.open_stream(
+            None,
+            Some(DeviceParams {
+                device_id: device,
+                num_channels,
+                first_channel: 0,
+            }),
             sample_format,
             sample_rate,
             buffer_frames,
@@ -290,11 +893,37 @@ impl Host {
             error_callback,
         )
     }
+
+    /// Open a new audio stream with the given configuration.
+    ///
+    /// This is the same as `Host::open_stream()`, except that the sample
+    /// format, sample rate, buffer size, and stream options are given as a
+    /// single `StreamConfig`.
+    ///
+    /// Only one stream can be opened at a time (this is a limitation with RtAudio).
+    pub fn open_stream_with_config<E>(
+        self,
+        output_device: Option<DeviceParams>,
+        input_device: Option<DeviceParams>,
+        config: &StreamConfig,
+        error_callback: E,
+    ) -> Result<StreamHandle, HostError>
+    where
+        E: FnOnce(Error) + Send + 'static,
+    {
+        StreamHandle::new(
+            self,
+            output_device,
+            input_device,
+            config.clone(),
+            error_callback,
+        )
+    }
 }
 
 impl Drop for Host {
     fn drop(&mut self) {
-        if !self.raw.is_null() {
+        if self.owned && !self.raw.is_null() {
             // Safe because we checked that the pointer is not null, and we
             // are guaranteed to be the only owner of this pointer.
             unsafe {
@@ -304,6 +933,81 @@ impl Drop for Host {
     }
 }
 
+/// Information about the default output and input devices.
+///
+/// This is returned by `Host::default_devices()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DefaultDevices {
+    /// The default output device, if there is one.
+    pub output: Option<DeviceInfo>,
+    /// The default input device, if there is one.
+    pub input: Option<DeviceInfo>,
+}
+
+impl DefaultDevices {
+    /// Get the `(output, input)` device parameters for these devices, ready
+    /// to be passed to `Host::open_stream()`.
+    ///
+    /// The number of channels is clamped to the number of channels on each
+    /// device. A side is `None` if there is no default device for it, or if
+    /// that device has no channels in that direction.
+    pub fn params(&self, channels: u32) -> (Option<DeviceParams>, Option<DeviceParams>) {
+        let output = self.output.as_ref().and_then(|d| {
+            let num_channels = channels.min(d.output_channels);
+            (num_channels > 0).then_some(DeviceParams {
+                device_id: Some(d.id),
+                num_channels,
+                first_channel: 0,
+            })
+        });
+        let input = self.input.as_ref().and_then(|d| {
+            let num_channels = channels.min(d.input_channels);
+            (num_channels > 0).then_some(DeviceParams {
+                device_id: Some(d.id),
+                num_channels,
+                first_channel: 0,
+            })
+        });
+
+        (output, input)
+    }
+}
+
+/// Whether a pair of devices can be used together as a duplex stream.
+///
+/// This is returned by `Host::supports_duplex()`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DuplexSupport {
+    /// The pair of devices is supported with the given sample rate.
+    Supported { sample_rate: u32 },
+    /// The devices have no sample rate in common (or they do not support the
+    /// requested sample rate).
+    NoCommonSampleRate {
+        out_rates: Vec<u32>,
+        in_rates: Vec<u32>,
+    },
+    /// The API requires that a duplex stream uses the same device for both
+    /// output and input (i.e. ASIO).
+    ApiRequiresSameDevice,
+    /// The output device has no output channels, or the input device has no
+    /// input channels.
+    MissingChannels {
+        output_channels: u32,
+        input_channels: u32,
+    },
+    /// The device with the given ID was not found.
+    DeviceNotFound(DeviceID),
+    /// An error occurred while retrieving the device info.
+    Error(Error),
+}
+
+impl DuplexSupport {
+    /// Returns `true` if this is `DuplexSupport::Supported`.
+    pub fn is_supported(&self) -> bool {
+        matches!(self, DuplexSupport::Supported { .. })
+    }
+}
+
 pub struct DeviceIter<'a> {
     index: usize,
     num_devices: usize,
@@ -312,14 +1016,74 @@ pub struct DeviceIter<'a> {
 }
 
 impl<'a> Iterator for DeviceIter<'a> {
-    type Item = Result<DeviceInfo, RtAudioError>;
+    type Item = Result<DeviceInfo, DeviceScanError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.index += 1;
-        if self.index > self.num_devices {
-            None
-        } else {
-            Some(self.instance.get_device_info_by_index(self.index - 1))
+        // Skip over any devices which are no longer present.
+        while self.index < self.num_devices {
+            self.index += 1;
+
+            match self.instance.scan_device(self.index - 1) {
+                Ok(Some(d)) => return Some(Ok(d)),
+                Ok(None) => {}
+                Err(e) => return Some(Err(e)),
+            }
         }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RtAudioErrorType;
+
+    #[test]
+    fn device_index_past_the_end_is_reported() {
+        let host = Host::new(Api::Unspecified).unwrap();
+        let index = host.num_devices();
+
+        let e = host.get_device_info_by_index(index).unwrap_err();
+        assert_eq!(e.kind(), RtAudioErrorType::InvalidParameter);
+        assert_eq!(
+            e.context(),
+            Some(&ErrorContext::GetDeviceInfo {
+                index: Some(index),
+                id: None,
+            })
+        );
+        assert!(matches!(host.device_by_index(index), Ok(None)));
+    }
+
+    #[test]
+    fn complete_iteration_stops_at_the_device_count() {
+        let host = Host::new(Api::Unspecified).unwrap();
+
+        assert!(host.iter_devices_complete().count() <= host.num_devices());
+    }
+
+    #[test]
+    fn device_cache_is_kept_until_invalidated() {
+        let mut host = Host::new(Api::Unspecified).unwrap();
+        let scanned = host.devices_cached().len();
+        assert_eq!(host.device_cache.as_ref().map(|c| c.len()), Some(scanned));
+
+        // A device which only exists in the cache is found without scanning.
+        let cached = DeviceInfo {
+            id: DeviceID::new(u32::MAX).unwrap(),
+            ..crate::device_info::test_device()
+        };
+        let mut devices = host.device_cache.take().unwrap().into_vec();
+        devices.push(cached.clone());
+        host.device_cache = Some(devices.into_boxed_slice());
+        assert_eq!(host.devices_cached().len(), scanned + 1);
+        assert_eq!(host.cached_device_by_id(cached.id), Some(&cached));
+
+        host.invalidate_device_cache();
+        assert!(host.device_cache.is_none());
+        assert_eq!(host.cached_device_by_id(cached.id), None);
+
+        assert_eq!(host.rescan_devices().len(), scanned);
     }
 }
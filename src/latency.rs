@@ -0,0 +1,169 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::{
+    Buffers, DeviceParams, Error, Host, HostError, SampleFormat, StreamConfig, StreamFlags,
+};
+
+/// How long to listen to the input before playing the impulse, in seconds.
+///
+/// This gives the devices time to settle, and it is used to measure the
+/// noise floor of the input.
+const SETTLE_SECS: f64 = 0.25;
+/// The amplitude of the impulse.
+const IMPULSE_AMPLITUDE: f32 = 0.5;
+/// The impulse is detected once the input rises this many times above the
+/// noise floor...
+const NOISE_FACTOR: f32 = 4.0;
+/// ...or above this absolute level, whichever is higher.
+const MIN_THRESHOLD: f32 = 0.05;
+
+const NOT_DETECTED: u64 = u64::MAX;
+
+/// The result of `Host::measure_roundtrip_latency()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoundtripLatency {
+    /// The measured round-trip latency in frames.
+    pub frames: u64,
+    /// The sample rate of the stream used for the measurement.
+    pub sample_rate: u32,
+}
+
+impl RoundtripLatency {
+    /// The measured round-trip latency in seconds.
+    pub fn as_secs_f64(&self) -> f64 {
+        self.frames as f64 / f64::from(self.sample_rate)
+    }
+}
+
+impl Host {
+    /// Measure the actual round-trip latency of a pair of devices by playing
+    /// an impulse on the output and detecting it in the input.
+    ///
+    /// This requires the output to be connected back into the input, either
+    /// with a physical loopback cable or acoustically (a speaker and a
+    /// microphone in a quiet room). The impulse is played on every output
+    /// channel at half of full scale, so turn down the volume of any
+    /// speakers first.
+    ///
+    /// This is a best-effort measurement tool, not a passive query. It opens
+    /// a stream, so it cannot be used while another stream is open. The
+    /// stream always uses interleaved `SampleFormat::Float32` buffers,
+    /// regardless of the format and flags in `config`. The measurement
+    /// includes any latency added by the hardware and the loopback itself.
+    ///
+    /// If the impulse is not detected within `timeout`, then an error is
+    /// returned. The `Host` is returned in all cases.
+    pub fn measure_roundtrip_latency(
+        self,
+        output: DeviceParams,
+        input: DeviceParams,
+        config: &StreamConfig,
+        timeout: Duration,
+    ) -> Result<(Host, RoundtripLatency), HostError> {
+        let mut config = config.clone();
+        config.sample_format = SampleFormat::Float32;
+        config.options.flags.remove(StreamFlags::NONINTERLEAVED);
+
+        let result = Arc::new(AtomicU64::new(NOT_DETECTED));
+
+        let mut stream = self.open_stream_with_config(Some(output), Some(input), &config, |e| {
+            log::error!("Error while measuring round-trip latency: {}", e)
+        })?;
+
+        let sample_rate = stream.info().sample_rate;
+
+        let cb_result = Arc::clone(&result);
+        let mut impulse_frame: Option<u64> = None;
+        let mut threshold = MIN_THRESHOLD;
+        let mut noise_peak = 0.0f32;
+
+        let start_res = stream.start(move |buffers, info, _status| {
+            let Buffers::Float32 { output, input } = buffers else {
+                return;
+            };
+
+            output.fill(0.0);
+
+            if cb_result.load(Ordering::Relaxed) != NOT_DETECTED
+                || info.out_channels == 0
+                || info.in_channels == 0
+            {
+                return;
+            }
+
+            let settle_frames = (f64::from(info.sample_rate) * SETTLE_SECS) as u64;
+            let frames = (output.len() / info.out_channels).min(input.len() / info.in_channels);
+
+            for f in 0..frames {
+                let frame = info.frame_counter + f as u64;
+
+                if impulse_frame.is_none() && frame >= settle_frames {
+                    output[f * info.out_channels..(f + 1) * info.out_channels]
+                        .fill(IMPULSE_AMPLITUDE);
+
+                    impulse_frame = Some(frame);
+                    threshold = (noise_peak * NOISE_FACTOR).max(MIN_THRESHOLD);
+                }
+
+                let peak = input[f * info.in_channels..(f + 1) * info.in_channels]
+                    .iter()
+                    .fold(0.0f32, |peak, s| peak.max(s.abs()));
+
+                match impulse_frame {
+                    None => noise_peak = noise_peak.max(peak),
+                    Some(impulse_frame) => {
+                        if peak > threshold {
+                            cb_result.store(frame - impulse_frame, Ordering::Relaxed);
+                            return;
+                        }
+                    }
+                }
+            }
+        });
+
+        if let Err(e) = start_res {
+            return Err(HostError {
+                host: stream.close(),
+                error: e,
+            });
+        }
+
+        let start = Instant::now();
+        let mut frames = NOT_DETECTED;
+        while start.elapsed() < timeout {
+            frames = result.load(Ordering::Relaxed);
+            if frames != NOT_DETECTED {
+                break;
+            }
+
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        let host = stream.close();
+
+        if frames == NOT_DETECTED {
+            return Err(HostError {
+                host,
+                error: Error::Unknown {
+                    code: rtaudio_sys::RTAUDIO_ERROR_UNKNOWN,
+                    msg: Some(format!(
+                        "The impulse was not detected in the input within {:?}. Make sure the output is connected to the input.",
+                        timeout
+                    ).into()),
+                    context: None,
+                    origin: None,
+                },
+            });
+        }
+
+        Ok((
+            host,
+            RoundtripLatency {
+                frames,
+                sample_rate,
+            },
+        ))
+    }
+}
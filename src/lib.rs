@@ -1,21 +1,89 @@
 use std::ffi::CStr;
 use std::os::raw::c_char;
 
+mod api_options;
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub mod bench_support;
 mod buffer;
+mod builder;
+mod capabilities;
+mod capture;
+mod channel_layout;
+#[cfg(feature = "compat")]
+pub mod compat;
+mod compiled;
 mod device_info;
 mod enums;
 mod error;
+mod error_channel;
+#[cfg(feature = "async")]
+mod events;
 mod host;
+mod latency;
+mod negotiation;
+mod open_stream_error;
 mod options;
+mod playback;
+mod probe;
+mod realtime;
+pub mod rt;
+#[cfg(feature = "serde")]
+mod serde_support;
+mod status_stats;
 mod stream;
+#[cfg(feature = "async")]
+mod stream_task;
+mod summary;
+mod timing;
+mod version;
+#[cfg(feature = "wav")]
+pub mod wav;
 
+pub use api_options::*;
 pub use buffer::*;
+pub use builder::*;
+pub use capabilities::*;
+pub use capture::*;
+pub use channel_layout::*;
+pub use compiled::*;
 pub use device_info::*;
 pub use enums::*;
 pub use error::*;
+pub use error_channel::*;
+#[cfg(feature = "async")]
+pub use events::*;
 pub use host::*;
+pub use latency::*;
+pub use negotiation::*;
+pub use open_stream_error::*;
 pub use options::*;
+pub use playback::*;
+pub use probe::*;
+pub use realtime::*;
+pub use status_stats::*;
 pub use stream::*;
+#[cfg(feature = "async")]
+pub use stream_task::*;
+pub use summary::*;
+pub use timing::*;
+pub use version::*;
+
+/// The exact version of `rtaudio-sys` which this crate is built against,
+/// for calling RtAudio functions which are not wrapped.
+///
+/// Use `Host::as_raw()` and `StreamHandle::as_raw()` to get the instance.
+///
+/// ```
+/// let host = rtaudio::Host::new(rtaudio::Api::Unspecified)?;
+///
+/// // Safe because the instance is valid for as long as `host` exists.
+/// let count = unsafe { rtaudio::sys::rtaudio_device_count(host.as_raw()) };
+/// assert_eq!(count as usize, host.num_devices());
+/// # Ok::<(), rtaudio::Error>(())
+/// ```
+#[cfg(feature = "sys")]
+pub use rtaudio_sys as sys;
 
 /// Get the current RtAudio version.
 pub fn version() -> String {
@@ -34,7 +102,17 @@ pub fn version() -> String {
     }
 }
 
+/// Get the current RtAudio version, parsed into its numbers.
+///
+/// See `VersionInfo::parse()`.
+pub fn version_info() -> VersionInfo {
+    VersionInfo::parse(&version())
+}
+
 /// Get the list of APIs compiled into this instance of RtAudio.
+///
+/// See `rtaudio::compiled_backends()` for the APIs which are known to be
+/// compiled in at compile time.
 pub fn compiled_apis() -> Vec<Api> {
     // Safe because this list is gauranteed to be the reported length, we
     // check for the null case, and we do not free the `raw_list` pointer.
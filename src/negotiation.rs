@@ -0,0 +1,198 @@
+use std::fmt;
+
+use crate::{BufferSizePolicy, RatePolicy, SampleFormat};
+
+/// A stream parameter along with the value that was requested and the value
+/// that is actually used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Negotiated<T> {
+    /// The value that was requested.
+    pub requested: T,
+    /// The value that is actually used.
+    pub actual: T,
+}
+
+impl<T: PartialEq> Negotiated<T> {
+    /// Whether or not the actual value differs from the requested value.
+    pub fn changed(&self) -> bool {
+        self.requested != self.actual
+    }
+}
+
+impl<T: fmt::Display + PartialEq> fmt::Display for Negotiated<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.changed() {
+            write!(f, "{} (requested {})", self.actual, self.requested)
+        } else {
+            write!(f, "{}", self.actual)
+        }
+    }
+}
+
+/// A record of the stream parameters that were requested when opening a
+/// stream versus the parameters that are actually used.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NegotiationReport {
+    /// The sample rate.
+    pub sample_rate: Negotiated<u32>,
+    /// The policy which was used to pick the sample rate.
+    pub rate_policy: RatePolicy,
+    /// The maximum number of frames in each process call.
+    ///
+    /// `requested` is the buffer size before it was adjusted by the
+    /// `BufferSizePolicy`.
+    pub buffer_frames: Negotiated<u32>,
+    /// The policy which was used to adjust the requested buffer size.
+    pub buffer_size_policy: BufferSizePolicy,
+    /// The number of buffers that was requested.
+    pub requested_num_buffers: u32,
+    /// Whether or not `requested_num_buffers` is the default for the API of
+    /// the stream (see `StreamOptions::default_num_buffers()`), because
+    /// `StreamOptions::num_buffers` was `None`.
+    pub num_buffers_from_api_default: bool,
+    /// The number of buffers that is actually used.
+    ///
+    /// This is `None` if the value is not reported. (RtAudio currently does
+    /// not report this value on any API.)
+    pub actual_num_buffers: Option<u32>,
+    /// The sample format of the stream.
+    pub sample_format: SampleFormat,
+    /// Whether or not RtAudio will convert the samples to/from a different
+    /// format because the output device does not natively support the
+    /// sample format.
+    ///
+    /// This is `None` if there is no output device or if the native formats
+    /// of the device could not be determined.
+    pub output_format_conversion: Option<bool>,
+    /// Whether or not RtAudio will convert the samples to/from a different
+    /// format because the input device does not natively support the
+    /// sample format.
+    ///
+    /// This is `None` if there is no input device or if the native formats
+    /// of the device could not be determined.
+    pub input_format_conversion: Option<bool>,
+}
+
+impl NegotiationReport {
+    /// Whether or not any parameter differs from what was requested, or
+    /// any format conversion will occur.
+    pub fn has_coercions(&self) -> bool {
+        self.sample_rate.changed()
+            || self.buffer_frames.changed()
+            || self
+                .actual_num_buffers
+                .map(|n| n != self.requested_num_buffers)
+                .unwrap_or(false)
+            || self.output_format_conversion == Some(true)
+            || self.input_format_conversion == Some(true)
+    }
+}
+
+impl fmt::Display for NegotiationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "sample rate: {}, buffer frames: {}, ",
+            self.sample_rate, self.buffer_frames
+        )?;
+
+        match self.actual_num_buffers {
+            Some(n) if n != self.requested_num_buffers => write!(
+                f,
+                "num buffers: {} (requested {})",
+                n, self.requested_num_buffers
+            )?,
+            Some(n) => write!(f, "num buffers: {}", n)?,
+            None => write!(
+                f,
+                "num buffers: unknown (requested {})",
+                self.requested_num_buffers
+            )?,
+        }
+        if self.num_buffers_from_api_default {
+            write!(f, " (API default)")?;
+        }
+
+        write!(f, ", ")?;
+
+        write!(f, "sample format: {:?}", self.sample_format)?;
+
+        if self.output_format_conversion == Some(true) {
+            write!(f, " (converted for the output device)")?;
+        }
+        if self.input_format_conversion == Some(true) {
+            write!(f, " (converted for the input device)")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> NegotiationReport {
+        NegotiationReport {
+            sample_rate: Negotiated {
+                requested: 48000,
+                actual: 48000,
+            },
+            rate_policy: RatePolicy::default(),
+            buffer_frames: Negotiated {
+                requested: 256,
+                actual: 256,
+            },
+            buffer_size_policy: BufferSizePolicy::default(),
+            requested_num_buffers: 4,
+            num_buffers_from_api_default: false,
+            actual_num_buffers: None,
+            sample_format: SampleFormat::Float32,
+            output_format_conversion: Some(false),
+            input_format_conversion: None,
+        }
+    }
+
+    #[test]
+    fn unchanged_parameters_are_not_coercions() {
+        let report = report();
+
+        assert!(!report.has_coercions());
+        assert_eq!(
+            report.to_string(),
+            "sample rate: 48000, buffer frames: 256, num buffers: unknown (requested 4), \
+             sample format: Float32"
+        );
+    }
+
+    #[test]
+    fn changed_parameters_are_coercions() {
+        let mut report = report();
+        report.sample_rate.actual = 44100;
+        report.buffer_frames.actual = 512;
+        report.actual_num_buffers = Some(2);
+
+        assert!(report.has_coercions());
+        assert_eq!(
+            report.to_string(),
+            "sample rate: 44100 (requested 48000), buffer frames: 512 (requested 256), \
+             num buffers: 2 (requested 4), sample format: Float32"
+        );
+    }
+
+    #[test]
+    fn format_conversion_is_a_coercion() {
+        let mut report = report();
+        report.input_format_conversion = Some(true);
+        report.num_buffers_from_api_default = true;
+        report.actual_num_buffers = Some(4);
+
+        assert!(report.has_coercions());
+        assert_eq!(
+            report.to_string(),
+            "sample rate: 48000, buffer frames: 256, num buffers: 4 (API default), \
+             sample format: Float32 (converted for the input device)"
+        );
+    }
+}
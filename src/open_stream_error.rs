@@ -0,0 +1,224 @@
+use std::fmt;
+
+use crate::{
+    DeviceID, DeviceParams, Error, ErrorContext, Host, HostError, OptionsError, ParamProblem, Role,
+    StreamConfig, StreamHandle, StreamOptions,
+};
+
+/// The reason that `Host::try_open_stream()` failed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OpenStreamError {
+    /// Neither an output device nor an input device was given.
+    NoDevices,
+    /// The device could not be found.
+    ///
+    /// `id` is `None` if no device ID was given and there is no default
+    /// device for that role.
+    DeviceNotFound { role: Role, id: Option<DeviceID> },
+    /// The device parameters are not valid for the device.
+    InvalidParams {
+        role: Role,
+        problems: Vec<ParamProblem>,
+    },
+    /// The device does not support the requested sample rate.
+    UnsupportedSampleRate {
+        role: Role,
+        sample_rate: u32,
+        supported: Vec<u32>,
+    },
+    /// The stream name is too long, or it contains a NUL character.
+    InvalidName { name: String },
+    /// The backend failed to open the stream.
+    Backend(Error),
+}
+
+impl std::error::Error for OpenStreamError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            OpenStreamError::Backend(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for OpenStreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OpenStreamError::NoDevices => {
+                write!(f, "RtAudio: no output or input device was given")
+            }
+            OpenStreamError::DeviceNotFound { role, id: Some(id) } => {
+                write!(f, "RtAudio: could not find {} device with ID {}", role, id)
+            }
+            OpenStreamError::DeviceNotFound { role, id: None } => {
+                write!(f, "RtAudio: no default {} device found", role)
+            }
+            OpenStreamError::InvalidParams { role, problems } => {
+                write!(f, "RtAudio: invalid {} device parameters: ", role)?;
+
+                for (i, problem) in problems.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", problem)?;
+                }
+
+                Ok(())
+            }
+            OpenStreamError::UnsupportedSampleRate {
+                role,
+                sample_rate,
+                supported,
+            } => write!(
+                f,
+                "RtAudio: sample rate {} is not supported by the {} device (supported sample rates: {:?})",
+                sample_rate, role, supported
+            ),
+            OpenStreamError::InvalidName { name } => write!(
+                f,
+                "RtAudio: stream name {:?} is longer than {} bytes or contains a NUL character",
+                name,
+                StreamOptions::MAX_NAME_LEN
+            ),
+            OpenStreamError::Backend(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<Error> for OpenStreamError {
+    fn from(e: Error) -> Self {
+        OpenStreamError::Backend(e)
+    }
+}
+
+impl From<OpenStreamError> for Error {
+    fn from(e: OpenStreamError) -> Self {
+        match e {
+            OpenStreamError::Backend(e) => e,
+            OpenStreamError::DeviceNotFound { id: None, .. } => Error::NoDevicesFound {
+                msg: Some(e.to_string().into()),
+                context: Some(ErrorContext::OpenStream),
+                origin: None,
+            },
+            OpenStreamError::DeviceNotFound { id: Some(_), .. } => Error::InvalidDevice {
+                msg: Some(e.to_string().into()),
+                context: Some(ErrorContext::OpenStream),
+                origin: None,
+            },
+            _ => Error::InvalidParameter {
+                msg: Some(e.to_string().into()),
+                context: Some(ErrorContext::OpenStream),
+                origin: None,
+            },
+        }
+    }
+}
+
+impl Host {
+    /// Open a new audio stream, checking the arguments beforehand.
+    ///
+    /// This is the same as `Host::open_stream_with_config()`, except that
+    /// preventable problems (no devices given, an unknown device, a channel
+    /// range the device does not have, a sample rate the device does not
+    /// support, or an invalid stream name) are returned as a
+    /// specific `OpenStreamError` instead of a generic `Error`.
+    ///
+    /// Devices which do not report any sample rates are not checked for
+    /// sample rate support.
+    ///
+    /// Only one stream can be opened at a time (this is a limitation with RtAudio).
+    pub fn try_open_stream<E>(
+        self,
+        output_device: Option<DeviceParams>,
+        input_device: Option<DeviceParams>,
+        config: &StreamConfig,
+        error_callback: E,
+    ) -> Result<StreamHandle, HostError<OpenStreamError>>
+    where
+        E: FnOnce(Error) + Send + 'static,
+    {
+        if let Err(e) = self.check_open_stream_args(output_device, input_device, config) {
+            return Err(HostError {
+                host: self,
+                error: e,
+            });
+        }
+
+        self.open_stream_with_config(output_device, input_device, config, error_callback)
+            .map_err(|HostError { host, error }| HostError {
+                host,
+                error: OpenStreamError::Backend(error),
+            })
+    }
+
+    fn check_open_stream_args(
+        &self,
+        output_device: Option<DeviceParams>,
+        input_device: Option<DeviceParams>,
+        config: &StreamConfig,
+    ) -> Result<(), OpenStreamError> {
+        if output_device.is_none() && input_device.is_none() {
+            return Err(OpenStreamError::NoDevices);
+        }
+
+        if let Err(OptionsError::InvalidName(_)) = config.options.validate() {
+            return Err(OpenStreamError::InvalidName {
+                name: config.options.name.clone(),
+            });
+        }
+
+        let mut devices = [None, None];
+
+        for ((params, role), device_slot) in
+            [(output_device, Role::Output), (input_device, Role::Input)]
+                .into_iter()
+                .zip(&mut devices)
+        {
+            let Some(params) = params else {
+                continue;
+            };
+
+            let params = self
+                .resolve_device_params(params, role)
+                .map_err(|_| OpenStreamError::DeviceNotFound { role, id: None })?;
+            let id = params
+                .device_id
+                .ok_or(OpenStreamError::DeviceNotFound { role, id: None })?;
+
+            let device = self
+                .device_by_id(id)?
+                .ok_or(OpenStreamError::DeviceNotFound { role, id: Some(id) })?;
+
+            if let Err(mut problems) = params.validate(&device, role) {
+                if config.options.skip_channel_validation {
+                    problems.retain(|p| !p.is_channel_range());
+                }
+
+                if !problems.is_empty() {
+                    return Err(OpenStreamError::InvalidParams { role, problems });
+                }
+            }
+
+            *device_slot = Some(device);
+        }
+
+        // The other policies pick a sample rate which the devices support.
+        if config.rate_policy.uses_requested_rate() {
+            let [output, input] = &devices;
+
+            if let Some((device, role)) = crate::options::unsupported_rate_device(
+                config.sample_rate,
+                output.as_ref(),
+                input.as_ref(),
+            ) {
+                return Err(OpenStreamError::UnsupportedSampleRate {
+                    role,
+                    sample_rate: config.sample_rate,
+                    supported: device.sample_rates.clone(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
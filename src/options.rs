@@ -1,16 +1,25 @@
 use rtaudio_sys::MAX_NAME_LENGTH;
 use std::ffi::CString;
+use std::fmt;
 use std::os::raw::{c_char, c_int, c_uint};
+use std::time::Duration;
 
-use crate::error::{RtAudioError, RtAudioErrorType};
-use crate::{DeviceID, StreamFlags};
+use crate::error::{Error, ErrorContext};
+use crate::{Api, ApiOptions, DeviceID, DeviceInfo, Role, SampleFormat, StreamFlags};
 
 /// Used for specifying the parameters of a device when opening a
 /// stream.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct DeviceParams {
     /// The ID (not index) of the device to use.
-    pub device_id: DeviceID,
+    ///
+    /// If this is `None`, then the default output or input device is used
+    /// (depending on which side of the stream these parameters are for).
+    ///
+    /// The default value is `None`.
+    pub device_id: Option<DeviceID>,
     /// The number of channels in the device to use.
     pub num_channels: u32,
     /// The first channel index on the device (default = 0) to use.
@@ -20,7 +29,7 @@ pub struct DeviceParams {
 impl Default for DeviceParams {
     fn default() -> Self {
         Self {
-            device_id: DeviceID(0),
+            device_id: None,
             num_channels: 2,
             first_channel: 0,
         }
@@ -28,17 +37,262 @@ impl Default for DeviceParams {
 }
 
 impl DeviceParams {
+    /// Parameters for the output of the given device, using up to the first
+    /// two output channels of the device.
+    ///
+    /// Returns `None` if the device has no output channels.
+    ///
+    /// ```
+    /// use rtaudio::{Api, DeviceParams, Host};
+    ///
+    /// let host = Host::new(Api::Unspecified).unwrap();
+    ///
+    /// if let Ok(device) = host.default_output_device() {
+    ///     let params = DeviceParams::output_from(&device);
+    ///     println!("{:?}", params);
+    /// }
+    /// ```
+    pub fn output_from(device: &DeviceInfo) -> Option<Self> {
+        Self::from_device(device, Role::Output)
+    }
+
+    /// Parameters for the input of the given device, using up to the first
+    /// two input channels of the device.
+    ///
+    /// Returns `None` if the device has no input channels.
+    pub fn input_from(device: &DeviceInfo) -> Option<Self> {
+        Self::from_device(device, Role::Input)
+    }
+
+    /// Parameters for the given side of the given device, using up to the
+    /// first two channels of the device.
+    ///
+    /// Returns `None` if the device has no channels for the given role.
+    pub fn from_device(device: &DeviceInfo, role: Role) -> Option<Self> {
+        let available = available_channels(device, role);
+        if available == 0 {
+            return None;
+        }
+
+        Some(Self {
+            device_id: Some(device.id),
+            num_channels: available.min(2),
+            first_channel: 0,
+        })
+    }
+
+    /// Use the given number of channels.
+    ///
+    /// Use `DeviceParams::clamp_to()` or `DeviceParams::validate()` to make
+    /// sure the device has these channels.
+    pub fn with_channels(mut self, num_channels: u32) -> Self {
+        self.num_channels = num_channels;
+        self
+    }
+
+    /// Start at the given channel of the device.
+    ///
+    /// Use `DeviceParams::clamp_to()` or `DeviceParams::validate()` to make
+    /// sure the device has these channels.
+    pub fn with_first_channel(mut self, first_channel: u32) -> Self {
+        self.first_channel = first_channel;
+        self
+    }
+
+    /// Adjust the channels so that they fit in the channels of the given
+    /// device, keeping at least one channel.
+    ///
+    /// The first channel is moved back to the last channel of the device if
+    /// it is past it, and then the number of channels is reduced until the
+    /// range fits. The device ID is not changed.
+    ///
+    /// Returns `None` if the device has no channels for the given role.
+    pub fn clamp_to(mut self, device: &DeviceInfo, role: Role) -> Option<Self> {
+        let available = available_channels(device, role);
+        if available == 0 {
+            return None;
+        }
+
+        self.first_channel = self.first_channel.min(available - 1);
+        self.num_channels = self.num_channels.clamp(1, available - self.first_channel);
+
+        Some(self)
+    }
+
+    /// Check these parameters against the given device, returning every
+    /// problem that was found instead of stopping at the first one.
+    ///
+    /// This is useful for settings UIs that want to show all problems at
+    /// once. Note that passing this check does not guarantee that a stream
+    /// will open successfully.
+    ///
+    /// ```
+    /// use rtaudio::{Api, DeviceParams, Host, Role};
+    ///
+    /// let host = Host::new(Api::Unspecified).unwrap();
+    ///
+    /// if let Ok(device) = host.default_output_device() {
+    ///     let params = DeviceParams::output_from(&device).unwrap().with_channels(8);
+    ///
+    ///     if let Err(problems) = params.validate(&device, Role::Output) {
+    ///         for problem in problems {
+    ///             println!("{}", problem);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn validate(&self, device: &DeviceInfo, role: Role) -> Result<(), Vec<ParamProblem>> {
+        let mut problems = Vec::new();
+
+        if let Some(params_id) = self.device_id {
+            if params_id != device.id {
+                problems.push(ParamProblem::DeviceIdMismatch {
+                    params_id,
+                    device_id: device.id,
+                });
+            }
+        }
+
+        let available = available_channels(device, role);
+
+        if self.num_channels == 0 {
+            problems.push(ParamProblem::ZeroChannels);
+        }
+
+        if available == 0 {
+            problems.push(ParamProblem::RoleNotSupported { role });
+        } else if self.first_channel >= available {
+            problems.push(ParamProblem::FirstChannelTooHigh {
+                first_channel: self.first_channel,
+                available,
+            });
+        } else if self
+            .first_channel
+            .checked_add(self.num_channels)
+            .map(|end| end > available)
+            .unwrap_or(true)
+        {
+            problems.push(ParamProblem::ChannelRangeOutOfBounds {
+                first_channel: self.first_channel,
+                num_channels: self.num_channels,
+                available,
+            });
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
+
+    /// The raw C struct of these parameters.
+    ///
+    /// The raw struct has no way to say "the default device", so a
+    /// `device_id` of `None` is converted to `0`, which is RtAudio's ID for
+    /// "no device" (not the backend's default device). Opening a stream with
+    /// it fails with `Error::InvalidParameter`. `Host::open_stream()` and the
+    /// related methods look up the default device before converting the
+    /// parameters, so this only matters when calling RtAudio directly.
     pub fn to_raw(&self) -> rtaudio_sys::rtaudio_stream_parameters_t {
+        let device_id = match self.device_id {
+            Some(id) => id.get(),
+            None => 0,
+        };
+
         rtaudio_sys::rtaudio_stream_parameters_t {
-            device_id: self.device_id.0 as c_uint,
+            device_id: device_id as c_uint,
             num_channels: self.num_channels as c_uint,
             first_channel: self.first_channel as c_uint,
         }
     }
 }
 
+/// The number of channels the device has for the given role.
+fn available_channels(device: &DeviceInfo, role: Role) -> u32 {
+    match role {
+        Role::Output => device.max_output_channels(),
+        Role::Input => device.max_input_channels(),
+    }
+}
+
+/// A problem found by `DeviceParams::validate()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamProblem {
+    /// The ID in the parameters does not match the ID of the device.
+    DeviceIdMismatch {
+        params_id: DeviceID,
+        device_id: DeviceID,
+    },
+    /// The number of channels is zero.
+    ZeroChannels,
+    /// The device has no channels for the given role.
+    RoleNotSupported { role: Role },
+    /// The first channel is past the last channel of the device.
+    FirstChannelTooHigh { first_channel: u32, available: u32 },
+    /// The range of channels extends past the last channel of the device.
+    ChannelRangeOutOfBounds {
+        first_channel: u32,
+        num_channels: u32,
+        available: u32,
+    },
+}
+
+impl ParamProblem {
+    /// Whether this is a problem with the range of channels, which is not
+    /// checked if `StreamOptions::skip_channel_validation` is set.
+    pub(crate) fn is_channel_range(&self) -> bool {
+        matches!(
+            self,
+            ParamProblem::RoleNotSupported { .. }
+                | ParamProblem::FirstChannelTooHigh { .. }
+                | ParamProblem::ChannelRangeOutOfBounds { .. }
+        )
+    }
+}
+
+impl fmt::Display for ParamProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParamProblem::DeviceIdMismatch {
+                params_id,
+                device_id,
+            } => write!(
+                f,
+                "device ID {} does not match the device (ID {})",
+                params_id, device_id
+            ),
+            ParamProblem::ZeroChannels => f.write_str("the number of channels is zero"),
+            ParamProblem::RoleNotSupported { role } => {
+                write!(f, "the device has no {} channels", role)
+            }
+            ParamProblem::FirstChannelTooHigh {
+                first_channel,
+                available,
+            } => write!(
+                f,
+                "first channel {} is out of range (the device has {} channels)",
+                first_channel, available
+            ),
+            ParamProblem::ChannelRangeOutOfBounds {
+                first_channel,
+                num_channels,
+                available,
+            } => write!(
+                f,
+                "channels {}..{} are out of range (the device has {} channels)",
+                first_channel,
+                u64::from(*first_channel) + u64::from(*num_channels),
+                available
+            ),
+        }
+    }
+}
+
 /// Additional options for opening a stream.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct StreamOptions {
     /// The bit flag parameters for this stream.
     ///
@@ -49,49 +303,883 @@ pub struct StreamOptions {
     /// A value of two is usually the smallest allowed. Larger numbers can potentially result in more
     /// robust stream performance, though likely at the cost of stream latency.
     ///
-    /// The actual value used when the stream is ran may be different.
+    /// The actual value used when the stream is ran may be different. If
+    /// this is `Some(0)`, then the backend picks the number of buffers.
     ///
-    /// The default value is `4`.
-    pub num_buffers: u32,
+    /// The default value is `None`, which uses the default for the API of
+    /// the stream (see `StreamOptions::default_num_buffers()`).
+    pub num_buffers: Option<u32>,
 
-    /// Scheduling priority of callback thread (only used with flag `StreamFlags::SCHEDULE_REALTIME`).
+    /// Scheduling priority of the callback thread.
     ///
-    /// Use a value of `-1` for the default priority.
+    /// See `CallbackPriority` for how each priority is mapped to the
+    /// backend.
     ///
-    /// The default value is `-1`.
-    pub priority: i32,
+    /// The default value is `CallbackPriority::Default`.
+    pub priority: CallbackPriority,
 
     /// The name of the stream (currently used only in Jack).
     ///
-    /// The size of the name cannot exceed 511 bytes.
+    /// The size of the name cannot exceed 511 bytes, and it cannot contain a
+    /// NUL character. Use `StreamOptions::set_name()` to check the name
+    /// when it is set instead of when the stream is opened, or
+    /// `StreamOptions::set_name_lossy()` to have a name that is too long be
+    /// truncated instead.
     pub name: String,
+
+    /// Protection against sending out-of-range samples to the output device
+    /// (i.e. because of a bug in DSP code).
+    ///
+    /// The default value is `OutputLimiter::Off`.
+    pub output_limiter: OutputLimiter,
+
+    /// Detection of an output that has gone silent (i.e. because of a bug in
+    /// DSP code or a disconnected routing graph).
+    ///
+    /// The default value is `None` (disabled).
+    pub silence_watchdog: Option<SilenceWatchdog>,
+
+    /// Measurement of how long the data callback takes to run, flagging
+    /// process calls which come close to missing their deadline.
+    ///
+    /// The default value is `None` (disabled).
+    pub callback_budget: Option<CallbackBudget>,
+
+    /// Whether warnings which RtAudio reports while the stream is running
+    /// (i.e. recovered underruns) are sent to the warning handler of the
+    /// `Host` as soon as they arrive.
+    ///
+    /// The warnings are delivered from the same notifier thread as the
+    /// error callback, so the warning handler must not block for long. They
+    /// are still queued on the audio thread without locking or allocating,
+    /// so if they arrive faster than they can be delivered, then the newest
+    /// warnings are dropped (see `StreamHandle::dropped_warnings()`).
+    /// `StreamHandle::take_warnings()` always returns an empty list when
+    /// this is enabled.
+    ///
+    /// Warnings are only reported while `Host::show_warnings()` is enabled.
+    ///
+    /// The default value is `false`, in which case the warnings are queued
+    /// until they are taken with `StreamHandle::take_warnings()` or the
+    /// stream is stopped.
+    pub deliver_warnings: bool,
+
+    /// Whether to skip checking that the requested channels exist on each
+    /// device before the stream is opened.
+    ///
+    /// By default, opening a stream fails with `Error::InvalidParameter` if
+    /// `first_channel + num_channels` is larger than the number of channels
+    /// the device reports. Set this to `true` for devices which misreport
+    /// their number of channels, in which case the request is passed on to
+    /// the backend as-is.
+    ///
+    /// The default value is `false`.
+    pub skip_channel_validation: bool,
+
+    /// Options which only apply to a specific API.
+    ///
+    /// Only the options for the API of the `Host` are used, and any flags
+    /// they set are added to `StreamOptions::flags`.
+    ///
+    /// The default value sets no options for any API.
+    pub api_specific: ApiOptions,
+}
+
+/// Protection against sending out-of-range samples to the output device.
+///
+/// When enabled, the limiter is applied to the output buffer after the data
+/// callback is called. This is realtime-safe.
+///
+/// Only floating point sample formats are affected, since samples in integer
+/// formats can never exceed full scale. In either mode, NaN and infinite
+/// samples are replaced with silence.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OutputLimiter {
+    /// Samples are sent to the output device as-is.
+    #[default]
+    Off,
+    /// Samples are hard clipped to the range `[-1.0, 1.0]`.
+    Clip,
+    /// Samples above a threshold of `0.9` are smoothly compressed so that
+    /// they never exceed the range `[-1.0, 1.0]`.
+    SoftKnee,
+}
+
+/// The scheduling priority of the callback thread.
+///
+/// The valid priorities differ between platforms, so apart from
+/// `CallbackPriority::Raw`, each priority is mapped to a value which suits
+/// the API of the stream when it is opened (see `CallbackPriority::to_raw()`):
+///
+/// | Priority   | ALSA, PulseAudio, OSS         | WASAPI                 | Other APIs  |
+/// |------------|-------------------------------|------------------------|-------------|
+/// | `Default`  | not changed                   | not changed            | not changed |
+/// | `High`     | `SCHED_RR` with priority `50` | MMCSS "Pro Audio" task | not changed |
+/// | `Realtime` | `SCHED_RR` with priority `80` | MMCSS "Pro Audio" task | not changed |
+///
+/// A warning is printed to the log when `High` or `Realtime` is requested
+/// on an API which does not support realtime scheduling (see
+/// `ApiCapabilities::supports_realtime_priority`). On Linux, realtime
+/// scheduling also requires permission from the system (i.e. an `rtprio`
+/// limit), otherwise RtAudio falls back to normal scheduling. Use
+/// `StreamHandle::realtime_status()` to check which scheduling the callback
+/// thread actually got.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CallbackPriority {
+    /// The default scheduling of the backend.
+    #[default]
+    Default,
+    /// Realtime scheduling with a moderate priority, leaving room for other
+    /// audio software (i.e. a JACK server) to run at a higher priority.
+    High,
+    /// Realtime scheduling with a high priority.
+    Realtime,
+    /// Pass this value on to the backend as-is. The
+    /// `StreamFlags::SCHEDULE_REALTIME` flag must also be set for it to be
+    /// used.
+    ///
+    /// `-1` is the default priority. The APIs which support realtime
+    /// scheduling clamp any other value to the range of `SCHED_RR`
+    /// priorities of the system (`1..=99` on Linux).
+    Raw(i32),
+}
+
+impl CallbackPriority {
+    /// The priority which `CallbackPriority::High` is mapped to.
+    pub const HIGH: i32 = 50;
+    /// The priority which `CallbackPriority::Realtime` is mapped to.
+    pub const REALTIME: i32 = 80;
+
+    /// The stream flags and the raw priority which this priority is mapped
+    /// to on the given API.
+    ///
+    /// The flags are added to `StreamOptions::flags`.
+    ///
+    /// ```
+    /// use rtaudio::{Api, CallbackPriority, StreamFlags};
+    ///
+    /// let realtime = (StreamFlags::SCHEDULE_REALTIME, CallbackPriority::REALTIME);
+    /// let unchanged = (StreamFlags::empty(), -1);
+    ///
+    /// assert_eq!(CallbackPriority::Realtime.to_raw(Api::LinuxALSA), realtime);
+    /// assert_eq!(CallbackPriority::Realtime.to_raw(Api::WindowsASIO), unchanged);
+    /// assert_eq!(CallbackPriority::Default.to_raw(Api::LinuxALSA), unchanged);
+    /// assert_eq!(
+    ///     CallbackPriority::Raw(20).to_raw(Api::WindowsASIO),
+    ///     (StreamFlags::empty(), 20)
+    /// );
+    /// ```
+    pub fn to_raw(&self, api: Api) -> (StreamFlags, i32) {
+        let priority = match self {
+            CallbackPriority::Default => return (StreamFlags::empty(), -1),
+            CallbackPriority::Raw(priority) => return (StreamFlags::empty(), *priority),
+            CallbackPriority::High => Self::HIGH,
+            CallbackPriority::Realtime => Self::REALTIME,
+        };
+
+        if api.capabilities().supports_realtime_priority {
+            (StreamFlags::SCHEDULE_REALTIME, priority)
+        } else {
+            (StreamFlags::empty(), -1)
+        }
+    }
+}
+
+/// Detection of an output that is producing continuous silence.
+///
+/// When enabled, every output buffer is checked after the data callback is
+/// called. Once `threshold_blocks` consecutive buffers contain only
+/// silence (every sample is exactly zero), a silence event is counted. See
+/// `StreamHandle::silence_events()` and `StreamHandle::is_output_silent()`,
+/// which are also available on `StreamStateObserver` for monitoring from
+/// another thread.
+/// The watchdog is re-armed as soon as a buffer with a non-zero sample is
+/// output.
+///
+/// The check is a single pass over the output buffer, and it is
+/// realtime-safe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SilenceWatchdog {
+    /// The number of consecutive silent output buffers before a silence
+    /// event is counted. A value of `0` is treated as `1`.
+    pub threshold_blocks: u32,
+}
+
+impl SilenceWatchdog {
+    /// Create a watchdog which counts a silence event after roughly
+    /// `duration` of continuous silence, given the sample rate and buffer
+    /// size of the stream.
+    pub fn from_duration(
+        duration: std::time::Duration,
+        sample_rate: u32,
+        buffer_frames: u32,
+    ) -> Self {
+        let blocks = (duration.as_secs_f64() * f64::from(sample_rate)
+            / f64::from(buffer_frames.max(1)))
+        .ceil();
+
+        Self {
+            threshold_blocks: (blocks as u32).max(1),
+        }
+    }
+}
+
+/// Measurement of how long the data callback takes to run.
+///
+/// When enabled, each call to the data callback is timed with a monotonic
+/// clock (`std::time::Instant`). The time budget of a process call is the
+/// duration of the audio it processes (`frames / sample_rate`). If the data
+/// callback takes longer than `budget_fraction` of that budget, an overrun
+/// is counted and recorded. See `StreamHandle::callback_timing()` (which is
+/// also available on `StreamStateObserver`) and
+/// `StreamHandle::take_callback_overruns()`.
+///
+/// This adds two clock reads to every process call, and it is
+/// realtime-safe.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct CallbackBudget {
+    /// The fraction of the time budget of a process call that the data
+    /// callback may take before an overrun is counted (i.e. `0.8` for 80%).
+    ///
+    /// The default value is `0.8`.
+    pub budget_fraction: f32,
+}
+
+impl Default for CallbackBudget {
+    fn default() -> Self {
+        Self {
+            budget_fraction: 0.8,
+        }
+    }
 }
 
 impl StreamOptions {
-    pub fn to_raw(&self) -> Result<rtaudio_sys::rtaudio_stream_options_t, RtAudioError> {
-        let name = str_to_c_array::<{ MAX_NAME_LENGTH }>(&self.name).map_err(|_| RtAudioError {
-            type_: RtAudioErrorType::InvalidParamter,
-            msg: Some("Stream name is invalid".into()),
+    /// The maximum length of the stream name in bytes.
+    pub const MAX_NAME_LEN: usize = MAX_NAME_LENGTH - 1;
+
+    /// The default options for the given API.
+    ///
+    /// This is the same as `StreamOptions::default()`, except that
+    /// `num_buffers` is `Some(StreamOptions::default_num_buffers(api))`.
+    pub fn default_for(api: Api) -> Self {
+        Self {
+            num_buffers: Some(Self::default_num_buffers(api)),
+            ..Default::default()
+        }
+    }
+
+    /// The default `num_buffers` for the given API.
+    ///
+    /// | API                  | Default | Notes                               |
+    /// |----------------------|---------|-------------------------------------|
+    /// | `Api::LinuxALSA`     | `3`     | The number of periods               |
+    /// | `Api::LinuxOSS`      | `3`     | The number of fragments             |
+    /// | `Api::LinuxPulse`    | `4`     | Sets the size of the server buffer  |
+    /// | `Api::WindowsDS`     | `4`     | DirectSound needs the extra buffers |
+    /// | Any other API        | `4`     | Ignored by the API                  |
+    ///
+    /// The APIs which ignore `num_buffers` keep the blanket default of `4`
+    /// (see `ApiCapabilities::per_stream_buffers`).
+    ///
+    /// ```
+    /// use rtaudio::{Api, StreamOptions};
+    ///
+    /// assert_eq!(StreamOptions::default_num_buffers(Api::LinuxALSA), 3);
+    /// assert_eq!(StreamOptions::default_num_buffers(Api::LinuxOSS), 3);
+    /// assert_eq!(StreamOptions::default_num_buffers(Api::LinuxPulse), 4);
+    /// assert_eq!(StreamOptions::default_num_buffers(Api::WindowsDS), 4);
+    ///
+    /// // The APIs which ignore the value keep the blanket default.
+    /// for api in [Api::MacOSXCore, Api::UnixJack, Api::WindowsASIO, Api::WindowsWASAPI] {
+    ///     assert!(!api.capabilities().per_stream_buffers);
+    ///     assert_eq!(StreamOptions::default_num_buffers(api), 4);
+    /// }
+    /// ```
+    pub fn default_num_buffers(api: Api) -> u32 {
+        match api {
+            Api::LinuxALSA | Api::LinuxOSS => 3,
+            Api::LinuxPulse | Api::WindowsDS => 4,
+            _ => DEFAULT_NUM_BUFFERS,
+        }
+    }
+
+    /// The number of buffers which is requested on the given API, which is
+    /// `num_buffers` or the default for the API if it is `None`.
+    pub(crate) fn num_buffers_for(&self, api: Api) -> u32 {
+        self.num_buffers
+            .unwrap_or_else(|| Self::default_num_buffers(api))
+    }
+
+    /// Options for the lowest possible latency.
+    ///
+    /// This sets the `StreamFlags::MINIMIZE_LATENCY` flag, the smallest
+    /// usual `num_buffers` of `2`, and a `priority` of
+    /// `CallbackPriority::Realtime`. The other options are left at their
+    /// default values.
+    ///
+    /// This trades robustness for latency, so underruns are more likely
+    /// under load. Realtime scheduling is only honored by some APIs (see
+    /// `ApiCapabilities::supports_realtime_priority`), and a warning is
+    /// printed to the log when the stream is opened on an API which
+    /// ignores it.
+    pub fn low_latency() -> Self {
+        Self {
+            flags: StreamFlags::MINIMIZE_LATENCY,
+            num_buffers: Some(2),
+            priority: CallbackPriority::Realtime,
+            ..Default::default()
+        }
+    }
+
+    /// Options for exclusive use of the device, so that the audio is not
+    /// mixed or resampled by the system (i.e. for bit-perfect output).
+    ///
+    /// This sets the `StreamFlags::HOG_DEVICE` flag and a `num_buffers` of
+    /// `2`. The other options are left at their default values.
+    ///
+    /// Exclusive use is only honored by some APIs (see
+    /// `ApiCapabilities::supports_exclusive` and `Api::unsupported_flags()`),
+    /// and a warning is printed to the log when the stream is opened on an
+    /// API which ignores it.
+    pub fn exclusive() -> Self {
+        Self {
+            flags: StreamFlags::HOG_DEVICE,
+            num_buffers: Some(2),
+            ..Default::default()
+        }
+    }
+
+    /// Check these options, returning the first problem that was found.
+    ///
+    /// This is the same check that is done when the stream is opened. Only
+    /// the name can be invalid (see `StreamOptions::set_name()`); the other
+    /// options are checked against the API with
+    /// `StreamOptions::validate_for()`.
+    pub fn validate(&self) -> Result<(), OptionsError> {
+        check_name(&self.name)?;
+
+        Ok(())
+    }
+
+    /// Set the name of the stream.
+    ///
+    /// An error is returned (and the name is left unchanged) if the name is
+    /// longer than `StreamOptions::MAX_NAME_LEN` bytes or if it contains a
+    /// NUL character.
+    ///
+    /// ```
+    /// use rtaudio::{NameError, StreamOptions};
+    ///
+    /// let mut options = StreamOptions::default();
+    /// assert_eq!(options.set_name("My App"), Ok(()));
+    /// assert_eq!(options.name, "My App");
+    ///
+    /// assert_eq!(options.set_name("My\0App"), Err(NameError::ContainsNul { pos: 2 }));
+    /// assert_eq!(
+    ///     options.set_name(&"a".repeat(600)),
+    ///     Err(NameError::TooLong { len: 600, max: 511 })
+    /// );
+    /// assert_eq!(options.name, "My App");
+    ///
+    /// // A name of the maximum length fits in the C array, including the
+    /// // terminating NUL.
+    /// let max_name = "a".repeat(StreamOptions::MAX_NAME_LEN);
+    /// assert_eq!(options.set_name(&max_name), Ok(()));
+    /// let raw = options.to_raw().unwrap();
+    /// let raw_name = unsafe { std::ffi::CStr::from_ptr(raw.name.as_ptr()) };
+    /// assert_eq!(raw_name.to_str().unwrap(), max_name);
+    /// ```
+    pub fn set_name(&mut self, name: &str) -> Result<(), NameError> {
+        check_name(name)?;
+        self.name = String::from(name);
+        Ok(())
+    }
+
+    /// Set the name of the stream, removing any NUL characters and
+    /// truncating it if it is too long.
+    ///
+    /// If the name is longer than `StreamOptions::MAX_NAME_LEN` bytes once
+    /// the NUL characters are removed, it is truncated on a UTF-8
+    /// character boundary so that it fits.
+    ///
+    /// Returns `true` if the name was changed.
+    ///
+    /// ```
+    /// use rtaudio::StreamOptions;
+    ///
+    /// let mut options = StreamOptions::default();
+    /// assert!(options.set_name_lossy("My\0App"));
+    /// assert_eq!(options.name, "MyApp");
+    ///
+    /// // "é" is two bytes long, so the 511 byte limit falls in the middle
+    /// // of the last character. It is dropped instead of being split.
+    /// let name = format!("{}é", "a".repeat(510));
+    /// assert!(options.set_name_lossy(&name));
+    /// assert_eq!(options.name, "a".repeat(510));
+    ///
+    /// let name = format!("{}é", "a".repeat(509));
+    /// assert!(!options.set_name_lossy(&name));
+    /// assert_eq!(options.name, name);
+    /// ```
+    pub fn set_name_lossy(&mut self, name: &str) -> bool {
+        let mut stripped: String = name.chars().filter(|&c| c != '\0').collect();
+
+        if stripped.len() > Self::MAX_NAME_LEN {
+            let mut end = Self::MAX_NAME_LEN;
+            while !stripped.is_char_boundary(end) {
+                end -= 1;
+            }
+            stripped.truncate(end);
+        }
+
+        let changed = stripped.len() < name.len();
+        self.name = stripped;
+        changed
+    }
+
+    /// Convert these options to the raw options used by RtAudio.
+    ///
+    /// An error is returned if the options are not valid (see
+    /// `StreamOptions::validate()`).
+    pub fn to_raw(&self) -> Result<rtaudio_sys::rtaudio_stream_options_t, Error> {
+        self.validate()?;
+
+        let name = str_to_c_array::<{ MAX_NAME_LENGTH }>(&self.name).map_err(|_| {
+            Error::InvalidParameter {
+                msg: Some("Stream name is invalid".into()),
+                context: None,
+                origin: None,
+            }
         })?;
 
         Ok(rtaudio_sys::rtaudio_stream_options_t {
             flags: self.flags.bits(),
-            num_buffers: self.num_buffers as c_uint,
-            priority: self.priority as c_int,
+            // `None` is resolved for the API when the stream is opened.
+            num_buffers: self.num_buffers.unwrap_or(DEFAULT_NUM_BUFFERS) as c_uint,
+            // The other priorities depend on the API, so they are mapped
+            // when the stream is opened.
+            priority: match self.priority {
+                CallbackPriority::Raw(priority) => priority as c_int,
+                _ => -1,
+            },
             name,
         })
     }
+
+    /// The stream flags which are passed to the backend on the given API,
+    /// including the flags added by `priority` and `api_specific`.
+    pub(crate) fn effective_flags(&self, api: Api) -> StreamFlags {
+        self.flags | self.api_specific.flags_for(api) | self.priority.to_raw(api).0
+    }
 }
 
+/// The default number of buffers on the APIs without a specific default.
+const DEFAULT_NUM_BUFFERS: u32 = 4;
+
 impl Default for StreamOptions {
     fn default() -> Self {
         Self {
             flags: StreamFlags::empty(),
-            num_buffers: 4,
-            priority: -1,
+            num_buffers: None,
+            priority: CallbackPriority::Default,
             name: String::from("RtAudio-rs Client"),
+            output_limiter: OutputLimiter::Off,
+            silence_watchdog: None,
+            callback_budget: None,
+            deliver_warnings: false,
+            skip_channel_validation: false,
+            api_specific: ApiOptions::default(),
+        }
+    }
+}
+
+/// An invalid value in `StreamOptions`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionsError {
+    /// The name is too long, or it contains a NUL character.
+    InvalidName(NameError),
+}
+
+impl std::error::Error for OptionsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            OptionsError::InvalidName(e) => Some(e),
+        }
+    }
+}
+
+impl fmt::Display for OptionsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OptionsError::InvalidName(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<OptionsError> for Error {
+    fn from(e: OptionsError) -> Self {
+        Error::InvalidParameter {
+            msg: Some(e.to_string().into()),
+            context: None,
+            origin: None,
+        }
+    }
+}
+
+/// An invalid stream name.
+///
+/// See `StreamOptions::set_name()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameError {
+    /// The name is `len` bytes long, which is longer than the maximum of
+    /// `max` (`StreamOptions::MAX_NAME_LEN`) bytes.
+    TooLong { len: usize, max: usize },
+    /// The name contains a NUL character at byte `pos`.
+    ContainsNul { pos: usize },
+}
+
+impl std::error::Error for NameError {}
+
+impl fmt::Display for NameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NameError::TooLong { len, max } => write!(
+                f,
+                "RtAudio: stream name is {} bytes long (the maximum is {} bytes)",
+                len, max
+            ),
+            NameError::ContainsNul { pos } => write!(
+                f,
+                "RtAudio: stream name contains a NUL character at byte {}",
+                pos
+            ),
+        }
+    }
+}
+
+impl From<NameError> for OptionsError {
+    fn from(e: NameError) -> Self {
+        OptionsError::InvalidName(e)
+    }
+}
+
+impl From<NameError> for Error {
+    fn from(e: NameError) -> Self {
+        OptionsError::InvalidName(e).into()
+    }
+}
+
+fn check_name(name: &str) -> Result<(), NameError> {
+    if let Some(pos) = name.find('\0') {
+        return Err(NameError::ContainsNul { pos });
+    }
+    if name.len() > StreamOptions::MAX_NAME_LEN {
+        return Err(NameError::TooLong {
+            len: name.len(),
+            max: StreamOptions::MAX_NAME_LEN,
+        });
+    }
+
+    Ok(())
+}
+
+/// Host-level options which are applied when a `Host` is created.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostOptions {
+    /// Whether or not RtAudio should report warnings. If this is `false`,
+    /// then warnings are discarded by RtAudio and they will not be printed
+    /// to the log or sent to the warning handler.
+    ///
+    /// The default value is `true`.
+    pub show_warnings: bool,
+
+    /// Whether or not creating a host with the Jack API should fail if the
+    /// Jack server is not already running.
+    ///
+    /// RtAudio always asks libjack not to start the server itself. However
+    /// by default a missing server only results in a warning and a host with
+    /// no devices. If this is `true`, then `Host::new_with_options()` will
+    /// connect to the server up front and return an error if it is not
+    /// running.
+    ///
+    /// This has no effect on other APIs.
+    ///
+    /// The default value is `false`.
+    pub jack_no_start_server: bool,
+
+    /// The maximum amount of time to wait while connecting to the Jack
+    /// server (only used when `jack_no_start_server` is `true`).
+    ///
+    /// If the connection takes longer than this, then
+    /// `Host::new_with_options()` returns an error. The connection attempt
+    /// continues on a helper thread, which destroys the RtAudio instance once
+    /// it finishes.
+    ///
+    /// The default value is `None` (no timeout).
+    pub jack_connect_timeout: Option<Duration>,
+}
+
+impl Default for HostOptions {
+    fn default() -> Self {
+        Self {
+            show_warnings: true,
+            jack_no_start_server: false,
+            jack_connect_timeout: None,
+        }
+    }
+}
+
+/// The configuration of a stream (everything besides the devices to use).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct StreamConfig {
+    /// The sample format to use. If the device doesn't natively support the
+    /// given format, then it will automatically be converted to/from that
+    /// format.
+    ///
+    /// The default value is `SampleFormat::Float32`.
+    pub sample_format: SampleFormat,
+
+    /// The sample rate to use. The stream may decide to use a different
+    /// sample rate if it's not supported.
+    ///
+    /// The default value is `48000`.
+    pub sample_rate: u32,
+
+    /// The desired maximum number of frames that can appear in a single
+    /// process call. The stream may decide to use a different value if it's
+    /// not supported. The given value should be a power of 2.
+    ///
+    /// The default value is `256`.
+    pub buffer_frames: u32,
+
+    /// How `buffer_frames` is adjusted before it is passed to the backend.
+    ///
+    /// The default value is `BufferSizePolicy::AsRequested`.
+    pub buffer_size_policy: BufferSizePolicy,
+
+    /// How the sample rate is picked when the devices do not support
+    /// `sample_rate`.
+    ///
+    /// The default value is `RatePolicy::AsRequested`.
+    pub rate_policy: RatePolicy,
+
+    /// Additional options for the stream.
+    pub options: StreamOptions,
+}
+
+impl Default for StreamConfig {
+    fn default() -> Self {
+        Self {
+            sample_format: SampleFormat::default(),
+            sample_rate: 48000,
+            buffer_frames: 256,
+            buffer_size_policy: BufferSizePolicy::default(),
+            rate_policy: RatePolicy::default(),
+            options: StreamOptions::default(),
+        }
+    }
+}
+
+/// How the requested buffer size of a stream is adjusted before it is
+/// passed to the backend.
+///
+/// Backends may still use a different buffer size than the one they are
+/// given (i.e. ASIO drivers often only support a fixed set of sizes).
+/// Regardless of the policy, the buffer size that is actually used is
+/// available in `StreamInfo::max_frames`, and both sizes are recorded in
+/// the `NegotiationReport` of the stream. A warning is logged if the actual
+/// buffer size is more than twice as large or less than half as large as
+/// the requested size.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BufferSizePolicy {
+    /// Pass the requested buffer size on to the backend as-is.
+    #[default]
+    AsRequested,
+    /// Round the requested buffer size to the nearest power of two (the
+    /// higher one if two are equally close).
+    NearestPowerOfTwo,
+    /// Ignore the requested buffer size and let the backend choose its
+    /// smallest buffer size.
+    Auto,
+}
+
+impl BufferSizePolicy {
+    /// The buffer size which is passed to the backend when `requested` is
+    /// the requested buffer size.
+    ///
+    /// A buffer size of `0` lets the backend choose its smallest buffer
+    /// size.
+    ///
+    /// ```
+    /// use rtaudio::BufferSizePolicy;
+    ///
+    /// assert_eq!(BufferSizePolicy::AsRequested.apply(300), 300);
+    /// assert_eq!(BufferSizePolicy::Auto.apply(300), 0);
+    ///
+    /// let policy = BufferSizePolicy::NearestPowerOfTwo;
+    /// assert_eq!(policy.apply(256), 256);
+    /// assert_eq!(policy.apply(300), 256);
+    /// assert_eq!(policy.apply(384), 512);
+    /// assert_eq!(policy.apply(400), 512);
+    /// assert_eq!(policy.apply(3), 4);
+    /// assert_eq!(policy.apply(1), 1);
+    /// assert_eq!(policy.apply(0), 0);
+    /// assert_eq!(policy.apply(u32::MAX), 1 << 31);
+    /// ```
+    pub fn apply(&self, requested: u32) -> u32 {
+        match self {
+            BufferSizePolicy::AsRequested => requested,
+            BufferSizePolicy::NearestPowerOfTwo => nearest_power_of_two(requested),
+            BufferSizePolicy::Auto => 0,
+        }
+    }
+}
+
+fn nearest_power_of_two(n: u32) -> u32 {
+    if n == 0 {
+        return 0;
+    }
+
+    let lower: u32 = 1 << (u32::BITS - 1 - n.leading_zeros());
+    match lower.checked_mul(2) {
+        Some(upper) if upper - n <= n - lower => upper,
+        _ => lower,
+    }
+}
+
+/// How the sample rate of a stream is picked, based on the sample rates
+/// that the devices report.
+///
+/// For a duplex stream, only the sample rates which both devices support
+/// are considered. If the devices have no sample rate in common, then the
+/// sample rates of the output device are used. A device which reports no
+/// sample rates is treated as supporting any sample rate, so if no device
+/// reports any sample rates, then the requested sample rate is always used.
+///
+/// The policy and the resulting sample rate are recorded in the
+/// `NegotiationReport` of the stream.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RatePolicy {
+    /// Pass the requested sample rate on to the backend as-is. If it is not
+    /// supported, the backend may choose a different sample rate (or fail
+    /// to open the stream).
+    #[default]
+    AsRequested,
+    /// Fail to open the stream with `Error::InvalidParameter` if a device
+    /// does not support the requested sample rate.
+    ExactOrError,
+    /// Use the supported sample rate which is closest to the requested
+    /// sample rate (the higher one if two are equally close).
+    Nearest,
+    /// Ignore the requested sample rate and use the preferred sample rate
+    /// of the output device (or of the input device if there is no output
+    /// device). If the other device does not support it, then the closest
+    /// sample rate that both devices support is used.
+    Preferred,
+}
+
+impl RatePolicy {
+    /// Pick the sample rate to open a stream with.
+    ///
+    /// ```
+    /// use rtaudio::{Api, Host, RatePolicy};
+    ///
+    /// let host = Host::new(Api::Unspecified).unwrap();
+    ///
+    /// if let Ok(device) = host.default_output_device() {
+    ///     let sample_rate = RatePolicy::Nearest.resolve(50000, Some(&device), None);
+    ///     println!("{:?}", sample_rate);
+    /// }
+    /// ```
+    pub fn resolve(
+        &self,
+        requested: u32,
+        output: Option<&DeviceInfo>,
+        input: Option<&DeviceInfo>,
+    ) -> Result<u32, Error> {
+        match self {
+            RatePolicy::AsRequested => Ok(requested),
+            RatePolicy::ExactOrError => {
+                if let Some((device, role)) = unsupported_rate_device(requested, output, input) {
+                    return Err(Error::InvalidParameter {
+                        msg: Some(format!(
+                            "Sample rate {} is not supported by the {} device \"{}\" (supported sample rates: {:?})",
+                            requested, role, device.name, device.sample_rates
+                        ).into()),
+                        context: Some(ErrorContext::OpenStream),
+                        origin: None,
+                    });
+                }
+
+                Ok(requested)
+            }
+            RatePolicy::Nearest => Ok(nearest_rate(requested, output, input)),
+            RatePolicy::Preferred => {
+                let preferred = output
+                    .or(input)
+                    .map(|d| d.preferred_sample_rate)
+                    .filter(|&sr| sr > 0)
+                    .unwrap_or(requested);
+
+                Ok(nearest_rate(preferred, output, input))
+            }
         }
     }
+
+    /// Whether the stream is opened with exactly the requested sample rate,
+    /// rather than one picked from the sample rates the devices support.
+    pub(crate) fn uses_requested_rate(&self) -> bool {
+        matches!(self, RatePolicy::AsRequested | RatePolicy::ExactOrError)
+    }
+}
+
+/// The first of the given devices which does not support `sample_rate`,
+/// along with its role.
+pub(crate) fn unsupported_rate_device<'a>(
+    sample_rate: u32,
+    output: Option<&'a DeviceInfo>,
+    input: Option<&'a DeviceInfo>,
+) -> Option<(&'a DeviceInfo, Role)> {
+    [(output, Role::Output), (input, Role::Input)]
+        .into_iter()
+        .find_map(|(device, role)| {
+            device
+                .filter(|device| !device.supports_sample_rate(sample_rate))
+                .map(|device| (device, role))
+        })
+}
+
+/// The supported sample rate which is closest to `target`, or `target`
+/// itself if no device reports any sample rates.
+fn nearest_rate(target: u32, output: Option<&DeviceInfo>, input: Option<&DeviceInfo>) -> u32 {
+    let rates = match (output, input) {
+        (Some(out), Some(inp)) => {
+            let common = out.common_sample_rates(inp);
+            if common.is_empty() {
+                log::warn!("RtAudio: the output and input devices have no sample rate in common");
+                out.sample_rates.clone()
+            } else {
+                common
+            }
+        }
+        (Some(device), None) | (None, Some(device)) => device.sample_rates.clone(),
+        (None, None) => Vec::new(),
+    };
+
+    rates
+        .iter()
+        .copied()
+        .min_by_key(|&sr| (sr.abs_diff(target), std::cmp::Reverse(sr)))
+        .unwrap_or(target)
 }
 
 fn str_to_c_array<const MAX_LEN: usize>(s: &str) -> Result<[c_char; MAX_LEN], ()> {
@@ -102,13 +1190,163 @@ fn str_to_c_array<const MAX_LEN: usize>(s: &str) -> Result<[c_char; MAX_LEN], ()
     let cs_slice =
         unsafe { std::slice::from_raw_parts(cs_slice.as_ptr() as *const c_char, cs_slice.len()) };
 
-    if cs_slice.len() > MAX_LEN as usize {
+    if cs_slice.len() > MAX_LEN {
         return Err(());
     }
 
     let mut c_array: [c_char; MAX_LEN] = [0; MAX_LEN];
 
-    c_array[0..cs_slice.len()].copy_from_slice(&cs_slice);
+    c_array[0..cs_slice.len()].copy_from_slice(cs_slice);
 
     Ok(c_array)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::device_info::test_device;
+
+    /// A device with the given channels and no duplex channels.
+    fn device(output_channels: u32, input_channels: u32) -> DeviceInfo {
+        DeviceInfo {
+            output_channels,
+            input_channels,
+            duplex_channels: 0,
+            ..test_device()
+        }
+    }
+
+    /// A device with the given preferred and supported sample rates.
+    fn device_with_rates(preferred_sample_rate: u32, sample_rates: &[u32]) -> DeviceInfo {
+        DeviceInfo {
+            preferred_sample_rate,
+            sample_rates: sample_rates.to_vec(),
+            ..test_device()
+        }
+    }
+
+    #[test]
+    fn params_from_device_use_up_to_two_channels() {
+        let params = DeviceParams::output_from(&device(4, 0)).unwrap();
+        assert_eq!(params.device_id, DeviceID::new(1));
+        assert_eq!((params.num_channels, params.first_channel), (2, 0));
+
+        let params = DeviceParams::input_from(&device(0, 1)).unwrap();
+        assert_eq!((params.num_channels, params.first_channel), (1, 0));
+
+        // No channels in that direction
+        assert_eq!(DeviceParams::input_from(&device(2, 0)), None);
+        assert_eq!(DeviceParams::output_from(&device(0, 0)), None);
+    }
+
+    #[test]
+    fn clamp_to_fits_the_channels_of_the_device() {
+        let device = device(4, 0);
+
+        let params = DeviceParams::output_from(&device)
+            .unwrap()
+            .with_first_channel(2)
+            .with_channels(8)
+            .clamp_to(&device, Role::Output)
+            .unwrap();
+        assert_eq!((params.num_channels, params.first_channel), (2, 2));
+        assert!(params.validate(&device, Role::Output).is_ok());
+
+        let params = DeviceParams::default().with_first_channel(9);
+        let params = params.clamp_to(&device, Role::Output).unwrap();
+        assert_eq!((params.num_channels, params.first_channel), (1, 3));
+
+        assert_eq!(DeviceParams::default().clamp_to(&device, Role::Input), None);
+    }
+
+    #[test]
+    fn validate_reports_every_problem() {
+        let device = device(4, 0);
+
+        let params = DeviceParams {
+            device_id: DeviceID::new(2),
+            num_channels: 0,
+            first_channel: 4,
+        };
+        let problems = params.validate(&device, Role::Output).unwrap_err();
+        assert_eq!(
+            problems,
+            [
+                ParamProblem::DeviceIdMismatch {
+                    params_id: DeviceID::new(2).unwrap(),
+                    device_id: device.id,
+                },
+                ParamProblem::ZeroChannels,
+                ParamProblem::FirstChannelTooHigh {
+                    first_channel: 4,
+                    available: 4,
+                },
+            ]
+        );
+
+        assert_eq!(
+            DeviceParams::default().validate(&device, Role::Input),
+            Err(vec![ParamProblem::RoleNotSupported { role: Role::Input }])
+        );
+    }
+
+    #[test]
+    fn rate_policies() {
+        let out = device_with_rates(48000, &[44100, 48000, 96000]);
+        let inp = device_with_rates(44100, &[44100, 96000]);
+        let any = device_with_rates(48000, &[]);
+
+        let policy = RatePolicy::AsRequested;
+        assert_eq!(policy.resolve(22050, Some(&out), None).unwrap(), 22050);
+
+        let policy = RatePolicy::ExactOrError;
+        assert_eq!(policy.resolve(48000, Some(&out), None).unwrap(), 48000);
+        assert!(policy.resolve(48000, Some(&out), Some(&inp)).is_err());
+        assert_eq!(policy.resolve(22050, Some(&any), None).unwrap(), 22050);
+
+        let policy = RatePolicy::Nearest;
+        assert_eq!(policy.resolve(50000, Some(&out), None).unwrap(), 48000);
+        assert_eq!(
+            policy.resolve(50000, Some(&out), Some(&inp)).unwrap(),
+            44100
+        );
+        assert_eq!(
+            policy.resolve(50000, Some(&out), Some(&any)).unwrap(),
+            48000
+        );
+        assert_eq!(policy.resolve(50000, Some(&any), None).unwrap(), 50000);
+
+        let policy = RatePolicy::Preferred;
+        assert_eq!(policy.resolve(22050, Some(&out), None).unwrap(), 48000);
+        assert_eq!(policy.resolve(22050, None, Some(&inp)).unwrap(), 44100);
+        // The input device does not support 48000, so 44100 is the closest
+        // sample rate they have in common.
+        assert_eq!(
+            policy.resolve(22050, Some(&out), Some(&inp)).unwrap(),
+            44100
+        );
+        assert_eq!(policy.resolve(22050, None, None).unwrap(), 22050);
+    }
+
+    #[test]
+    fn raw_params_use_zero_for_no_device() {
+        let params = DeviceParams {
+            device_id: None,
+            num_channels: 2,
+            first_channel: 1,
+        };
+
+        let raw = params.to_raw();
+        assert_eq!(raw.device_id, 0);
+        assert_eq!(raw.num_channels, 2);
+        assert_eq!(raw.first_channel, 1);
+
+        let raw = DeviceParams {
+            device_id: DeviceID::new(131),
+            ..params
+        }
+        .to_raw();
+        assert_eq!(raw.device_id, 131);
+    }
+}
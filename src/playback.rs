@@ -0,0 +1,637 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::error::ErrorContext;
+use crate::rt::{Consumer, Producer, SpscRing};
+use crate::{Buffers, Error, StreamHandle, StreamInfo, StreamStatus};
+
+/// How often `OutputWriter::flush_blocking()` checks whether the buffer has
+/// been played.
+const FLUSH_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// The number of samples which are moved from the buffer to the output at
+/// once.
+const CHUNK_LEN: usize = 256;
+
+/// The writing end of an output playback started with
+/// `StreamHandle::start_output_playback()`.
+///
+/// Samples are written as interleaved `f32` samples, and converted to the
+/// sample format and layout of the stream. This can be sent to another
+/// thread, and writing to it never blocks.
+///
+/// If the buffer runs empty, then the stream outputs silence and the
+/// missing frames are counted (see `OutputWriter::underruns()`). This also
+/// happens before the first samples are written.
+pub struct OutputWriter {
+    producer: Producer<f32>,
+    channels: usize,
+    shared: Arc<PlaybackShared>,
+}
+
+struct PlaybackShared {
+    underruns: AtomicU64,
+    /// Set once nothing more will be written, so that the data callback
+    /// stops the stream when the buffer has been played.
+    stop_when_drained: AtomicBool,
+}
+
+impl OutputWriter {
+    /// Write as many samples from `src` as there is space for, in whole
+    /// frames.
+    ///
+    /// Returns the number of samples (not frames) that were written.
+    pub fn write(&mut self, src: &[f32]) -> usize {
+        let space = self.space().min(src.len());
+        let whole_frames = space - space % self.channels;
+
+        self.producer.push_iter(src[..whole_frames].iter().copied())
+    }
+
+    /// The number of samples (not frames) which can currently be written.
+    pub fn space(&self) -> usize {
+        self.producer.free_len()
+    }
+
+    /// The number of samples (not frames) which have been written but not
+    /// played yet.
+    pub fn queued(&self) -> usize {
+        self.producer.len()
+    }
+
+    /// The number of channels in each frame.
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    /// The maximum number of samples the buffer can hold.
+    pub fn capacity(&self) -> usize {
+        self.producer.capacity()
+    }
+
+    /// The number of frames of silence which were output because the buffer
+    /// was empty.
+    pub fn underruns(&self) -> u64 {
+        self.shared.underruns.load(Ordering::Relaxed)
+    }
+
+    /// Wait until every sample which has been written has been handed to
+    /// the device, or until `timeout` has elapsed.
+    ///
+    /// Returns `true` if the buffer was emptied in time. Note that the
+    /// device may still be playing the last block when this returns.
+    pub fn flush_blocking(&self, timeout: Duration) -> bool {
+        let start = Instant::now();
+
+        loop {
+            if self.producer.is_empty() {
+                return true;
+            }
+            if start.elapsed() >= timeout {
+                return false;
+            }
+
+            std::thread::sleep(FLUSH_POLL_INTERVAL);
+        }
+    }
+
+    /// Stop the stream once every sample which has been written has been
+    /// played, instead of outputting silence. The rest of the last block is
+    /// not counted as an underrun.
+    #[cfg_attr(not(feature = "wav"), allow(dead_code))]
+    pub(crate) fn stop_when_drained(&self) {
+        self.shared.stop_when_drained.store(true, Ordering::Release);
+    }
+}
+
+impl std::fmt::Debug for OutputWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OutputWriter")
+            .field("channels", &self.channels)
+            .field("queued", &self.queued())
+            .field("capacity", &self.capacity())
+            .field("underruns", &self.underruns())
+            .finish()
+    }
+}
+
+impl StreamHandle {
+    /// Start the stream, playing samples from a lock-free buffer which holds
+    /// up to `capacity_frames` frames.
+    ///
+    /// The returned `OutputWriter` takes interleaved `f32` samples, whatever
+    /// the sample format and layout of the stream. The buffer is allocated
+    /// here, so nothing is allocated on the audio thread. The input of the
+    /// stream (if any) is ignored.
+    ///
+    /// ```no_run
+    /// use rtaudio::{Api, DeviceParams, SampleFormat};
+    /// use std::time::Duration;
+    ///
+    /// let host = rtaudio::Host::new(Api::Unspecified)?;
+    /// let mut stream = host
+    ///     .stream_builder()
+    ///     .output_device(DeviceParams::default())
+    ///     .sample_format(SampleFormat::Float32)
+    ///     .sample_rate(48000)
+    ///     .open(|error| eprintln!("{}", error))
+    ///     .map_err(|e| e.error)?;
+    ///
+    /// // Room for a tenth of a second of output.
+    /// let mut writer = stream.start_output_playback(4800)?;
+    /// let channels = writer.channels();
+    ///
+    /// // One second of a 440 Hz sine wave.
+    /// let samples: Vec<f32> = (0..48000)
+    ///     .flat_map(|i| {
+    ///         let s = (i as f32 * 440.0 * std::f32::consts::TAU / 48000.0).sin() * 0.2;
+    ///         std::iter::repeat(s).take(channels)
+    ///     })
+    ///     .collect();
+    ///
+    /// let mut remaining = &samples[..];
+    /// while !remaining.is_empty() {
+    ///     let n = writer.write(remaining);
+    ///     remaining = &remaining[n..];
+    ///     std::thread::sleep(Duration::from_millis(10));
+    /// }
+    /// writer.flush_blocking(Duration::from_secs(1));
+    /// # Ok::<(), rtaudio::Error>(())
+    /// ```
+    ///
+    /// Returns `Error::InvalidUse` if the stream has no output channels, and
+    /// `Error::InvalidParameter` if `capacity_frames` is `0`.
+    pub fn start_output_playback(&mut self, capacity_frames: usize) -> Result<OutputWriter, Error> {
+        let channels = self.info().out_channels;
+
+        if channels == 0 {
+            return Err(Error::InvalidUse {
+                msg: Some("the stream has no output channels to play to".into()),
+                context: Some(ErrorContext::StartStream),
+                origin: None,
+            });
+        }
+        if capacity_frames == 0 {
+            return Err(Error::InvalidParameter {
+                msg: Some("the playback buffer must hold at least one frame".into()),
+                context: Some(ErrorContext::StartStream),
+                origin: None,
+            });
+        }
+
+        let (producer, mut consumer) = SpscRing::new(capacity_frames * channels);
+        let shared = Arc::new(PlaybackShared {
+            underruns: AtomicU64::new(0),
+            stop_when_drained: AtomicBool::new(false),
+        });
+        let shared_cb = Arc::clone(&shared);
+        let stop = self.callback_stop();
+
+        self.start(
+            move |mut buffers: Buffers<'_>, info: &StreamInfo, _status: StreamStatus| {
+                // Loaded before popping, so that every sample which was
+                // written before it was set is seen.
+                let draining = shared_cb.stop_when_drained.load(Ordering::Acquire);
+
+                let missing = pop_output(&mut consumer, &mut buffers, info);
+                if draining {
+                    if consumer.is_empty() {
+                        stop.request();
+                    }
+                } else if missing > 0 {
+                    shared_cb
+                        .underruns
+                        .fetch_add(missing as u64, Ordering::Relaxed);
+                }
+            },
+        )?;
+
+        Ok(OutputWriter {
+            producer,
+            channels,
+            shared,
+        })
+    }
+}
+
+/// The progress of a sequence of frames started with
+/// `StreamHandle::start_frames()`.
+///
+/// This can be sent to another thread.
+#[derive(Clone)]
+pub struct FramesPlayback {
+    shared: Arc<FramesShared>,
+}
+
+struct FramesShared {
+    frames: AtomicU64,
+    finished: AtomicBool,
+}
+
+impl FramesPlayback {
+    /// The number of frames which have been handed to the device.
+    pub fn frames_played(&self) -> u64 {
+        self.shared.frames.load(Ordering::Relaxed)
+    }
+
+    /// Whether every frame has been handed to the device. The stream then
+    /// stops once the device has played them.
+    pub fn is_finished(&self) -> bool {
+        self.shared.finished.load(Ordering::Acquire)
+    }
+
+    /// Wait until every frame has been handed to the device, or until
+    /// `timeout` has elapsed.
+    ///
+    /// Returns `true` if playback finished in time. Note that the device
+    /// may still be playing the last block when this returns.
+    pub fn wait_until_finished(&self, timeout: Duration) -> bool {
+        let start = Instant::now();
+
+        loop {
+            if self.is_finished() {
+                return true;
+            }
+            if start.elapsed() >= timeout {
+                return false;
+            }
+
+            std::thread::sleep(FLUSH_POLL_INTERVAL);
+        }
+    }
+}
+
+impl std::fmt::Debug for FramesPlayback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FramesPlayback")
+            .field("frames_played", &self.frames_played())
+            .field("finished", &self.is_finished())
+            .finish()
+    }
+}
+
+impl StreamHandle {
+    /// Start the stream, playing the frames of `frames` until it ends.
+    ///
+    /// Each frame is an array of `N` `f32` samples, which is converted to
+    /// the sample format and layout of the stream. The frames are pulled on
+    /// the audio thread, so the iterator must not allocate or block. Once
+    /// it ends, the rest of the last block is filled with silence and
+    /// `FramesPlayback::is_finished()` returns `true`. The stream then stops
+    /// itself after the queued buffers have played, and its state goes back
+    /// to `StreamState::Open`, so it can be started again.
+    ///
+    /// The channels of each frame are mapped to the output channels the
+    /// same way as `Buffers::route_input_to_output()`:
+    ///
+    /// * If `N` is `1`, the sample is copied to every output channel.
+    /// * Otherwise each sample is copied to the output channel with the same
+    ///   index. Extra samples are discarded, and extra output channels are
+    ///   filled with silence.
+    ///
+    /// Frames in this shape are what `dasp` signals produce (see
+    /// `StreamHandle::start_signal()` with the `dasp` feature). A sine wave
+    /// without any dependencies:
+    ///
+    /// ```no_run
+    /// use rtaudio::{Api, DeviceParams};
+    /// use std::time::Duration;
+    ///
+    /// let host = rtaudio::Host::new(Api::Unspecified)?;
+    /// let mut stream = host
+    ///     .stream_builder()
+    ///     .output_device(DeviceParams::default())
+    ///     .sample_rate(48000)
+    ///     .open(|error| eprintln!("{}", error))
+    ///     .map_err(|e| e.error)?;
+    ///
+    /// let sine = (0..48000 * 3).map(|i| [(i as f32 * 440.0 / 48000.0 * std::f32::consts::TAU).sin() * 0.5]);
+    /// let playback = stream.start_frames(sine)?;
+    /// playback.wait_until_finished(Duration::from_secs(4));
+    /// # Ok::<(), rtaudio::Error>(())
+    /// ```
+    ///
+    /// Returns `Error::InvalidUse` if the stream has no output channels.
+    pub fn start_frames<I, const N: usize>(&mut self, frames: I) -> Result<FramesPlayback, Error>
+    where
+        I: IntoIterator<Item = [f32; N]>,
+        I::IntoIter: Send + 'static,
+    {
+        if self.info().out_channels == 0 {
+            return Err(Error::InvalidUse {
+                msg: Some("the stream has no output channels to play to".into()),
+                context: Some(ErrorContext::StartStream),
+                origin: None,
+            });
+        }
+
+        let mut frames = frames.into_iter();
+        let shared = Arc::new(FramesShared {
+            frames: AtomicU64::new(0),
+            finished: AtomicBool::new(false),
+        });
+        let shared_cb = Arc::clone(&shared);
+        let stop = self.callback_stop();
+
+        self.start(
+            move |mut buffers: Buffers<'_>, info: &StreamInfo, _status: StreamStatus| {
+                buffers.fill_output_silence();
+
+                if shared_cb.finished.load(Ordering::Relaxed) {
+                    return;
+                }
+
+                let played = pull_frames(&mut frames, &mut buffers, info);
+                shared_cb.frames.fetch_add(played as u64, Ordering::Relaxed);
+                if played < output_len(&buffers) / info.out_channels {
+                    shared_cb.finished.store(true, Ordering::Release);
+                    stop.request();
+                }
+            },
+        )?;
+
+        Ok(FramesPlayback { shared })
+    }
+
+    /// Start the stream, playing the `dasp` signal until it is exhausted.
+    ///
+    /// The samples are converted to `f32` and then played with
+    /// `StreamHandle::start_frames()`, so the same channel mapping applies
+    /// (i.e. a mono signal is copied to every output channel), and the
+    /// stream stops itself once the signal is exhausted. A signal which is
+    /// never exhausted plays until the stream is stopped.
+    ///
+    /// This is only available with the `dasp` feature.
+    ///
+    /// ```no_run
+    /// use dasp_signal::{self as signal, Signal};
+    /// use rtaudio::{Api, DeviceParams};
+    /// use std::time::Duration;
+    ///
+    /// let host = rtaudio::Host::new(Api::Unspecified)?;
+    /// let mut stream = host
+    ///     .stream_builder()
+    ///     .output_device(DeviceParams::default())
+    ///     .sample_rate(48000)
+    ///     .open(|error| eprintln!("{}", error))
+    ///     .map_err(|e| e.error)?;
+    ///
+    /// let sine = signal::rate(48000.0).const_hz(440.0).sine().scale_amp(0.5).take(48000 * 3);
+    /// let playback = stream.start_signal(signal::from_iter(sine.map(|s| [s])))?;
+    /// playback.wait_until_finished(Duration::from_secs(4));
+    /// # Ok::<(), rtaudio::Error>(())
+    /// ```
+    ///
+    /// Returns `Error::InvalidUse` if the stream has no output channels.
+    #[cfg(feature = "dasp")]
+    pub fn start_signal<S, T, const N: usize>(&mut self, signal: S) -> Result<FramesPlayback, Error>
+    where
+        S: dasp_signal::Signal<Frame = [T; N]> + Send + 'static,
+        T: dasp_sample::ToSample<f32> + 'static,
+    {
+        self.start_frames(signal_frames(signal))
+    }
+}
+
+/// The frames of a `dasp` signal until it is exhausted, converted to `f32`.
+#[cfg(feature = "dasp")]
+fn signal_frames<S, T, const N: usize>(signal: S) -> impl Iterator<Item = [f32; N]>
+where
+    S: dasp_signal::Signal<Frame = [T; N]>,
+    T: dasp_sample::ToSample<f32>,
+{
+    signal
+        .until_exhausted()
+        .map(|frame| frame.map(dasp_sample::ToSample::to_sample_))
+}
+
+/// Fill the (silent) output with frames from `frames` until it ends.
+///
+/// Returns the number of frames that were written.
+fn pull_frames<const N: usize>(
+    frames: &mut impl Iterator<Item = [f32; N]>,
+    buffers: &mut Buffers<'_>,
+    info: &StreamInfo,
+) -> usize {
+    let channels = info.out_channels;
+    let num_frames = output_len(buffers) / channels;
+    let mode = info.interleave_mode();
+
+    for f in 0..num_frames {
+        let Some(frame) = frames.next() else {
+            return f;
+        };
+
+        for c in 0..channels {
+            let s = match N {
+                1 => frame[0],
+                _ if c < N => frame[c],
+                _ => continue,
+            };
+            let i = mode.sample_index(c, f, channels, num_frames);
+
+            write_output_sample(buffers, i, s);
+        }
+    }
+
+    num_frames
+}
+
+/// Fill the output with as many whole frames from the buffer as are
+/// available, converted from interleaved `f32` samples, and fill the rest
+/// with silence.
+///
+/// Returns the number of frames that were missing.
+pub(crate) fn pop_output(
+    consumer: &mut Consumer<f32>,
+    buffers: &mut Buffers<'_>,
+    info: &StreamInfo,
+) -> usize {
+    let channels = info.out_channels;
+    if channels == 0 {
+        return 0;
+    }
+
+    buffers.fill_output_silence();
+
+    let frames = output_len(buffers) / channels;
+    let available = frames.min(consumer.len() / channels);
+
+    // The index in the output of the `k`th interleaved sample.
+    let mode = info.interleave_mode();
+    let index = |k: usize| mode.sample_index(k % channels, k / channels, channels, frames);
+
+    let mut chunk = [0.0f32; CHUNK_LEN];
+    let mut k = 0;
+    while k < available * channels {
+        let len = CHUNK_LEN.min(available * channels - k);
+        let popped = consumer.pop_slice(&mut chunk[..len]);
+
+        for &s in &chunk[..popped] {
+            write_output_sample(buffers, index(k), s);
+            k += 1;
+        }
+
+        if popped < len {
+            break;
+        }
+    }
+
+    frames - available
+}
+
+/// The number of samples (not frames) in the output buffer.
+fn output_len(buffers: &Buffers<'_>) -> usize {
+    match buffers {
+        Buffers::SInt8 { output, .. } => output.len(),
+        Buffers::SInt16 { output, .. } => output.len(),
+        Buffers::SInt24 { output, .. } => output.len() / 3,
+        Buffers::SInt32 { output, .. } => output.len(),
+        Buffers::Float32 { output, .. } => output.len(),
+        Buffers::Float64 { output, .. } => output.len(),
+    }
+}
+
+/// Convert an `f32` sample to the sample format of the output, and write it
+/// at index `i`.
+fn write_output_sample(buffers: &mut Buffers<'_>, i: usize, s: f32) {
+    match buffers {
+        Buffers::SInt8 { output, .. } => output[i] = (s * 128.0).round().clamp(-128.0, 127.0) as i8,
+        Buffers::SInt16 { output, .. } => {
+            output[i] = (s * 32_768.0).round().clamp(-32_768.0, 32_767.0) as i16
+        }
+        Buffers::SInt24 { output, .. } => output[i * 3..i * 3 + 3].copy_from_slice(&f32_to_i24(s)),
+        Buffers::SInt32 { output, .. } => {
+            output[i] = (f64::from(s) * 2_147_483_648.0)
+                .round()
+                .clamp(-2_147_483_648.0, 2_147_483_647.0) as i32
+        }
+        Buffers::Float32 { output, .. } => output[i] = s,
+        Buffers::Float64 { output, .. } => output[i] = f64::from(s),
+    }
+}
+
+/// Convert a sample to 24 bits in the host's native byte order.
+fn f32_to_i24(s: f32) -> [u8; 3] {
+    let value = (s * 8_388_608.0).round().clamp(-8_388_608.0, 8_388_607.0) as i32;
+
+    let bytes = value.to_ne_bytes();
+    if cfg!(target_endian = "little") {
+        [bytes[0], bytes[1], bytes[2]]
+    } else {
+        [bytes[1], bytes[2], bytes[3]]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(out_channels: usize, deinterleaved: bool) -> StreamInfo {
+        StreamInfo {
+            out_channels,
+            deinterleaved,
+            ..Default::default()
+        }
+    }
+
+    fn filled(samples: &[f32]) -> Consumer<f32> {
+        let (mut producer, consumer) = SpscRing::new(64);
+        producer.push_iter(samples.iter().copied());
+        consumer
+    }
+
+    #[test]
+    fn pop_output_converts_and_pads_with_silence() {
+        // 1.5 stereo frames are queued, so only one whole frame is played.
+        let mut consumer = filled(&[0.5, -1.0, 0.25]);
+        let mut output = [7i16; 6];
+        let mut buffers = Buffers::SInt16 {
+            output: &mut output,
+            input: &[],
+        };
+
+        assert_eq!(pop_output(&mut consumer, &mut buffers, &info(2, false)), 2);
+        assert_eq!(output, [16_384, -32_768, 0, 0, 0, 0]);
+        // The partial frame stays queued.
+        assert_eq!(consumer.len(), 1);
+    }
+
+    #[test]
+    fn pop_output_follows_the_interleave_mode() {
+        let samples = [0.1, 0.2, 0.3, 0.4, 0.5, 0.6];
+
+        let mut output = [0.0f32; 6];
+        let mut buffers = Buffers::Float32 {
+            output: &mut output,
+            input: &[],
+        };
+        pop_output(&mut filled(&samples), &mut buffers, &info(2, false));
+        assert_eq!(output, samples);
+
+        let mut buffers = Buffers::Float32 {
+            output: &mut output,
+            input: &[],
+        };
+        pop_output(&mut filled(&samples), &mut buffers, &info(2, true));
+        assert_eq!(output, [0.1, 0.3, 0.5, 0.2, 0.4, 0.6]);
+    }
+
+    #[test]
+    fn pop_output_clamps_integer_samples() {
+        let mut consumer = filled(&[2.0, -2.0]);
+        let mut output = [0i32; 2];
+        let mut buffers = Buffers::SInt32 {
+            output: &mut output,
+            input: &[],
+        };
+        pop_output(&mut consumer, &mut buffers, &info(2, false));
+        assert_eq!(output, [i32::MAX, i32::MIN]);
+
+        let mut consumer = filled(&[1.0, -0.5]);
+        let mut output = [0u8; 6];
+        let mut buffers = Buffers::SInt24 {
+            output: &mut output,
+            input: &[],
+        };
+        pop_output(&mut consumer, &mut buffers, &info(2, false));
+        assert_eq!(output[..3], f32_to_i24(8_388_607.0 / 8_388_608.0));
+        assert_eq!(output[3..], f32_to_i24(-0.5));
+    }
+
+    #[test]
+    fn pop_output_without_output_channels_does_nothing() {
+        let mut consumer = filled(&[0.5]);
+        let mut buffers = Buffers::Float32 {
+            output: &mut [],
+            input: &[],
+        };
+
+        assert_eq!(pop_output(&mut consumer, &mut buffers, &info(0, false)), 0);
+        assert_eq!(consumer.len(), 1);
+    }
+
+    #[cfg(feature = "dasp")]
+    #[test]
+    fn signal_frames_are_converted_until_exhausted() {
+        /// A stereo signal of three `i16` frames.
+        struct Steps(i16);
+
+        impl dasp_signal::Signal for Steps {
+            type Frame = [i16; 2];
+
+            fn next(&mut self) -> [i16; 2] {
+                self.0 += 1;
+                [self.0 * 8192, -self.0 * 8192]
+            }
+
+            fn is_exhausted(&self) -> bool {
+                self.0 >= 3
+            }
+        }
+
+        let frames: Vec<[f32; 2]> = signal_frames(Steps(0)).collect();
+        assert_eq!(frames, [[0.25, -0.25], [0.5, -0.5], [0.75, -0.75]]);
+    }
+}
@@ -0,0 +1,72 @@
+use std::fmt;
+
+use crate::{Api, DeviceID, DeviceInfo, DeviceScanError, Host, HostOptions};
+
+/// A diagnostic report of everything RtAudio knows about the system.
+///
+/// This is created with `Host::probe_all()`. Its `Display` implementation
+/// prints a readable report that is suitable for pasting into bug reports.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProbeReport {
+    /// The RtAudio version.
+    pub version: String,
+    /// The API used by the host.
+    pub api: Api,
+    /// The ID of the default output device, if there is one.
+    pub default_output_device_id: Option<DeviceID>,
+    /// The ID of the default input device, if there is one.
+    pub default_input_device_id: Option<DeviceID>,
+    /// The result of scanning every device, in order of their index.
+    pub devices: Vec<Result<DeviceInfo, DeviceScanError>>,
+}
+
+impl fmt::Display for ProbeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "RtAudio version: {}", self.version)?;
+        writeln!(f, "API: {} ({})", self.api, self.api.get_display_name())?;
+
+        match self.default_output_device_id {
+            Some(id) => writeln!(f, "Default output device ID: {}", id)?,
+            None => writeln!(f, "Default output device ID: none")?,
+        }
+        match self.default_input_device_id {
+            Some(id) => writeln!(f, "Default input device ID: {}", id)?,
+            None => writeln!(f, "Default input device ID: none")?,
+        }
+
+        write!(f, "Devices ({}):", self.devices.len())?;
+
+        for (index, device) in self.devices.iter().enumerate() {
+            match device {
+                Ok(d) => write!(f, "\n  [{}] {:#}", index, d)?,
+                Err(e) => write!(f, "\n  [{}] {}", index, e)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Check whether the given API is compiled in and can be used right now.
+///
+/// This is useful for choosing a fallback order of APIs. For
+/// `Api::UnixJack`, this connects to the Jack server (with
+/// `HostOptions::jack_no_start_server` forced to `true`), so it returns
+/// `false` if the server is not running.
+///
+/// The given options are used when creating the temporary host, so
+/// `HostOptions::jack_connect_timeout` can be used to bound the check.
+pub fn probe_api(api: Api, options: HostOptions) -> bool {
+    if !crate::compiled_apis().contains(&api) {
+        return false;
+    }
+
+    Host::new_with_options(
+        api,
+        HostOptions {
+            jack_no_start_server: true,
+            ..options
+        },
+    )
+    .is_ok()
+}
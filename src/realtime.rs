@@ -0,0 +1,257 @@
+use std::sync::atomic::{AtomicI32, AtomicU8, AtomicUsize, Ordering};
+
+/// How the thread which runs the data callback is scheduled.
+///
+/// This is read back from the callback thread of a running stream, so it
+/// shows whether a `CallbackPriority` was actually honored. See
+/// `StreamHandle::realtime_status()`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RealtimeStatus {
+    /// The data callback has not run since the stream was started, or the
+    /// scheduling policy can't be read on this platform.
+    #[default]
+    Unknown,
+    /// The callback thread uses a normal (non-realtime) scheduling policy.
+    Normal,
+    /// The callback thread uses a realtime scheduling policy (`SCHED_FIFO`
+    /// or `SCHED_RR`) with the given priority.
+    Realtime { priority: i32 },
+}
+
+impl RealtimeStatus {
+    /// Whether or not the callback thread is known to use a realtime
+    /// scheduling policy.
+    pub fn is_realtime(&self) -> bool {
+        matches!(self, RealtimeStatus::Realtime { .. })
+    }
+}
+
+const UNKNOWN: u8 = 0;
+const NORMAL: u8 = 1;
+const REALTIME: u8 = 2;
+
+/// The realtime status of the audio thread.
+///
+/// The audio thread only records its id (see `current_thread_id()`), since
+/// reading the scheduling policy can lock and enter the kernel. The notifier
+/// thread then takes the id and stores the status of that thread.
+#[derive(Debug, Default)]
+pub(crate) struct SharedRealtimeStatus {
+    kind: AtomicU8,
+    priority: AtomicI32,
+    /// The id of the audio thread, or `0` if it has not been recorded.
+    thread: AtomicUsize,
+}
+
+impl SharedRealtimeStatus {
+    /// Record the id of the calling audio thread. This does not lock or
+    /// make a syscall.
+    pub(crate) fn record_thread(&self, thread: usize) {
+        self.thread.store(thread, Ordering::Release);
+    }
+
+    /// Take the id which was recorded by the audio thread, if any.
+    pub(crate) fn take_thread(&self) -> Option<usize> {
+        Some(self.thread.swap(0, Ordering::AcqRel)).filter(|&thread| thread != 0)
+    }
+
+    /// Forget the recorded thread and status, i.e. when the stream is
+    /// started again.
+    pub(crate) fn reset(&self) {
+        self.thread.store(0, Ordering::Release);
+        self.store(RealtimeStatus::Unknown);
+    }
+
+    pub(crate) fn load(&self) -> RealtimeStatus {
+        match self.kind.load(Ordering::Acquire) {
+            NORMAL => RealtimeStatus::Normal,
+            REALTIME => RealtimeStatus::Realtime {
+                priority: self.priority.load(Ordering::Relaxed),
+            },
+            _ => RealtimeStatus::Unknown,
+        }
+    }
+
+    pub(crate) fn store(&self, status: RealtimeStatus) {
+        let kind = match status {
+            RealtimeStatus::Unknown => UNKNOWN,
+            RealtimeStatus::Normal => NORMAL,
+            RealtimeStatus::Realtime { priority } => {
+                self.priority.store(priority, Ordering::Relaxed);
+                REALTIME
+            }
+        };
+
+        self.kind.store(kind, Ordering::Release);
+    }
+}
+
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd"
+))]
+mod pthread {
+    use std::os::raw::c_int;
+
+    // Larger than `struct sched_param` on all of the above, which only has
+    // the priority (followed by padding on Apple platforms) in common.
+    #[repr(C)]
+    pub(super) struct SchedParam {
+        pub(super) sched_priority: c_int,
+        pub(super) _reserved: [c_int; 7],
+    }
+
+    // `pthread_t` is an integer or a pointer, both of which are the size
+    // of a `usize` on the above.
+    extern "C" {
+        pub(super) fn pthread_self() -> usize;
+        pub(super) fn pthread_getschedparam(
+            thread: usize,
+            policy: *mut c_int,
+            param: *mut SchedParam,
+        ) -> c_int;
+    }
+}
+
+/// The id of the calling thread, which is never `0`.
+///
+/// This only reads thread-local state, so it is safe to call on the audio
+/// thread. It is `0` on platforms where the scheduling can't be read.
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd"
+))]
+pub(crate) fn current_thread_id() -> usize {
+    // Safe because this has no preconditions.
+    unsafe { pthread::pthread_self() }
+}
+
+/// The scheduling can't be read on this platform, so no id is recorded.
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd"
+)))]
+pub(crate) fn current_thread_id() -> usize {
+    0
+}
+
+/// Read the scheduling policy of the thread with the given id (from
+/// `current_thread_id()`).
+///
+/// This can lock and make a system call, so it must not be called on the
+/// audio thread.
+///
+/// # Safety
+///
+/// The thread must not have exited.
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd"
+))]
+pub(crate) unsafe fn thread_status(thread: usize) -> RealtimeStatus {
+    use pthread::SchedParam;
+    use std::os::raw::c_int;
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    const REALTIME_POLICIES: [c_int; 2] = [1, 2]; // SCHED_FIFO, SCHED_RR
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    const REALTIME_POLICIES: [c_int; 2] = [4, 2];
+    #[cfg(target_os = "freebsd")]
+    const REALTIME_POLICIES: [c_int; 2] = [1, 3];
+
+    let mut policy: c_int = 0;
+    let mut param = SchedParam {
+        sched_priority: 0,
+        _reserved: [0; 7],
+    };
+
+    // Safe because the thread is alive, and both pointers point to valid
+    // memory which is large enough for the values written by the call.
+    let res = unsafe { pthread::pthread_getschedparam(thread, &mut policy, &mut param) };
+
+    if res != 0 {
+        RealtimeStatus::Unknown
+    } else if REALTIME_POLICIES.contains(&policy) {
+        RealtimeStatus::Realtime {
+            priority: param.sched_priority,
+        }
+    } else {
+        RealtimeStatus::Normal
+    }
+}
+
+/// Read the scheduling policy of the thread with the given id.
+///
+/// # Safety
+///
+/// The thread must not have exited.
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd"
+)))]
+pub(crate) unsafe fn thread_status(_thread: usize) -> RealtimeStatus {
+    RealtimeStatus::Unknown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shared_status_round_trips() {
+        let shared = SharedRealtimeStatus::default();
+        assert_eq!(shared.load(), RealtimeStatus::Unknown);
+
+        for status in [
+            RealtimeStatus::Normal,
+            RealtimeStatus::Realtime { priority: 85 },
+            RealtimeStatus::Unknown,
+        ] {
+            shared.store(status);
+            assert_eq!(shared.load(), status);
+        }
+    }
+
+    #[test]
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    fn a_new_thread_is_not_realtime() {
+        let status = std::thread::spawn(|| {
+            // Safe because this is the calling thread.
+            unsafe { thread_status(current_thread_id()) }
+        })
+        .join()
+        .unwrap();
+        assert_eq!(status, RealtimeStatus::Normal);
+    }
+
+    #[test]
+    fn the_recorded_thread_is_taken_once() {
+        let shared = SharedRealtimeStatus::default();
+        assert_eq!(shared.take_thread(), None);
+
+        shared.record_thread(42);
+        assert_eq!(shared.take_thread(), Some(42));
+        assert_eq!(shared.take_thread(), None);
+
+        shared.record_thread(42);
+        shared.store(RealtimeStatus::Normal);
+        shared.reset();
+        assert_eq!(shared.take_thread(), None);
+        assert_eq!(shared.load(), RealtimeStatus::Unknown);
+    }
+}
@@ -0,0 +1,446 @@
+//! Realtime-safe primitives for communicating with the audio thread.
+
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// A bounded, lock-free, single-producer single-consumer queue.
+///
+/// Create one with `SpscRing::new()`, which returns the `Producer` and the
+/// `Consumer` halves of the queue. Each half can be sent to a different
+/// thread. Neither `Producer::push()` nor `Consumer::pop()` lock or allocate,
+/// so they are safe to call from the audio thread.
+///
+/// # Memory ordering
+///
+/// The producer writes an element into its slot and then publishes it with
+/// a `Release` store to the tail index. The consumer reads the tail index
+/// with `Acquire`, so once it sees an element it also sees everything the
+/// producer wrote before pushing it. The same is true in the opposite
+/// direction for the head index, so a slot is never reused by the producer
+/// until the consumer is done reading it.
+///
+/// # Example
+///
+/// ```
+/// use rtaudio::rt::SpscRing;
+///
+/// let (mut tx, mut rx) = SpscRing::new(2);
+///
+/// assert_eq!(rx.pop(), None);
+/// assert_eq!(tx.push(1), Ok(()));
+/// assert_eq!(tx.push(2), Ok(()));
+/// // The queue is full, so the value is handed back.
+/// assert_eq!(tx.push(3), Err(3));
+///
+/// assert_eq!(rx.pop(), Some(1));
+/// assert_eq!(tx.push(3), Ok(()));
+/// assert_eq!(rx.pop(), Some(2));
+/// assert_eq!(rx.pop(), Some(3));
+/// assert_eq!(rx.pop(), None);
+/// ```
+pub struct SpscRing<T> {
+    slots: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    /// `slots.len() - 1`. The length of `slots` is always a power of two.
+    mask: usize,
+    capacity: usize,
+
+    /// The total number of elements that have been popped (wrapping).
+    head: AtomicUsize,
+    /// The total number of elements that have been pushed (wrapping).
+    tail: AtomicUsize,
+}
+
+// Safe because access to each slot is synchronized with the head and tail
+// indexes, and there is only ever a single producer and a single consumer.
+unsafe impl<T: Send> Send for SpscRing<T> {}
+unsafe impl<T: Send> Sync for SpscRing<T> {}
+
+impl<T> SpscRing<T> {
+    /// Create a new queue that can hold up to `capacity` elements.
+    ///
+    /// All memory is allocated up front.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is `0`.
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(capacity: usize) -> (Producer<T>, Consumer<T>) {
+        assert!(capacity > 0, "SpscRing capacity must be greater than 0");
+
+        // Using a power of two means the indexes can simply wrap around
+        // when they overflow.
+        let len = capacity.next_power_of_two();
+        let slots = (0..len)
+            .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+            .collect();
+
+        let ring = Arc::new(Self {
+            slots,
+            mask: len - 1,
+            capacity,
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+        });
+
+        (
+            Producer {
+                ring: Arc::clone(&ring),
+            },
+            Consumer { ring },
+        )
+    }
+
+    /// The maximum number of elements this queue can hold.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The number of elements currently in the queue.
+    ///
+    /// If called while the other half is in use, the value may already
+    /// be out of date.
+    pub fn len(&self) -> usize {
+        // Load the head first so that the tail can never be behind it.
+        let head = self.head.load(Ordering::Acquire);
+        let tail = self.tail.load(Ordering::Acquire);
+        tail.wrapping_sub(head)
+    }
+
+    /// Whether or not the queue is currently empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Drop for SpscRing<T> {
+    fn drop(&mut self) {
+        let mut head = *self.head.get_mut();
+        let tail = *self.tail.get_mut();
+
+        while head != tail {
+            // Safe because every slot between head and tail has been
+            // initialized by the producer and not yet read by the consumer.
+            unsafe {
+                (*self.slots[head & self.mask].get()).assume_init_drop();
+            }
+            head = head.wrapping_add(1);
+        }
+    }
+}
+
+/// The sending half of an `SpscRing`.
+pub struct Producer<T> {
+    ring: Arc<SpscRing<T>>,
+}
+
+impl<T> Producer<T> {
+    /// Push an element onto the queue.
+    ///
+    /// If the queue is full, then the element is returned back.
+    pub fn push(&mut self, value: T) -> Result<(), T> {
+        let ring = &*self.ring;
+
+        // Only the producer writes to the tail.
+        let tail = ring.tail.load(Ordering::Relaxed);
+        let head = ring.head.load(Ordering::Acquire);
+
+        if tail.wrapping_sub(head) >= ring.capacity {
+            return Err(value);
+        }
+
+        // Safe because this slot is not visible to the consumer until the
+        // tail is updated below, and the consumer has finished reading it
+        // (synchronized by the `Acquire` load of the head above).
+        unsafe {
+            (*ring.slots[tail & ring.mask].get()).write(value);
+        }
+
+        ring.tail.store(tail.wrapping_add(1), Ordering::Release);
+
+        Ok(())
+    }
+
+    /// Push elements from an iterator until it is exhausted or the queue is
+    /// full. Elements after that are not taken from the iterator.
+    ///
+    /// Returns the number of elements that were pushed. The elements only
+    /// become visible to the consumer once all of them have been written.
+    ///
+    /// ```
+    /// use rtaudio::rt::SpscRing;
+    ///
+    /// let (mut tx, mut rx) = SpscRing::new(4);
+    ///
+    /// let mut values = [1, 2, 3, 4, 5].into_iter();
+    /// assert_eq!(tx.push_iter(&mut values), 4);
+    /// assert_eq!(values.next(), Some(5));
+    ///
+    /// let mut out = [0; 3];
+    /// assert_eq!(rx.pop_slice(&mut out), 3);
+    /// assert_eq!(out, [1, 2, 3]);
+    /// assert_eq!(rx.pop_slice(&mut out), 1);
+    /// assert_eq!(out[0], 4);
+    /// ```
+    pub fn push_iter<I: IntoIterator<Item = T>>(&mut self, values: I) -> usize {
+        let ring = &*self.ring;
+
+        // Only the producer writes to the tail.
+        let tail = ring.tail.load(Ordering::Relaxed);
+        let head = ring.head.load(Ordering::Acquire);
+
+        let free = ring.capacity - tail.wrapping_sub(head);
+
+        let mut pushed = 0;
+        for value in values.into_iter().take(free) {
+            // Safe for the same reasons as in `Producer::push()`.
+            unsafe {
+                (*ring.slots[tail.wrapping_add(pushed) & ring.mask].get()).write(value);
+            }
+            pushed += 1;
+        }
+
+        ring.tail
+            .store(tail.wrapping_add(pushed), Ordering::Release);
+
+        pushed
+    }
+
+    /// The number of elements which can currently be pushed.
+    pub fn free_len(&self) -> usize {
+        self.ring.capacity.saturating_sub(self.ring.len())
+    }
+
+    /// Whether or not the queue is currently full.
+    pub fn is_full(&self) -> bool {
+        self.ring.len() >= self.ring.capacity
+    }
+
+    /// The maximum number of elements the queue can hold.
+    pub fn capacity(&self) -> usize {
+        self.ring.capacity
+    }
+
+    /// The number of elements currently in the queue.
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+
+    /// Whether or not the queue is currently empty.
+    pub fn is_empty(&self) -> bool {
+        self.ring.is_empty()
+    }
+}
+
+/// The receiving half of an `SpscRing`.
+pub struct Consumer<T> {
+    ring: Arc<SpscRing<T>>,
+}
+
+impl<T> Consumer<T> {
+    /// Pop the oldest element from the queue.
+    ///
+    /// Returns `None` if the queue is empty.
+    pub fn pop(&mut self) -> Option<T> {
+        let ring = &*self.ring;
+
+        // Only the consumer writes to the head.
+        let head = ring.head.load(Ordering::Relaxed);
+        let tail = ring.tail.load(Ordering::Acquire);
+
+        if head == tail {
+            return None;
+        }
+
+        // Safe because this slot was initialized by the producer before the
+        // tail was updated (synchronized by the `Acquire` load of the tail
+        // above), and the producer won't reuse it until the head is updated
+        // below.
+        let value = unsafe { (*ring.slots[head & ring.mask].get()).assume_init_read() };
+
+        ring.head.store(head.wrapping_add(1), Ordering::Release);
+
+        Some(value)
+    }
+
+    /// Pop the oldest elements into `dest`, until it is full or the queue
+    /// is empty.
+    ///
+    /// Returns the number of elements that were popped. See
+    /// `Producer::push_iter()`.
+    pub fn pop_slice(&mut self, dest: &mut [T]) -> usize
+    where
+        T: Copy,
+    {
+        let ring = &*self.ring;
+
+        // Only the consumer writes to the head.
+        let head = ring.head.load(Ordering::Relaxed);
+        let tail = ring.tail.load(Ordering::Acquire);
+
+        let popped = tail.wrapping_sub(head).min(dest.len());
+
+        for (i, d) in dest[..popped].iter_mut().enumerate() {
+            // Safe for the same reasons as in `Consumer::pop()`.
+            *d =
+                unsafe { (*ring.slots[head.wrapping_add(i) & ring.mask].get()).assume_init_read() };
+        }
+
+        ring.head
+            .store(head.wrapping_add(popped), Ordering::Release);
+
+        popped
+    }
+
+    /// The maximum number of elements the queue can hold.
+    pub fn capacity(&self) -> usize {
+        self.ring.capacity
+    }
+
+    /// The number of elements currently in the queue.
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+
+    /// Whether or not the queue is currently empty.
+    pub fn is_empty(&self) -> bool {
+        self.ring.is_empty()
+    }
+}
+
+/// Counts the allocations made by each thread, so that tests can check that
+/// the code which runs on the audio thread does not allocate.
+#[cfg(test)]
+struct CountingAllocator;
+
+#[cfg(test)]
+std::thread_local! {
+    static ALLOCATIONS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+#[cfg(test)]
+unsafe impl std::alloc::GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
+        // `try_with()` since the thread local may already be destroyed
+        // while the thread is exiting.
+        let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+        std::alloc::System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
+        std::alloc::System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: std::alloc::Layout, new_size: usize) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+        std::alloc::System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[cfg(test)]
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// The number of allocations that the current thread made while running `f`.
+#[cfg(test)]
+pub(crate) fn count_allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(|n| n.get());
+    f();
+    ALLOCATIONS.with(|n| n.get()) - before
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_and_pop_do_not_allocate() {
+        let (mut tx, mut rx) = SpscRing::new(4);
+        let mut dest = [0.0f32; 4];
+
+        let allocations = count_allocations(|| {
+            tx.push(1.0).unwrap();
+            tx.push_iter([2.0, 3.0, 4.0, 5.0]);
+            assert_eq!(rx.pop(), Some(1.0));
+            assert_eq!(rx.pop_slice(&mut dest), 3);
+        });
+
+        assert_eq!(allocations, 0);
+    }
+
+    #[test]
+    fn capacity_is_not_rounded_up() {
+        let (mut tx, rx) = SpscRing::new(3);
+        assert_eq!(tx.capacity(), 3);
+        assert_eq!(rx.capacity(), 3);
+
+        assert_eq!(tx.push_iter(0..10), 3);
+        assert!(tx.is_full());
+        assert_eq!(tx.free_len(), 0);
+        assert_eq!(rx.len(), 3);
+        assert_eq!(tx.push(10), Err(10));
+    }
+
+    #[test]
+    fn indexes_wrap_around() {
+        let (mut tx, mut rx) = SpscRing::new(3);
+        let mut out = [0; 2];
+
+        for i in 0..1000 {
+            assert_eq!(tx.push_iter([i * 2, i * 2 + 1]), 2);
+            assert_eq!(rx.pop_slice(&mut out), 2);
+            assert_eq!(out, [i * 2, i * 2 + 1]);
+            assert!(rx.is_empty());
+        }
+    }
+
+    #[test]
+    fn unread_elements_are_dropped() {
+        let value = Arc::new(());
+        let (mut tx, mut rx) = SpscRing::new(4);
+
+        for _ in 0..3 {
+            tx.push(Arc::clone(&value)).unwrap();
+        }
+        drop(rx.pop());
+        assert_eq!(Arc::strong_count(&value), 3);
+
+        drop(tx);
+        drop(rx);
+        assert_eq!(Arc::strong_count(&value), 1);
+    }
+
+    #[test]
+    fn elements_arrive_in_order_across_threads() {
+        const COUNT: u64 = 100_000;
+        let (mut tx, mut rx) = SpscRing::new(64);
+
+        let producer = std::thread::spawn(move || {
+            let mut next = 0;
+            while next < COUNT {
+                if tx.push(next).is_ok() {
+                    next += 1;
+                } else {
+                    std::thread::yield_now();
+                }
+            }
+        });
+
+        let mut expected = 0;
+        let mut out = [0; 16];
+        while expected < COUNT {
+            let popped = rx.pop_slice(&mut out);
+            for &value in &out[..popped] {
+                assert_eq!(value, expected);
+                expected += 1;
+            }
+            if popped == 0 {
+                std::thread::yield_now();
+            }
+        }
+
+        producer.join().unwrap();
+        assert_eq!(rx.pop(), None);
+    }
+}
@@ -0,0 +1,190 @@
+//! `Serialize` and `Deserialize` implementations which can't be derived.
+//!
+//! Flags are stored as a list of flag names (i.e. `["HOG_DEVICE"]`) instead
+//! of raw bits, and an `Api` is stored as its short name (i.e. `"alsa"`).
+//! Missing fields take their default values, and unknown fields are
+//! ignored, so a configuration written by a newer version can still be read.
+//! Unknown flag names are an error.
+//!
+//! ```
+//! use rtaudio::{Api, CallbackPriority, DeviceParams, StreamConfig, StreamFlags};
+//!
+//! let toml_str = r#"
+//!     sample_rate = 44100
+//!
+//!     [options]
+//!     flags = ["MINIMIZE_LATENCY", "HOG_DEVICE"]
+//!     priority = "Realtime"
+//! "#;
+//! let config: StreamConfig = toml::from_str(toml_str).unwrap();
+//! assert_eq!(config.sample_rate, 44100);
+//! assert_eq!(config.buffer_frames, StreamConfig::default().buffer_frames);
+//! assert_eq!(
+//!     config.options.flags,
+//!     StreamFlags::MINIMIZE_LATENCY | StreamFlags::HOG_DEVICE
+//! );
+//! assert_eq!(config.options.priority, CallbackPriority::Realtime);
+//!
+//! let round_trip: StreamConfig = toml::from_str(&toml::to_string(&config).unwrap()).unwrap();
+//! assert_eq!(round_trip, config);
+//! let round_trip: StreamConfig =
+//!     serde_json::from_str(&serde_json::to_string(&config).unwrap()).unwrap();
+//! assert_eq!(round_trip, config);
+//!
+//! let err = toml::from_str::<StreamConfig>("[options]\nflags = [\"HOG\"]").unwrap_err();
+//! assert!(err.to_string().contains("unknown stream flag `HOG`"));
+//!
+//! let params: DeviceParams = serde_json::from_str(r#"{ "device_id": 3 }"#).unwrap();
+//! assert_eq!(params.device_id.map(|id| id.get()), Some(3));
+//! assert_eq!(params.num_channels, DeviceParams::default().num_channels);
+//! assert!(serde_json::from_str::<DeviceParams>(r#"{ "device_id": 0 }"#).is_err());
+//!
+//! let api: Api = serde_json::from_str(r#""wasapi""#).unwrap();
+//! assert_eq!(api, Api::WindowsWASAPI);
+//! assert_eq!(serde_json::to_string(&api).unwrap(), r#""wasapi""#);
+//! ```
+//!
+//! A list of devices can be cached, and reconciled with a fresh scan
+//! using `DeviceInfo::matches()`:
+//!
+//! ```
+//! use rtaudio::{Api, DeviceInfo, Host};
+//!
+//! let host = Host::new(Api::Unspecified).unwrap();
+//! let devices: Vec<DeviceInfo> = host.iter_devices().collect();
+//! let json = serde_json::to_string(&devices).unwrap();
+//!
+//! // In a later run:
+//! let cached: Vec<DeviceInfo> = serde_json::from_str(&json).unwrap();
+//! for device in host.iter_devices() {
+//!     if !cached.iter().any(|c| c.matches(&device)) {
+//!         println!("new device: {}", device);
+//!     }
+//! }
+//! ```
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use bitflags::Flags;
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+use crate::{Api, NativeFormats, StreamFlags};
+
+fn serialize_flags<F: Flags, S: Serializer>(flags: &F, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(flags.iter_names().map(|(name, _)| name))
+}
+
+struct FlagsVisitor<F> {
+    /// The kind of flag, used in error messages.
+    kind: &'static str,
+    _flags: PhantomData<F>,
+}
+
+impl<'de, F: Flags> Visitor<'de> for FlagsVisitor<F> {
+    type Value = F;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a list of {} flag names", self.kind)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<F, A::Error> {
+        let mut flags = F::empty();
+
+        while let Some(name) = seq.next_element::<String>()? {
+            let flag = F::from_name(&name).ok_or_else(|| {
+                let expected: Vec<&str> = F::FLAGS.iter().map(|flag| flag.name()).collect();
+
+                de::Error::custom(format!(
+                    "unknown {} flag `{}`, expected one of {}",
+                    self.kind,
+                    name,
+                    expected.join(", ")
+                ))
+            })?;
+
+            flags.insert(flag);
+        }
+
+        Ok(flags)
+    }
+}
+
+fn deserialize_flags<'de, F: Flags, D: Deserializer<'de>>(
+    deserializer: D,
+    kind: &'static str,
+) -> Result<F, D::Error> {
+    deserializer.deserialize_seq(FlagsVisitor {
+        kind,
+        _flags: PhantomData,
+    })
+}
+
+impl Serialize for StreamFlags {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_flags(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for StreamFlags {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_flags(deserializer, "stream")
+    }
+}
+
+impl Serialize for NativeFormats {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_flags(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for NativeFormats {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_flags(deserializer, "native format")
+    }
+}
+
+impl Serialize for Api {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Api {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DeviceID, DeviceInfo};
+
+    fn device() -> DeviceInfo {
+        DeviceInfo {
+            id: DeviceID::new(129).unwrap(),
+            is_default_input: false,
+            sample_rates: vec![44100, 48000],
+            name: String::from("Kopfhörer (USB-Audio) 🎧"),
+            ..crate::device_info::test_device()
+        }
+    }
+
+    #[test]
+    fn device_info_round_trips() {
+        let devices = vec![device()];
+
+        let json = serde_json::to_string(&devices).unwrap();
+        assert!(json.contains(r#""native_formats":["SINT16","FLOAT32"]"#));
+        assert!(json.contains(r#""id":129"#));
+
+        let cached: Vec<DeviceInfo> = serde_json::from_str(&json).unwrap();
+        assert_eq!(cached, devices);
+        assert!(cached[0].matches(&devices[0]));
+
+        let toml_str = toml::to_string(&devices[0]).unwrap();
+        assert_eq!(toml::from_str::<DeviceInfo>(&toml_str).unwrap(), devices[0]);
+    }
+}
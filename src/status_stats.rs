@@ -0,0 +1,196 @@
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use crate::StreamStatus;
+
+/// Cumulative statistics of the `StreamStatus` passed to the data callback.
+///
+/// A process call with any status flag set is counted as an xrun. See
+/// `StreamHandle::status_stats()`.
+///
+/// ```
+/// use rtaudio::StatusStats;
+///
+/// let stats = StatusStats {
+///     calls: 24000,
+///     xruns: 3,
+///     input_overflows: 1,
+///     output_underflows: 2,
+///     last_xrun_time: Some(12.5),
+///     longest_clean_run: 60.0,
+/// };
+/// assert_eq!(
+///     stats.to_string(),
+///     "24000 process calls, 3 xruns (2 output underflows, 1 input overflows), \
+///      last xrun at 12.500 s, longest xrun-free run 60.000 s"
+/// );
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct StatusStats {
+    /// The number of process calls.
+    pub calls: u64,
+    /// The number of process calls with any status flag set.
+    pub xruns: u64,
+    /// The number of process calls with `StreamStatus::INPUT_OVERFLOW` set.
+    pub input_overflows: u64,
+    /// The number of process calls with `StreamStatus::OUTPUT_UNDERFLOW`
+    /// set.
+    pub output_underflows: u64,
+    /// The stream time (see `StreamInfo::stream_time`) of the most recent
+    /// xrun, or `None` if there has not been one.
+    pub last_xrun_time: Option<f64>,
+    /// The longest stretch of stream time without an xrun in seconds,
+    /// including the current one.
+    ///
+    /// A stretch ends when the stream is stopped.
+    pub longest_clean_run: f64,
+}
+
+impl fmt::Display for StatusStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} process calls, {} xruns ({} output underflows, {} input overflows)",
+            self.calls, self.xruns, self.output_underflows, self.input_overflows
+        )?;
+
+        match self.last_xrun_time {
+            Some(time) => write!(f, ", last xrun at {:.3} s", time)?,
+            None => write!(f, ", no xruns yet")?,
+        }
+
+        write!(f, ", longest xrun-free run {:.3} s", self.longest_clean_run)
+    }
+}
+
+/// The raw value of `last_xrun_time` when there has not been an xrun.
+const NO_XRUN: u64 = u64::MAX;
+
+/// The status statistics which are published by the audio thread.
+///
+/// Only the audio thread writes to this (apart from a reset), so each value
+/// is simply stored.
+#[derive(Debug)]
+pub(crate) struct SharedStatusStats {
+    calls: AtomicU64,
+    xruns: AtomicU64,
+    input_overflows: AtomicU64,
+    output_underflows: AtomicU64,
+    last_xrun_time: AtomicU64,
+    longest_clean_run: AtomicU64,
+    /// Set by the stream handle to ask the audio thread to reset its
+    /// statistics at the start of the next process call.
+    reset_requested: AtomicBool,
+}
+
+impl Default for SharedStatusStats {
+    fn default() -> Self {
+        Self {
+            calls: AtomicU64::new(0),
+            xruns: AtomicU64::new(0),
+            input_overflows: AtomicU64::new(0),
+            output_underflows: AtomicU64::new(0),
+            last_xrun_time: AtomicU64::new(NO_XRUN),
+            longest_clean_run: AtomicU64::new(0.0f64.to_bits()),
+            reset_requested: AtomicBool::new(false),
+        }
+    }
+}
+
+impl SharedStatusStats {
+    pub(crate) fn snapshot(&self) -> StatusStats {
+        let calls = self.calls.load(Ordering::Acquire);
+
+        let last_xrun_time = match self.last_xrun_time.load(Ordering::Relaxed) {
+            NO_XRUN => None,
+            bits => Some(f64::from_bits(bits)),
+        };
+
+        StatusStats {
+            calls,
+            xruns: self.xruns.load(Ordering::Relaxed),
+            input_overflows: self.input_overflows.load(Ordering::Relaxed),
+            output_underflows: self.output_underflows.load(Ordering::Relaxed),
+            last_xrun_time,
+            longest_clean_run: f64::from_bits(self.longest_clean_run.load(Ordering::Relaxed)),
+        }
+    }
+
+    /// Reset the statistics. If the stream is running, the audio thread
+    /// resets its own statistics at the start of the next process call.
+    pub(crate) fn request_reset(&self) {
+        self.reset_requested.store(true, Ordering::Relaxed);
+        self.publish(&StatusStats::default());
+    }
+
+    fn publish(&self, stats: &StatusStats) {
+        self.xruns.store(stats.xruns, Ordering::Relaxed);
+        self.input_overflows
+            .store(stats.input_overflows, Ordering::Relaxed);
+        self.output_underflows
+            .store(stats.output_underflows, Ordering::Relaxed);
+        self.last_xrun_time.store(
+            stats.last_xrun_time.map(f64::to_bits).unwrap_or(NO_XRUN),
+            Ordering::Relaxed,
+        );
+        self.longest_clean_run
+            .store(stats.longest_clean_run.to_bits(), Ordering::Relaxed);
+        // Publish this last, so that a reader which sees a count also sees
+        // the values which were stored along with it.
+        self.calls.store(stats.calls, Ordering::Release);
+    }
+}
+
+/// The status statistics as they are accumulated on the audio thread.
+#[derive(Debug, Default)]
+pub(crate) struct StatusAccumulator {
+    stats: StatusStats,
+    /// The stream time at which the current xrun-free run started, or
+    /// `None` if there has not been a process call since the stream was
+    /// started.
+    run_start: Option<f64>,
+}
+
+impl StatusAccumulator {
+    /// Start a new xrun-free run at the next process call. This must only be
+    /// called while the stream is not running.
+    pub(crate) fn restart_run(&mut self) {
+        self.run_start = None;
+    }
+
+    /// Record the status of a single process call.
+    ///
+    /// This does not lock or allocate.
+    pub(crate) fn record(
+        &mut self,
+        status: StreamStatus,
+        stream_time: f64,
+        shared: &SharedStatusStats,
+    ) {
+        if shared.reset_requested.swap(false, Ordering::Relaxed) {
+            *self = Self::default();
+        }
+
+        let run_start = *self.run_start.get_or_insert(stream_time);
+
+        self.stats.calls += 1;
+
+        if status.is_empty() {
+            self.stats.longest_clean_run =
+                self.stats.longest_clean_run.max(stream_time - run_start);
+        } else {
+            self.stats.xruns += 1;
+            if status.contains(StreamStatus::INPUT_OVERFLOW) {
+                self.stats.input_overflows += 1;
+            }
+            if status.contains(StreamStatus::OUTPUT_UNDERFLOW) {
+                self.stats.output_underflows += 1;
+            }
+
+            self.stats.last_xrun_time = Some(stream_time);
+            self.run_start = Some(stream_time);
+        }
+
+        shared.publish(&self.stats);
+    }
+}
@@ -1,10 +1,31 @@
+use std::cell::UnsafeCell;
 use std::ffi::CStr;
 use std::os::raw::{c_int, c_uint, c_void};
+use std::panic::AssertUnwindSafe;
 use std::pin::Pin;
-use std::sync::Mutex;
-
-use crate::error::{RtAudioError, RtAudioErrorType};
-use crate::{Buffers, DeviceParams, Host, SampleFormat, StreamFlags, StreamOptions, StreamStatus};
+use std::sync::atomic::{
+    AtomicBool, AtomicI32, AtomicPtr, AtomicU64, AtomicU8, AtomicUsize, Ordering,
+};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError};
+use std::thread::{JoinHandle, Thread};
+use std::time::{Duration, Instant};
+
+use crate::error::{
+    Error, ErrorContext, ErrorDevice, ErrorOrigin, HostError, LastError, RtAudioErrorType,
+    RtAudioWarning, WarningHandler,
+};
+#[cfg(feature = "async")]
+use crate::events::{EventQueue, StreamEvent};
+use crate::realtime::SharedRealtimeStatus;
+use crate::rt::{Consumer, Producer, SpscRing};
+use crate::status_stats::{SharedStatusStats, StatusAccumulator};
+use crate::timing::{SharedTiming, TimingAccumulator};
+use crate::{
+    Api, BufferSizePolicy, Buffers, CallbackBudget, CallbackOverrun, CallbackTiming, DeviceInfo,
+    DeviceParams, Host, InterleaveMode, Negotiated, NegotiationReport, OutputLimiter, ParamProblem,
+    RealtimeStatus, Role, SampleFormat, SilenceWatchdog, StatusStats, StreamConfig, StreamFlags,
+    StreamStatus, StreamSummary, SummaryDevice,
+};
 
 /// Information about a running RtAudio stream.
 #[derive(Debug, Clone, Default)]
@@ -14,6 +35,20 @@ pub struct StreamInfo {
     /// The number of input audio channels.
     pub in_channels: usize,
 
+    /// The index of the first channel of the output device which is used by
+    /// the stream.
+    ///
+    /// This is the requested `DeviceParams::first_channel`. When the stream
+    /// is opened, it is checked against the number of channels that the
+    /// device provides, and opening fails if the requested channels do not
+    /// exist (instead of the backend silently using different channels).
+    pub out_first_channel: usize,
+    /// The index of the first channel of the input device which is used by
+    /// the stream.
+    ///
+    /// See `StreamInfo::out_first_channel`.
+    pub in_first_channel: usize,
+
     /// The sample format.
     pub sample_format: SampleFormat,
     /// The sample rate.
@@ -34,6 +69,176 @@ pub struct StreamInfo {
 
     /// The number of seconds that have elapsed since the stream was started.
     pub stream_time: f64,
+
+    /// The number of frames that have been processed since the stream was
+    /// started (the absolute index of the first frame in the current
+    /// buffers).
+    ///
+    /// Unlike `stream_time`, this does not lose precision in long-running
+    /// streams. This is reset to `0` every time the stream is started.
+    pub frame_counter: u64,
+}
+
+impl StreamInfo {
+    /// Whether or not this is a duplex stream where the number of input
+    /// channels differs from the number of output channels.
+    ///
+    /// In this case, the input buffer cannot be copied directly into the
+    /// output buffer. Use `Buffers::route_input_to_output()` instead.
+    pub fn channel_count_mismatch(&self) -> bool {
+        self.in_channels > 0 && self.out_channels > 0 && self.in_channels != self.out_channels
+    }
+
+    /// How the samples are laid out in the buffers (the same as
+    /// `StreamInfo::deinterleaved`).
+    pub fn interleave_mode(&self) -> InterleaveMode {
+        if self.deinterleaved {
+            InterleaveMode::Planar
+        } else {
+            InterleaveMode::Interleaved
+        }
+    }
+}
+
+/// A breakdown of where the latency of a stream comes from.
+///
+/// All latencies are in frames. Fields are `None` when the API does not
+/// report the value:
+///
+/// * `total` is populated on the APIs which report latency (CoreAudio,
+///   ALSA, Jack, ASIO, and WASAPI). See `ApiCapabilities::reports_latency`.
+/// * RtAudio only reports the sum of the input and output latencies, so
+///   `input` and `output` are only populated for input-only and output-only
+///   streams respectively.
+/// * `num_buffers` is only populated on the APIs which use the `num_buffers`
+///   stream option (ALSA, PulseAudio, OSS, and DirectSound). Note that
+///   RtAudio does not report the number of buffers that was actually used,
+///   so this is the number of buffers that was requested.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LatencyBreakdown {
+    /// The total latency of the stream.
+    pub total: Option<usize>,
+    /// The latency of the input side of the stream.
+    pub input: Option<usize>,
+    /// The latency of the output side of the stream.
+    pub output: Option<usize>,
+    /// The number of buffers used by the backend.
+    pub num_buffers: Option<u32>,
+}
+
+/// The state of a stream.
+///
+/// This is retrieved with `StreamHandle::state()`, or from any thread with
+/// a `StreamStateObserver`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamState {
+    /// The stream has been closed (or dropped).
+    Closed,
+    /// The stream is open but not running.
+    Open,
+    /// The stream is running.
+    Running,
+    /// An error occurred which caused the stream to stop.
+    ///
+    /// The stream should be closed or dropped, or it can be started again
+    /// (which may also fail).
+    Errored { type_: RtAudioErrorType },
+}
+
+impl StreamState {
+    const CLOSED: i32 = -1;
+    const OPEN: i32 = -2;
+    const RUNNING: i32 = -3;
+
+    fn to_raw(&self) -> i32 {
+        match self {
+            StreamState::Closed => Self::CLOSED,
+            StreamState::Open => Self::OPEN,
+            StreamState::Running => Self::RUNNING,
+            // Error codes are always positive.
+            StreamState::Errored { type_ } => type_.clone() as i32,
+        }
+    }
+
+    fn from_raw(raw: i32) -> Self {
+        match raw {
+            Self::CLOSED => StreamState::Closed,
+            Self::OPEN => StreamState::Open,
+            Self::RUNNING => StreamState::Running,
+            raw => StreamState::Errored {
+                type_: RtAudioErrorType::from_raw(raw as rtaudio_sys::rtaudio_error_t)
+                    .unwrap_or(RtAudioErrorType::Unknown),
+            },
+        }
+    }
+}
+
+/// Observes the state of a stream from any thread.
+///
+/// This is retrieved with `StreamHandle::state_observer()`. Unlike the
+/// `StreamHandle`, this can be sent to and shared between threads (i.e. a
+/// UI thread), and it stays valid after the stream is closed.
+#[derive(Clone)]
+pub struct StreamStateObserver {
+    shared: Arc<StreamShared>,
+}
+
+impl StreamStateObserver {
+    /// The current state of the stream.
+    pub fn state(&self) -> StreamState {
+        self.shared.state()
+    }
+
+    /// The number of frames that have been processed since the stream was
+    /// last started (see `StreamHandle::frame_counter()`).
+    pub fn frame_counter(&self) -> u64 {
+        self.shared.frame_counter.load(Ordering::Relaxed)
+    }
+
+    /// The number of times the output has gone silent for longer than the
+    /// threshold of the silence watchdog (see
+    /// `StreamOptions::silence_watchdog`).
+    ///
+    /// This is always `0` if the watchdog is disabled.
+    pub fn silence_events(&self) -> usize {
+        self.shared.silence_events.load(Ordering::Relaxed)
+    }
+
+    /// Whether the output is currently silent for longer than the threshold
+    /// of the silence watchdog (see `StreamOptions::silence_watchdog`).
+    ///
+    /// This is always `false` if the watchdog is disabled.
+    pub fn is_output_silent(&self) -> bool {
+        self.shared.output_silent.load(Ordering::Relaxed)
+    }
+
+    /// A summary of how long the data callback has taken to run since the
+    /// stream was last started (see `StreamOptions::callback_budget`).
+    ///
+    /// This is always empty if the measurement is disabled.
+    pub fn callback_timing(&self) -> CallbackTiming {
+        self.shared.timing.snapshot()
+    }
+
+    /// Cumulative statistics of the `StreamStatus` passed to the data
+    /// callback (see `StreamHandle::status_stats()`).
+    pub fn status_stats(&self) -> StatusStats {
+        self.shared.status.snapshot()
+    }
+
+    /// How the thread which runs the data callback is scheduled (see
+    /// `StreamHandle::realtime_status()`).
+    pub fn realtime_status(&self) -> RealtimeStatus {
+        self.shared.realtime.load()
+    }
+}
+
+impl std::fmt::Debug for StreamStateObserver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StreamStateObserver")
+            .field("state", &self.state())
+            .finish()
+    }
 }
 
 /// A handle to an opened RtAudio stream.
@@ -45,7 +250,21 @@ pub struct StreamInfo {
 pub struct StreamHandle {
     info: StreamInfo,
     raw: rtaudio_sys::rtaudio_t,
+    /// Whether or not the RtAudio instance is destroyed when this is
+    /// dropped (see `Host::from_raw()`).
+    owned: bool,
     started: bool,
+    api: Api,
+    error_origin: ErrorOrigin,
+    config: StreamConfig,
+    negotiation_report: NegotiationReport,
+    shared: Arc<StreamShared>,
+    warning_handler: Option<WarningHandler>,
+    /// This is `None` if the warnings are delivered by the notifier thread.
+    warnings: Option<Consumer<WarningEntry>>,
+    overruns: Consumer<CallbackOverrun>,
+    error_sink: Arc<ErrorSink>,
+    notifier: Option<JoinHandle<()>>,
 
     cb_context: Pin<Box<CallbackContext>>,
 }
@@ -55,29 +274,120 @@ impl StreamHandle {
         mut host: Host,
         output_device: Option<DeviceParams>,
         input_device: Option<DeviceParams>,
-        sample_format: SampleFormat,
-        sample_rate: u32,
-        buffer_frames: u32,
-        options: StreamOptions,
+        config: StreamConfig,
         error_callback: E,
-    ) -> Result<StreamHandle, (Host, RtAudioError)>
+    ) -> Result<StreamHandle, HostError>
     where
-        E: FnOnce(RtAudioError) + Send + 'static,
+        E: FnOnce(Error) + Send + 'static,
     {
-        assert!(!host.raw.is_null());
+        if host.raw.is_null() {
+            return Err(HostError {
+                host,
+                error: Error::InvalidUse {
+                    msg: Some("the RtAudio instance has already been destroyed".into()),
+                    context: Some(ErrorContext::OpenStream),
+                    origin: None,
+                },
+            });
+        }
         let raw = host.raw;
 
+        let api = host.api();
+        let StreamConfig {
+            sample_format,
+            sample_rate,
+            buffer_frames,
+            buffer_size_policy,
+            rate_policy,
+            options,
+        } = config;
+
+        for warning in options.validate_for(api) {
+            log::warn!("{}", warning);
+        }
+        options.api_specific.log_ignored(api);
+
+        let output_device = match output_device
+            .map(|p| host.resolve_device_params(p, Role::Output))
+            .transpose()
+        {
+            Ok(p) => p,
+            Err(e) => {
+                return Err(HostError {
+                    host,
+                    error: e.with_origin(ErrorOrigin::new(api)),
+                })
+            }
+        };
+        let input_device = match input_device
+            .map(|p| host.resolve_device_params(p, Role::Input))
+            .transpose()
+        {
+            Ok(p) => p,
+            Err(e) => {
+                return Err(HostError {
+                    host,
+                    error: e.with_origin(ErrorOrigin::new(api)),
+                })
+            }
+        };
+
+        let output_info = lookup_device(&host, output_device);
+        let input_info = lookup_device(&host, input_device);
+
+        let error_origin = ErrorOrigin {
+            api,
+            output_device: error_device(output_device, output_info.as_ref()),
+            input_device: error_device(input_device, input_info.as_ref()),
+        };
+
+        if !options.skip_channel_validation {
+            for (params, device, role) in [
+                (output_device, &output_info, Role::Output),
+                (input_device, &input_info, Role::Input),
+            ] {
+                if let Err(e) = check_channel_range(params, device.as_ref(), role) {
+                    return Err(HostError {
+                        host,
+                        error: e.with_origin(error_origin),
+                    });
+                }
+            }
+        }
+
+        let open_sample_rate =
+            match rate_policy.resolve(sample_rate, output_info.as_ref(), input_info.as_ref()) {
+                Ok(sr) => sr,
+                Err(e) => {
+                    return Err(HostError {
+                        host,
+                        error: e.with_origin(error_origin),
+                    })
+                }
+            };
+
         let mut raw_options = match options.to_raw() {
             Ok(o) => o,
-            Err(e) => return Err((host, e)),
+            Err(e) => {
+                return Err(HostError {
+                    host,
+                    error: e.with_origin(error_origin),
+                })
+            }
         };
+        raw_options.flags = options.effective_flags(api).bits();
+        raw_options.priority = options.priority.to_raw(api).1 as c_int;
+        raw_options.num_buffers = options.num_buffers_for(api) as c_uint;
 
         let mut info = StreamInfo {
             out_channels: output_device.map(|p| p.num_channels as usize).unwrap_or(0),
             in_channels: input_device.map(|p| p.num_channels as usize).unwrap_or(0),
 
+            out_first_channel: output_device.map(|p| p.first_channel as usize).unwrap_or(0),
+            in_first_channel: input_device.map(|p| p.first_channel as usize).unwrap_or(0),
+
             sample_format,
-            sample_rate, // This will be overwritten later.
+            sample_rate: open_sample_rate, // This will be overwritten later.
 
             max_frames: buffer_frames as usize, // This will be overwritten later.
 
@@ -86,11 +396,27 @@ impl StreamHandle {
             latency: None, // This will be overwritten later.
 
             stream_time: 0.0,
+
+            frame_counter: 0,
         };
 
+        let shared = Arc::new(StreamShared::default());
+        let (warnings_tx, warnings_rx) = SpscRing::new(WARNING_QUEUE_CAPACITY);
+        let (overruns_tx, overruns_rx) = SpscRing::new(OVERRUN_QUEUE_CAPACITY);
+        shared.set_state(StreamState::Open);
+
         let mut cb_context = Box::pin(CallbackContext {
             info: info.clone(),
             cb: Box::new(|_, _, _| {}), // This will be replaced later.
+            shared: Arc::clone(&shared),
+            output_limiter: options.output_limiter,
+            silence_watchdog: options.silence_watchdog,
+            silent_blocks: 0,
+            callback_budget: options.callback_budget,
+            timing: TimingAccumulator::default(),
+            status: StatusAccumulator::default(),
+            overruns: overruns_tx,
+            realtime_recorded: false,
         });
 
         let cb_context_ptr: *mut CallbackContext = &mut *cb_context;
@@ -111,23 +437,33 @@ impl StreamHandle {
                 std::ptr::null_mut()
             };
 
-        {
-            let mut cb_singleton = ERROR_CB_SINGLETON.lock().unwrap();
-
-            if cb_singleton.cb.is_some() {
-                return Err((
-                    host,
-                    RtAudioError {
-                        type_: RtAudioErrorType::InvalidUse,
-                        msg: Some("Only one RtAudio stream can exist at a time".into()),
-                    },
-                ));
-            }
+        let error_sink = Arc::new(ErrorSink::new(
+            raw,
+            Arc::clone(&shared),
+            warnings_tx,
+            options.deliver_warnings,
+        ));
+
+        // If the warnings are delivered by the notifier thread, then it
+        // becomes the consumer of the warning queue.
+        let (notifier_warnings, warnings_rx) = if options.deliver_warnings {
+            (Some(warnings_rx), None)
+        } else {
+            (None, Some(warnings_rx))
+        };
 
-            cb_singleton.cb = Some(Box::new(error_callback));
+        if !error_sink.install() {
+            return Err(HostError {
+                host,
+                error: Error::InvalidUse {
+                    msg: Some("Only one RtAudio stream can exist at a time".into()),
+                    context: Some(ErrorContext::OpenStream),
+                    origin: Some(Box::new(error_origin)),
+                },
+            });
         }
 
-        let mut buffer_frames_res = buffer_frames as c_uint;
+        let mut buffer_frames_res = buffer_size_policy.apply(buffer_frames) as c_uint;
 
         // Safe because we have checked that `raw` is not null, we have
         // constructed the `output_params` and `input_params` pointers
@@ -140,7 +476,7 @@ impl StreamHandle {
                 output_device_ptr,
                 input_device_ptr,
                 sample_format.to_raw(),
-                sample_rate as c_uint,
+                open_sample_rate as c_uint,
                 &mut buffer_frames_res,
                 Some(crate::stream::raw_data_callback),
                 cb_context_ptr as *mut c_void,
@@ -148,15 +484,16 @@ impl StreamHandle {
                 Some(raw_error_callback),
             )
         };
-        if let Err(e) = crate::check_for_error(raw) {
+        if let Err(e) = host.check_for_error(ErrorContext::OpenStream) {
             // Safe because we have checked that `raw` is not null.
             unsafe {
                 rtaudio_sys::rtaudio_close_stream(raw);
             }
-            {
-                ERROR_CB_SINGLETON.lock().unwrap().cb = None;
-            }
-            return Err((host, e));
+            error_sink.uninstall();
+            return Err(HostError {
+                host,
+                error: e.with_origin(error_origin),
+            });
         }
 
         // Get info about the stream.
@@ -168,15 +505,16 @@ impl StreamHandle {
                 info.latency = Some(latency as usize);
             }
         }
-        if let Err(e) = crate::check_for_error(raw) {
+        if let Err(e) = host.check_for_error(ErrorContext::QueryLatency) {
             // Safe because we have checked that `raw` is not null.
             unsafe {
                 rtaudio_sys::rtaudio_close_stream(raw);
             }
-            {
-                ERROR_CB_SINGLETON.lock().unwrap().cb = None;
-            }
-            return Err((host, e));
+            error_sink.uninstall();
+            return Err(HostError {
+                host,
+                error: e.with_origin(error_origin),
+            });
         }
 
         // Safe because we have checked that `raw` is not null.
@@ -186,23 +524,110 @@ impl StreamHandle {
                 info.sample_rate = sr as u32;
             }
         };
-        if let Err(e) = crate::check_for_error(raw) {
+        if let Err(e) = host.check_for_error(ErrorContext::QuerySampleRate) {
             // Safe because we have checked that `raw` is not null.
             unsafe {
                 rtaudio_sys::rtaudio_close_stream(raw);
             }
-            {
-                ERROR_CB_SINGLETON.lock().unwrap().cb = None;
-            }
-            return Err((host, e));
+            error_sink.uninstall();
+            return Err(HostError {
+                host,
+                error: e.with_origin(error_origin),
+            });
         }
 
         cb_context.info = info.clone();
 
+        let format_conversion = |device: Option<DeviceInfo>| {
+            let device = device?;
+            Some(!device.supports_native_format(sample_format))
+        };
+
+        let negotiation_report = NegotiationReport {
+            sample_rate: Negotiated {
+                requested: sample_rate,
+                actual: info.sample_rate,
+            },
+            rate_policy,
+            buffer_frames: Negotiated {
+                requested: buffer_frames,
+                actual: buffer_frames_res,
+            },
+            buffer_size_policy,
+            requested_num_buffers: options.num_buffers_for(api),
+            num_buffers_from_api_default: options.num_buffers.is_none(),
+            actual_num_buffers: None,
+            sample_format,
+            output_format_conversion: format_conversion(output_info),
+            input_format_conversion: format_conversion(input_info),
+        };
+
+        let notifier = match spawn_notifier(
+            Arc::clone(&error_sink),
+            error_origin.clone(),
+            Box::new(error_callback),
+            notifier_warnings.map(|warnings| NotifierWarnings {
+                warnings,
+                handler: host.warning_handler.clone(),
+            }),
+        ) {
+            Ok(n) => n,
+            Err(e) => {
+                // Safe because we have checked that `raw` is not null.
+                unsafe {
+                    rtaudio_sys::rtaudio_close_stream(raw);
+                }
+                error_sink.uninstall();
+                return Err(HostError {
+                    host,
+                    error: Error::ThreadError {
+                        msg: Some(
+                            format!("failed to start the error notifier thread: {}", e).into(),
+                        ),
+                        context: Some(ErrorContext::OpenStream),
+                        origin: Some(Box::new(error_origin)),
+                    },
+                });
+            }
+        };
+
+        if buffer_size_policy != BufferSizePolicy::Auto && buffer_frames > 0 {
+            let requested = u64::from(buffer_frames);
+            let actual = u64::from(buffer_frames_res);
+
+            if actual > requested * 2 || actual * 2 < requested {
+                log::warn!(
+                    "RtAudio: requested a buffer size of {} frames, but the stream uses {} frames",
+                    buffer_frames,
+                    buffer_frames_res
+                );
+            }
+        }
+
+        log::info!("Opened RtAudio stream: {}", negotiation_report);
+
         let stream = Self {
             info,
             raw,
+            owned: host.owned,
             started: false,
+            api,
+            error_origin,
+            config: StreamConfig {
+                sample_format,
+                sample_rate,
+                buffer_frames,
+                buffer_size_policy,
+                rate_policy,
+                options,
+            },
+            negotiation_report,
+            shared,
+            warning_handler: host.warning_handler.take(),
+            warnings: warnings_rx,
+            overruns: overruns_rx,
+            error_sink,
+            notifier: Some(notifier),
             cb_context,
         };
 
@@ -212,23 +637,344 @@ impl StreamHandle {
         Ok(stream)
     }
 
+    /// The raw RtAudio instance which the stream is open on.
+    ///
+    /// There is no way to release it from the stream, since RtAudio keeps
+    /// a pointer to the callback context of the stream. Use
+    /// `StreamHandle::close()` to get the host back, and then
+    /// `Host::into_raw()`.
+    ///
+    /// The stream must not be started, stopped, closed or destroyed through
+    /// the raw instance. Querying it (i.e. `rtaudio_get_stream_latency()`)
+    /// is fine, but note that its error state is shared with this stream.
+    pub fn as_raw(&self) -> rtaudio_sys::rtaudio_t {
+        self.raw
+    }
+
     /// Information about the stream.
     pub fn info(&self) -> &StreamInfo {
         &self.info
     }
 
+    /// The configuration that was requested when opening this stream.
+    pub fn requested_config(&self) -> &StreamConfig {
+        &self.config
+    }
+
+    /// The configuration that is actually used by this stream.
+    ///
+    /// The sample rate and buffer size may differ from the requested
+    /// configuration if the requested values were not supported.
+    pub fn actual_config(&self) -> StreamConfig {
+        StreamConfig {
+            sample_format: self.info.sample_format,
+            sample_rate: self.info.sample_rate,
+            buffer_frames: self.info.max_frames as u32,
+            buffer_size_policy: self.config.buffer_size_policy,
+            rate_policy: self.config.rate_policy,
+            options: self.config.options.clone(),
+        }
+    }
+
+    /// A record of the stream parameters that were requested versus the
+    /// parameters that are actually used.
+    pub fn negotiation_report(&self) -> &NegotiationReport {
+        &self.negotiation_report
+    }
+
+    /// The effective configuration of this stream, including the API and
+    /// the devices, for logs and bug reports. See `StreamSummary`.
+    pub fn summary(&self) -> StreamSummary {
+        let device = |device: &Option<ErrorDevice>, channels, first_channel| {
+            device.as_ref().map(|d| SummaryDevice {
+                id: d.id,
+                name: d.name.clone(),
+                channels,
+                first_channel,
+            })
+        };
+
+        StreamSummary {
+            api: self.api,
+            output: device(
+                &self.error_origin.output_device,
+                self.info.out_channels,
+                self.info.out_first_channel,
+            ),
+            input: device(
+                &self.error_origin.input_device,
+                self.info.in_channels,
+                self.info.in_first_channel,
+            ),
+            sample_format: self.info.sample_format,
+            sample_rate: self.negotiation_report.sample_rate,
+            buffer_frames: self.negotiation_report.buffer_frames,
+            latency: self.info.latency,
+            interleave_mode: self.info.interleave_mode(),
+            flags: self.config.options.effective_flags(self.api),
+        }
+    }
+
+    /// The number of frames that have been processed since the stream was
+    /// last started.
+    ///
+    /// This is the same value as `StreamInfo::frame_counter` in the data
+    /// callback, except that it also includes the frames of the most recent
+    /// process call.
+    pub fn frame_counter(&self) -> u64 {
+        self.shared.frame_counter.load(Ordering::Relaxed)
+    }
+
+    /// The total number of out-of-range output samples that were caught by
+    /// the output limiter (see `StreamOptions::output_limiter`).
+    pub fn limited_samples(&self) -> usize {
+        self.shared.limited_samples.load(Ordering::Relaxed)
+    }
+
+    /// The number of times the output has gone silent for longer than the
+    /// threshold of the silence watchdog (see
+    /// `StreamOptions::silence_watchdog`).
+    ///
+    /// This is always `0` if the watchdog is disabled.
+    pub fn silence_events(&self) -> usize {
+        self.shared.silence_events.load(Ordering::Relaxed)
+    }
+
+    /// Whether the output is currently silent for longer than the threshold
+    /// of the silence watchdog (see `StreamOptions::silence_watchdog`).
+    ///
+    /// This is always `false` if the watchdog is disabled.
+    pub fn is_output_silent(&self) -> bool {
+        self.shared.output_silent.load(Ordering::Relaxed)
+    }
+
+    /// A summary of how long the data callback has taken to run since the
+    /// stream was last started (see `StreamOptions::callback_budget`).
+    ///
+    /// This is always empty if the measurement is disabled.
+    pub fn callback_timing(&self) -> CallbackTiming {
+        self.shared.timing.snapshot()
+    }
+
+    /// Cumulative statistics of the `StreamStatus` passed to the data
+    /// callback, i.e. how many underflows and overflows there have been.
+    ///
+    /// Unlike `StreamHandle::callback_timing()`, these are kept when the
+    /// stream is restarted. Use `StreamHandle::reset_status_stats()` to
+    /// reset them.
+    pub fn status_stats(&self) -> StatusStats {
+        self.shared.status.snapshot()
+    }
+
+    /// Reset the statistics returned by `StreamHandle::status_stats()`.
+    ///
+    /// If the stream is running, the audio thread resets its own copy at the
+    /// start of the next process call.
+    pub fn reset_status_stats(&mut self) {
+        self.shared.status.request_reset();
+    }
+
+    /// How the thread which runs the data callback is scheduled, i.e.
+    /// whether a `CallbackPriority` was actually honored.
+    ///
+    /// The data callback records its thread the first time it runs after
+    /// the stream is started, and the notifier thread reads the scheduling
+    /// of that thread shortly afterwards (within 100 ms). It is
+    /// `RealtimeStatus::Unknown` until then.
+    pub fn realtime_status(&self) -> RealtimeStatus {
+        self.shared.realtime.load()
+    }
+
+    /// The current state of the stream.
+    pub fn state(&self) -> StreamState {
+        self.shared.state()
+    }
+
+    /// Retrieve an observer which can be used to poll the state of this
+    /// stream from any thread.
+    pub fn state_observer(&self) -> StreamStateObserver {
+        StreamStateObserver {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+
+    /// A handle which stops this stream from inside its data callback.
+    pub(crate) fn callback_stop(&self) -> CallbackStop {
+        CallbackStop {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+
+    /// Take the warnings that RtAudio reported while the stream was running.
+    ///
+    /// These warnings (i.e. recovered underruns) are reported from the audio
+    /// thread, so they are queued instead of being sent to the warning
+    /// handler right away. Any warnings which are not taken are sent to the
+    /// warning handler (or printed to the log) when the stream is stopped,
+    /// or when `StreamHandle::flush_warnings()` is called.
+    ///
+    /// If `StreamOptions::deliver_warnings` is set, then the warnings are
+    /// sent to the warning handler as they arrive instead, and this always
+    /// returns an empty list.
+    pub fn take_warnings(&mut self) -> Vec<RtAudioWarning> {
+        match &mut self.warnings {
+            Some(warnings) => drain_warnings(warnings),
+            None => Vec::new(),
+        }
+    }
+
+    /// Take the process calls in which the data callback took longer than
+    /// allowed by `StreamOptions::callback_budget`.
+    ///
+    /// Up to 32 overruns are kept until they are taken. Any further
+    /// overruns are not recorded here, but every overrun is counted in
+    /// `CallbackTiming::overruns`.
+    pub fn take_callback_overruns(&mut self) -> Vec<CallbackOverrun> {
+        std::iter::from_fn(|| self.overruns.pop()).collect()
+    }
+
+    /// Send the warnings that RtAudio reported while the stream was running
+    /// to the warning handler (or print them to the log if there is no
+    /// handler).
+    pub fn flush_warnings(&mut self) {
+        for warning in self.take_warnings() {
+            crate::error::handle_warning(warning, self.warning_handler.as_ref());
+        }
+    }
+
+    /// The number of warnings from the audio thread that were dropped because
+    /// the queue was full.
+    pub fn dropped_warnings(&self) -> usize {
+        self.shared.dropped_warnings.load(Ordering::Relaxed)
+    }
+
+    /// The API used by this stream.
+    pub fn api(&self) -> Api {
+        self.api
+    }
+
+    /// A breakdown of where the latency of this stream comes from.
+    ///
+    /// See `LatencyBreakdown` for which fields are populated on which APIs.
+    pub fn backend_latency_breakdown(&self) -> LatencyBreakdown {
+        let caps = self.api.capabilities();
+
+        let total = self.info.latency;
+        let input = if self.info.out_channels == 0 {
+            total
+        } else {
+            None
+        };
+        let output = if self.info.in_channels == 0 {
+            total
+        } else {
+            None
+        };
+        let num_buffers = if caps.per_stream_buffers {
+            Some(self.config.options.num_buffers_for(self.api))
+        } else {
+            None
+        };
+
+        LatencyBreakdown {
+            total,
+            input,
+            output,
+            num_buffers,
+        }
+    }
+
+    /// Take the error or warning that RtAudio has reported but which has not
+    /// been checked yet, if there is one.
+    ///
+    /// Reading the error clears it, so a second call will return `None`
+    /// until RtAudio reports something new. Warnings are returned here
+    /// instead of being sent to the warning handler or printed to the log.
+    ///
+    /// Errors which were already returned by another method of
+    /// `StreamHandle` have been cleared, so they are not returned again.
+    pub fn take_last_error(&self) -> Option<LastError> {
+        let _raw = self.error_sink.lock_raw();
+
+        match crate::error::take_last_error(self.raw)? {
+            LastError::Error(e) => Some(LastError::Error(e.with_origin(self.error_origin.clone()))),
+            warning => Some(warning),
+        }
+    }
+
+    /// Deliver any remaining error to the error callback, stop the notifier
+    /// thread, and remove the error sink.
+    ///
+    /// This must only be called after the stream has been closed.
+    fn stop_notifier(&mut self) {
+        self.error_sink.closed.store(true, Ordering::Release);
+
+        if let Some(notifier) = self.notifier.take() {
+            notifier.thread().unpark();
+            let _ = notifier.join();
+        }
+
+        self.error_sink.uninstall();
+    }
+
+    /// Replace the `EventStream` which the notifier thread sends events to.
+    #[cfg(feature = "async")]
+    pub(crate) fn set_event_queue(&self, queue: Arc<EventQueue>) {
+        let old = self
+            .error_sink
+            .events
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .replace(queue);
+
+        if let Some(old) = old {
+            old.close();
+        }
+    }
+
+    /// The caller must hold the lock from `ErrorSink::lock_raw()`.
+    fn check_for_error(&self, context: ErrorContext) -> Result<(), Error> {
+        let res = crate::check_for_error(self.raw, context)
+            .map_err(|e| e.with_origin(self.error_origin.clone()));
+
+        if let Some(warning) = res? {
+            crate::error::handle_warning(warning, self.warning_handler.as_ref());
+        }
+
+        Ok(())
+    }
+
     /// Start the stream.
     ///
     /// * `data_callback` - This gets called whenever there are new buffers
-    /// to process.
+    ///   to process.
     ///
     /// If an error is returned, then it means that the stream failed to
     /// start.
-    pub fn start<F>(&mut self, data_callback: F) -> Result<(), RtAudioError>
+    pub fn start<F>(&mut self, data_callback: F) -> Result<(), Error>
     where
         F: FnMut(Buffers<'_>, &StreamInfo, StreamStatus) + Send + 'static,
     {
         self.cb_context.cb = Box::new(data_callback);
+        self.cb_context.info.frame_counter = 0;
+        self.shared.frame_counter.store(0, Ordering::Relaxed);
+        self.cb_context.silent_blocks = 0;
+        self.shared.output_silent.store(false, Ordering::Relaxed);
+        let cb_context = &mut *self.cb_context;
+        cb_context.timing.reset(&cb_context.shared.timing);
+        cb_context.status.restart_run();
+        cb_context.realtime_recorded = false;
+        cb_context.shared.realtime.reset();
+        while self.overruns.pop().is_some() {}
+
+        let _raw = self.error_sink.lock_raw();
+
+        // Cleared while holding the lock, so that the notifier thread cannot
+        // apply a stop from the last run to this one.
+        self.shared.stop_requested.store(false, Ordering::Relaxed);
+        self.shared
+            .stopped_by_callback
+            .store(false, Ordering::Relaxed);
 
         // Safe because `self.raw` cannot be null. Also, the data pointed to
         // the callback context is pinned in place, and it will always stay
@@ -236,7 +982,7 @@ impl StreamHandle {
         unsafe {
             rtaudio_sys::rtaudio_start_stream(self.raw);
         }
-        if let Err(e) = crate::check_for_error(self.raw) {
+        if let Err(e) = self.check_for_error(ErrorContext::StartStream) {
             // Safe because `self.raw` cannot be null.
             unsafe {
                 rtaudio_sys::rtaudio_stop_stream(self.raw);
@@ -246,10 +992,27 @@ impl StreamHandle {
         }
 
         self.started = true;
+        self.shared.set_state(StreamState::Running);
 
         Ok(())
     }
 
+    /// Start the stream, copying the input of the stream directly to the
+    /// output.
+    ///
+    /// If the number of input and output channels differ, then the channels
+    /// are adapted as described in `Buffers::route_input_to_output()`.
+    ///
+    /// If an error is returned, then it means that the stream failed to
+    /// start.
+    pub fn start_loopback(&mut self) -> Result<(), Error> {
+        self.start(
+            |mut buffers: Buffers<'_>, info: &StreamInfo, _status: StreamStatus| {
+                buffers.route_input_to_output(info);
+            },
+        )
+    }
+
     /// Stop the stream.
     ///
     /// This will block the calling thread until the stream is stopped. After
@@ -258,12 +1021,38 @@ impl StreamHandle {
     ///
     /// This does not close the stream.
     pub fn stop(&mut self) {
+        if let Err(e) = self.try_stop() {
+            log::error!("Error while stopping RtAudio stream: {}", e);
+        }
+    }
+
+    /// Stop the stream, returning the error reported by the backend (if
+    /// any). The stream is considered stopped either way.
+    pub(crate) fn try_stop(&mut self) -> Result<(), Error> {
+        let mut res = Ok(());
+
         if self.started {
+            let raw = self.error_sink.lock_raw();
+
+            // Stopping the stream resets the error state, so pick up an
+            // error from the audio thread which the notifier thread has not
+            // seen yet.
+            self.error_sink.poll_locked();
+
+            // The audio thread may exit once the stream is stopped, so the
+            // notifier thread must not read its scheduling anymore.
+            self.shared.realtime.take_thread();
+
+            // The stream may already have been stopped by the data callback
+            // (see `CallbackStop`), and stopping it again is a warning.
+            //
             // Safe because `self.raw` cannot be null.
-            unsafe { rtaudio_sys::rtaudio_stop_stream(self.raw) };
-            if let Err(e) = crate::check_for_error(self.raw) {
-                log::error!("Error while stopping RtAudio stream: {}", e);
+            if unsafe { rtaudio_sys::rtaudio_is_stream_running(self.raw) } != 0 {
+                // Safe because `self.raw` cannot be null.
+                unsafe { rtaudio_sys::rtaudio_stop_stream(self.raw) };
+                res = self.check_for_error(ErrorContext::StopStream);
             }
+            drop(raw);
 
             // TODO: Make sure that the stream is always properly stopped
             // at this point.
@@ -271,8 +1060,20 @@ impl StreamHandle {
             // Drop the user's callback.
             self.cb_context.cb = Box::new(|_, _, _| {});
 
+            self.flush_warnings();
+
             self.started = false;
+
+            // Keep the error state (if any) so that it can still be observed.
+            let _ = self.shared.state.compare_exchange(
+                StreamState::RUNNING,
+                StreamState::OPEN,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            );
         }
+
+        res
     }
 
     /// Close the stream.
@@ -281,54 +1082,309 @@ impl StreamHandle {
     /// case, this will block the calling thread until the stream is stopped.
     /// After which the `data_callback` passed into `Stream::start()` will be
     /// dropped.
-    pub fn close(mut self) -> Host {
-        self.stop();
+    ///
+    /// The returned `Host` can be used to open another stream, as many times
+    /// as needed. It keeps the warning handler and the `show_warnings`
+    /// setting of the original `Host`. The cached device list is discarded,
+    /// so the devices will be scanned again the next time they are queried.
+    ///
+    /// Any error which occurs while stopping or closing the stream is
+    /// logged. Use `StreamHandle::try_close()` to receive it instead.
+    ///
+    /// ```
+    /// use rtaudio::{Api, Host, SampleFormat, StreamOptions};
+    ///
+    /// let mut host = Host::new(Api::Unspecified).unwrap();
+    ///
+    /// for buffer_frames in [128, 256, 512] {
+    ///     host = match host.open_output_stream(
+    ///         None,
+    ///         2,
+    ///         SampleFormat::Float32,
+    ///         48000,
+    ///         buffer_frames,
+    ///         StreamOptions::default(),
+    ///         |error| eprintln!("{}", error),
+    ///     ) {
+    ///         Ok(stream) => stream.close(),
+    ///         // The `Host` is also returned if the stream failed to open.
+    ///         Err(e) => e.host,
+    ///     };
+    ///
+    ///     // No error is left over from the previous stream.
+    ///     assert!(host.take_last_error().is_none());
+    /// }
+    /// ```
+    pub fn close(self) -> Host {
+        let (host, res) = self.close_inner();
+
+        if let Err(e) = res {
+            log::error!("Error while closing RtAudio stream: {}", e);
+        }
+
+        host
+    }
+
+    /// Close the stream, returning any error which occurred while stopping
+    /// or closing it.
+    ///
+    /// This is the same as `StreamHandle::close()`, except that the error
+    /// is returned instead of being logged. The stream is closed either
+    /// way, and the `Host` is returned in both cases, so it can still be
+    /// used to open another stream.
+    ///
+    /// If both stopping and closing the stream fail, then the error from
+    /// stopping the stream is returned and the other error is logged.
+    ///
+    /// ```no_run
+    /// use rtaudio::{Api, Host, SampleFormat, StreamOptions};
+    ///
+    /// let host = Host::new(Api::Unspecified).unwrap();
+    ///
+    /// let stream = host
+    ///     .open_output_stream(
+    ///         None,
+    ///         2,
+    ///         SampleFormat::Float32,
+    ///         48000,
+    ///         256,
+    ///         StreamOptions::default(),
+    ///         |error| eprintln!("{}", error),
+    ///     )
+    ///     .unwrap();
+    ///
+    /// let host = match stream.try_close() {
+    ///     Ok(host) => host,
+    ///     Err(e) => {
+    ///         eprintln!("the device may be unreliable: {}", e);
+    ///         e.host
+    ///     }
+    /// };
+    /// ```
+    pub fn try_close(self) -> Result<Host, HostError> {
+        let (host, res) = self.close_inner();
+
+        match res {
+            Ok(()) => Ok(host),
+            Err(error) => Err(HostError { host, error }),
+        }
+    }
+
+    fn close_inner(mut self) -> (Host, Result<(), Error>) {
+        let stop_res = self.try_stop();
+
+        let raw = self.error_sink.lock_raw();
+        self.error_sink.poll_locked();
 
         // Safe because `self.raw` cannot be null.
         unsafe { rtaudio_sys::rtaudio_close_stream(self.raw) };
-        if let Err(e) = crate::check_for_error(self.raw) {
-            log::error!("Error while closing RtAudio stream: {}", e);
-        }
+        let close_res = self.check_for_error(ErrorContext::CloseStream);
+        drop(raw);
+
+        self.stop_notifier();
 
-        let host = Host { raw: self.raw };
+        self.shared.set_state(StreamState::Closed);
+
+        let host = Host {
+            raw: self.raw,
+            warning_handler: self.warning_handler.take(),
+            device_cache: None,
+            owned: self.owned,
+        };
 
         // Make sure this isn't freed when `Stream` is dropped.
         self.raw = std::ptr::null_mut();
 
-        host
+        let res = match (stop_res, close_res) {
+            (Err(stop_err), Err(close_err)) => {
+                log::error!("Error while closing RtAudio stream: {}", close_err);
+                Err(stop_err)
+            }
+            (Err(e), Ok(())) | (Ok(()), Err(e)) => Err(e),
+            (Ok(()), Ok(())) => Ok(()),
+        };
+
+        (host, res)
     }
 }
 
 impl Drop for StreamHandle {
     fn drop(&mut self) {
-        {
-            ERROR_CB_SINGLETON.lock().unwrap().cb = None;
-        }
-
         if self.raw.is_null() {
             return;
         }
 
         self.stop();
 
+        let raw = self.error_sink.lock_raw();
+        self.error_sink.poll_locked();
+
         // Safe because we checked that `self.raw` is not null.
         unsafe { rtaudio_sys::rtaudio_close_stream(self.raw) };
-        if let Err(e) = crate::check_for_error(self.raw) {
+        if let Err(e) = self.check_for_error(ErrorContext::CloseStream) {
             log::error!("Error while closing RtAudio stream: {}", e);
         }
+        drop(raw);
+
+        self.stop_notifier();
+
+        if self.owned {
+            // Safe because we checked that `self.raw` is not null, and
+            // we are guaranteed to be the only owner of this pointer.
+            unsafe { rtaudio_sys::rtaudio_destroy(self.raw) };
+        }
 
-        // Safe because we checked that `self.raw` is not null, and
-        // we are guaranteed to be the only owner of this pointer.
-        unsafe { rtaudio_sys::rtaudio_destroy(self.raw) };
+        self.shared.set_state(StreamState::Closed);
     }
 }
 
-struct CallbackContext {
-    info: StreamInfo,
-    cb: Box<dyn FnMut(Buffers<'_>, &StreamInfo, StreamStatus) + Send + 'static>,
+/// Look up the info of the device with the given parameters, if it exists.
+fn lookup_device(host: &Host, params: Option<DeviceParams>) -> Option<DeviceInfo> {
+    host.device_by_id(params?.device_id?).ok().flatten()
 }
 
-#[no_mangle]
+/// Identify the device with the given parameters in errors.
+fn error_device(params: Option<DeviceParams>, info: Option<&DeviceInfo>) -> Option<ErrorDevice> {
+    Some(ErrorDevice {
+        id: params?.device_id?,
+        name: info.map(|info| info.name.clone()),
+    })
+}
+
+/// Check that the device has the requested range of channels.
+///
+/// The check is skipped if the device cannot be found, in which case
+/// RtAudio reports the error when the stream is opened.
+fn check_channel_range(
+    params: Option<DeviceParams>,
+    device: Option<&DeviceInfo>,
+    role: Role,
+) -> Result<(), Error> {
+    let (Some(params), Some(device)) = (params, device) else {
+        return Ok(());
+    };
+
+    let Err(problems) = params.validate(device, role) else {
+        return Ok(());
+    };
+
+    let problem = problems.into_iter().find(|p| {
+        matches!(
+            p,
+            ParamProblem::FirstChannelTooHigh { .. } | ParamProblem::ChannelRangeOutOfBounds { .. }
+        )
+    });
+
+    match problem {
+        Some(problem) => Err(Error::InvalidParameter {
+            msg: Some(format!("invalid {} channels: {}", role, problem).into()),
+            context: Some(ErrorContext::OpenStream),
+            origin: None,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// The data callback given to `StreamHandle::start()`.
+type DataCallback = Box<dyn FnMut(Buffers<'_>, &StreamInfo, StreamStatus) + Send + 'static>;
+
+struct CallbackContext {
+    info: StreamInfo,
+    cb: DataCallback,
+    shared: Arc<StreamShared>,
+    output_limiter: OutputLimiter,
+    silence_watchdog: Option<SilenceWatchdog>,
+    /// The number of consecutive silent output buffers.
+    silent_blocks: u32,
+    callback_budget: Option<CallbackBudget>,
+    timing: TimingAccumulator,
+    status: StatusAccumulator,
+    overruns: Producer<CallbackOverrun>,
+    /// Whether the scheduling of the callback thread has been recorded
+    /// since the stream was started.
+    realtime_recorded: bool,
+}
+
+/// State that is shared between the stream handle and the data callback.
+#[derive(Default)]
+struct StreamShared {
+    frame_counter: AtomicU64,
+    limited_samples: AtomicUsize,
+    silence_events: AtomicUsize,
+    output_silent: AtomicBool,
+    timing: SharedTiming,
+    status: SharedStatusStats,
+    realtime: SharedRealtimeStatus,
+    /// The raw value of a `StreamState`.
+    state: AtomicI32,
+    dropped_warnings: AtomicUsize,
+    /// Set by a `CallbackStop` to make the data callback stop the stream.
+    stop_requested: AtomicBool,
+    /// Set by the data callback once it has told RtAudio to stop the stream,
+    /// and cleared by the notifier thread once it has updated the state.
+    stopped_by_callback: AtomicBool,
+}
+
+impl StreamShared {
+    fn state(&self) -> StreamState {
+        StreamState::from_raw(self.state.load(Ordering::Acquire))
+    }
+
+    fn set_state(&self, state: StreamState) {
+        self.state.store(state.to_raw(), Ordering::Release);
+    }
+}
+
+/// Stops a stream from inside its data callback, for the playback helpers
+/// which know when they have run out of audio.
+///
+/// Once requested, the data callback returns `1` to RtAudio after the
+/// current block, which plays out the buffers that are already queued and
+/// then stops the stream. The notifier thread then moves the state of the
+/// stream back to `StreamState::Open`. This does not allocate or lock, so it
+/// can be used on the audio thread.
+#[derive(Clone)]
+pub(crate) struct CallbackStop {
+    shared: Arc<StreamShared>,
+}
+
+impl CallbackStop {
+    pub(crate) fn request(&self) {
+        self.shared.stop_requested.store(true, Ordering::Release);
+    }
+}
+
+// # Realtime safety of the data callback
+//
+// `raw_data_callback()` runs on the audio thread once per block, so apart
+// from the user's callback it must not allocate, lock, log, or make any
+// syscall. Everything it touches is set up when the stream is opened:
+//
+// * `Buffers::from_raw()` only builds slices over the pointers from
+//   RtAudio.
+// * The status statistics, the callback timing, the limiter and silence
+//   counters, and the frame counter are published with plain atomic stores
+//   and counters.
+// * Overruns are pushed into the preallocated lock-free overrun queue, and
+//   are dropped (but still counted) if it is full.
+// * A `CallbackStop` is a flag which is read with an atomic load. The
+//   notifier thread updates the state of the stream afterwards.
+// * The first call after the stream is started records the id of the
+//   audio thread (`pthread_self()`, a thread-local read) with an atomic
+//   store. Its scheduling is read by the notifier thread, since
+//   `pthread_getschedparam()` can lock and enter the kernel.
+// * `Instant::now()` is only called when `StreamOptions::callback_budget`
+//   is set. It reads a monotonic clock, which does not block (and does not
+//   enter the kernel on the common platforms).
+//
+// Errors and warnings take the path described below, which keeps the same
+// guarantees. Keep it this way when adding to the callback.
+//
+// The `data_callback_does_not_allocate` test checks the first guarantee
+// with every option turned on. Locks and syscalls are not checked by a
+// test, so this list has to be kept up to date by hand.
+
+#[no_mangle]
 pub(crate) unsafe extern "C" fn raw_data_callback(
     out: *mut c_void,
     in_: *mut c_void,
@@ -352,6 +1408,14 @@ pub(crate) unsafe extern "C" fn raw_data_callback(
 
     cb_context.info.stream_time = stream_time;
 
+    if !cb_context.realtime_recorded {
+        cb_context
+            .shared
+            .realtime
+            .record_thread(crate::realtime::current_thread_id());
+        cb_context.realtime_recorded = true;
+    }
+
     // This is safe because we assume that the correct amount
     // of data pointed to by `out` and `in_` exists. Also this
     // function checks if they are null.
@@ -367,19 +1431,682 @@ pub(crate) unsafe extern "C" fn raw_data_callback(
     };
 
     let status = StreamStatus::from_bits_truncate(status);
+    cb_context
+        .status
+        .record(status, stream_time, &cb_context.shared.status);
+
+    if let Some(budget) = cb_context.callback_budget {
+        let start = Instant::now();
+
+        (cb_context.cb)(buffers, &cb_context.info, status);
+
+        let duration = start.elapsed();
+        let block_budget = (u64::from(frames) * 1_000_000_000)
+            .checked_div(u64::from(cb_context.info.sample_rate))
+            .map(Duration::from_nanos)
+            .unwrap_or(Duration::ZERO);
+
+        let overrun = cb_context.timing.record(
+            duration,
+            block_budget,
+            budget.budget_fraction,
+            &cb_context.shared.timing,
+        );
+
+        if overrun {
+            // If the queue is full, then the overrun is still counted in
+            // the timing.
+            let _ = cb_context.overruns.push(CallbackOverrun {
+                frame: cb_context.info.frame_counter,
+                frames: frames as usize,
+                duration,
+                budget: block_budget,
+            });
+        }
+    } else {
+        (cb_context.cb)(buffers, &cb_context.info, status);
+    }
 
-    (cb_context.cb)(buffers, &cb_context.info, status);
+    if cb_context.output_limiter != OutputLimiter::Off {
+        // This is safe for the same reason as above. The buffers passed to
+        // the user's callback have been dropped at this point.
+        let mut buffers = unsafe {
+            Buffers::from_raw(
+                out,
+                std::ptr::null_mut(),
+                frames as usize,
+                cb_context.info.out_channels,
+                0,
+                cb_context.info.sample_format,
+            )
+        };
+
+        let limited = buffers.limit_output(cb_context.output_limiter);
+        if limited > 0 {
+            cb_context
+                .shared
+                .limited_samples
+                .fetch_add(limited, Ordering::Relaxed);
+        }
+    }
+
+    if let Some(watchdog) = cb_context.silence_watchdog {
+        if cb_context.info.out_channels > 0 {
+            // This is safe for the same reason as above.
+            let buffers = unsafe {
+                Buffers::from_raw(
+                    out,
+                    std::ptr::null_mut(),
+                    frames as usize,
+                    cb_context.info.out_channels,
+                    0,
+                    cb_context.info.sample_format,
+                )
+            };
+
+            if buffers.is_output_silent() {
+                cb_context.silent_blocks = cb_context.silent_blocks.saturating_add(1);
+
+                if cb_context.silent_blocks == watchdog.threshold_blocks.max(1) {
+                    let shared = &cb_context.shared;
+                    shared.silence_events.fetch_add(1, Ordering::Relaxed);
+                    shared.output_silent.store(true, Ordering::Relaxed);
+                }
+            } else if cb_context.silent_blocks > 0 {
+                cb_context.silent_blocks = 0;
+                cb_context
+                    .shared
+                    .output_silent
+                    .store(false, Ordering::Relaxed);
+            }
+        }
+    }
+
+    cb_context.info.frame_counter += u64::from(frames);
+    cb_context
+        .shared
+        .frame_counter
+        .store(cb_context.info.frame_counter, Ordering::Relaxed);
+
+    if cb_context.shared.stop_requested.load(Ordering::Acquire) {
+        cb_context
+            .shared
+            .stopped_by_callback
+            .store(true, Ordering::Release);
+
+        // Play out the queued buffers, then stop the stream.
+        return 1;
+    }
 
     0
 }
 
-lazy_static::lazy_static! {
-    static ref ERROR_CB_SINGLETON: Mutex<ErrorCallbackSingleton> =
-        Mutex::new(ErrorCallbackSingleton { cb: None });
+// # Delivering errors from the audio thread
+//
+// Errors and warnings which RtAudio reports while the stream is running
+// (i.e. a device was disconnected, or an underrun was recovered) reach the
+// `ErrorSink` of the stream in one of two ways:
+//
+// * The C wrapper of RtAudio (in rtaudio-sys 0.3) does not forward them to
+//   the error callback passed to `rtaudio_open_stream()`. It only records
+//   them in the error state of the instance, and every call into the
+//   instance resets that state. So the notifier thread polls it every
+//   `NOTIFIER_POLL_INTERVAL`, and the thread which owns the `StreamHandle`
+//   polls it right before stopping or closing the stream. Both hold the
+//   `raw_lock` of the sink while calling into the instance, so the error
+//   state is never read or reset in the middle of another call. (RtAudio
+//   writes it from the audio thread without any synchronization, so a
+//   message which is read while it is being overwritten may be garbled.)
+// * A wrapper which does forward them may call `raw_error_callback()` from
+//   the audio thread, so that must not lock or allocate. It also has no
+//   user data pointer, so the sink is reached through the global
+//   `ERROR_SINK` pointer (there can only be one stream at a time).
+//
+// In the sink:
+//
+// * Errors are copied into the fixed-size `ErrorSlot`, the state of the
+//   stream becomes `StreamState::Errored`, and the notifier thread is woken
+//   up. The notifier thread builds the `Error` and calls the user's error
+//   callback. Only the first error is kept until the notifier thread has
+//   taken it, since the error callback is only called once.
+// * Warnings are pushed into the lock-free warning queue, which is drained
+//   from the thread which owns the `StreamHandle`, or by the notifier thread
+//   if `StreamOptions::deliver_warnings` is set.
+//
+// The sink is installed before the stream is opened, and it is only
+// removed after `rtaudio_close_stream()` has returned, at which point the
+// audio thread no longer reports anything for the stream.
+
+/// The error sink of the stream which is currently open (if any).
+///
+/// This holds one strong reference of the `Arc<ErrorSink>`.
+static ERROR_SINK: AtomicPtr<ErrorSink> = AtomicPtr::new(std::ptr::null_mut());
+
+/// How often the notifier thread checks for errors if it is not woken up.
+const NOTIFIER_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The maximum length of an error message from the audio thread in bytes.
+const ERROR_MSG_LEN: usize = 256;
+
+/// The state of the stream which `raw_error_callback()` can reach without
+/// locking.
+struct ErrorSink {
+    /// The RtAudio instance of the stream, whose error state is polled.
+    ///
+    /// This stays valid until the notifier thread has exited.
+    raw: rtaudio_sys::rtaudio_t,
+    /// Held while calling into `raw`.
+    raw_lock: Mutex<()>,
+
+    slot: ErrorSlot,
+    shared: Arc<StreamShared>,
+
+    /// Set while a thread is pushing into `warnings`.
+    warnings_busy: AtomicBool,
+    warnings: UnsafeCell<Producer<WarningEntry>>,
+    /// Whether the notifier thread delivers the queued warnings (see
+    /// `StreamOptions::deliver_warnings`).
+    deliver_warnings: bool,
+
+    /// The notifier thread, once it has been started.
+    notifier: OnceLock<Thread>,
+    /// Set when the notifier thread should exit.
+    closed: AtomicBool,
+
+    /// The sending end of the `EventStream` returned by
+    /// `StreamHandle::events()`. This is only locked by the notifier thread
+    /// and the thread which owns the `StreamHandle`, never by the audio
+    /// thread.
+    #[cfg(feature = "async")]
+    events: std::sync::Mutex<Option<Arc<EventQueue>>>,
+}
+
+// Safe because access to `warnings` is guarded by `warnings_busy`, and
+// `raw` is only used while `raw_lock` is held.
+unsafe impl Sync for ErrorSink {}
+// Safe because `raw` is only used while `raw_lock` is held, and the
+// instance outlives every thread which holds the sink.
+unsafe impl Send for ErrorSink {}
+
+impl ErrorSink {
+    fn new(
+        raw: rtaudio_sys::rtaudio_t,
+        shared: Arc<StreamShared>,
+        warnings: Producer<WarningEntry>,
+        deliver_warnings: bool,
+    ) -> Self {
+        Self {
+            raw,
+            raw_lock: Mutex::new(()),
+            slot: ErrorSlot::new(),
+            shared,
+            warnings_busy: AtomicBool::new(false),
+            warnings: UnsafeCell::new(warnings),
+            deliver_warnings,
+            notifier: OnceLock::new(),
+            closed: AtomicBool::new(false),
+            #[cfg(feature = "async")]
+            events: std::sync::Mutex::new(None),
+        }
+    }
+
+    /// Install this sink as the global error sink.
+    ///
+    /// Returns `false` if another stream is already open.
+    fn install(self: &Arc<Self>) -> bool {
+        let ptr = Arc::into_raw(Arc::clone(self)) as *mut ErrorSink;
+
+        if ERROR_SINK
+            .compare_exchange(
+                std::ptr::null_mut(),
+                ptr,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            )
+            .is_ok()
+        {
+            true
+        } else {
+            // Safe because this pointer was created with `Arc::into_raw()`
+            // above and it was never shared.
+            drop(unsafe { Arc::from_raw(ptr) });
+            false
+        }
+    }
+
+    /// Remove this sink if it is the global error sink.
+    ///
+    /// This must only be called once RtAudio can no longer call
+    /// `raw_error_callback()` for this stream.
+    fn uninstall(self: &Arc<Self>) {
+        let ptr = Arc::as_ptr(self) as *mut ErrorSink;
+
+        if ERROR_SINK
+            .compare_exchange(
+                ptr,
+                std::ptr::null_mut(),
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            )
+            .is_ok()
+        {
+            // Safe because the global pointer was created with
+            // `Arc::into_raw()` in `ErrorSink::install()`.
+            drop(unsafe { Arc::from_raw(ptr) });
+        }
+    }
+
+    /// Lock the RtAudio instance of the stream.
+    fn lock_raw(&self) -> MutexGuard<'_, ()> {
+        // The lock does not protect any data of its own.
+        self.raw_lock.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Check the error state of the RtAudio instance for an error or
+    /// warning from the audio thread (see the note above `ERROR_SINK`).
+    fn poll(&self) {
+        let _raw = self.lock_raw();
+        self.poll_locked();
+    }
+
+    /// Move the state of the stream back to `StreamState::Open` if the data
+    /// callback has stopped it (see `CallbackStop`).
+    ///
+    /// This holds the lock from `ErrorSink::lock_raw()`, like
+    /// `StreamHandle::start()` does while it resets the flag and sets the
+    /// state to `StreamState::Running`.
+    fn apply_callback_stop(&self) {
+        let _raw = self.lock_raw();
+
+        if self
+            .shared
+            .stopped_by_callback
+            .swap(false, Ordering::AcqRel)
+        {
+            self.shared.realtime.take_thread();
+            let _ = self.shared.state.compare_exchange(
+                StreamState::RUNNING,
+                StreamState::OPEN,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            );
+        }
+    }
+
+    /// Read the scheduling of the audio thread once the data callback has
+    /// recorded its id (see `SharedRealtimeStatus`).
+    ///
+    /// This holds the lock from `ErrorSink::lock_raw()`, like
+    /// `StreamHandle::try_stop()` does while it stops the stream and forgets
+    /// the id, so the audio thread is still alive while it is read.
+    fn resolve_realtime_status(&self) {
+        let _raw = self.lock_raw();
+
+        if self.shared.stopped_by_callback.load(Ordering::Acquire) {
+            return;
+        }
+        if let Some(thread) = self.shared.realtime.take_thread() {
+            // Safe because the stream has not been stopped since the thread
+            // recorded its id, so the thread is still alive.
+            let status = unsafe { crate::realtime::thread_status(thread) };
+            self.shared.realtime.store(status);
+        }
+    }
+
+    /// The same as `ErrorSink::poll()`, for a caller which already holds the
+    /// lock from `ErrorSink::lock_raw()`.
+    fn poll_locked(&self) {
+        if let Some(last) = crate::error::take_last_error(self.raw) {
+            self.report(last);
+        }
+    }
+
+    /// Report an error or warning which was taken from the error state of
+    /// the RtAudio instance.
+    fn report(&self, last: LastError) {
+        match last {
+            LastError::Error(e) => {
+                self.push_error(e.raw_code(), e.raw_message().unwrap_or_default())
+            }
+            LastError::Warning(w) => {
+                self.push_warning(w.msg.as_deref().unwrap_or_default().as_bytes())
+            }
+        }
+    }
+
+    /// Queue a warning without blocking or allocating.
+    fn push_warning(&self, msg: &[u8]) {
+        if self
+            .warnings_busy
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            // Another thread is pushing a warning at the same time. Don't
+            // wait for it.
+            self.shared.dropped_warnings.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+
+        // Safe because `warnings_busy` guarantees that no other thread is
+        // accessing the producer.
+        let warnings = unsafe { &mut *self.warnings.get() };
+        let pushed = warnings.push(WarningEntry::new(msg)).is_ok();
+        if !pushed {
+            self.shared.dropped_warnings.fetch_add(1, Ordering::Relaxed);
+        }
+
+        self.warnings_busy.store(false, Ordering::Release);
+
+        if pushed && self.deliver_warnings {
+            if let Some(notifier) = self.notifier.get() {
+                notifier.unpark();
+            }
+        }
+    }
+
+    /// Report an error without blocking or allocating.
+    ///
+    /// If an error is already waiting to be delivered, then the new error
+    /// is discarded and the state of the stream is left as-is.
+    fn push_error(&self, code: rtaudio_sys::rtaudio_error_t, msg: &[u8]) {
+        if !self.slot.write(code, msg) {
+            return;
+        }
+
+        if let Some(type_) = RtAudioErrorType::from_raw(code) {
+            self.shared.set_state(StreamState::Errored { type_ });
+        }
+
+        if let Some(notifier) = self.notifier.get() {
+            notifier.unpark();
+        }
+    }
+}
+
+/// A single error which can be written without locking or allocating.
+struct ErrorSlot {
+    /// One of `EMPTY`, `WRITING`, or `FULL`.
+    state: AtomicU8,
+    code: AtomicI32,
+    len: AtomicUsize,
+    msg: UnsafeCell<[u8; ERROR_MSG_LEN]>,
 }
 
-pub(crate) struct ErrorCallbackSingleton {
-    cb: Option<Box<dyn FnOnce(RtAudioError) + Send + 'static>>,
+impl ErrorSlot {
+    const EMPTY: u8 = 0;
+    const WRITING: u8 = 1;
+    const FULL: u8 = 2;
+
+    fn new() -> Self {
+        Self {
+            state: AtomicU8::new(Self::EMPTY),
+            code: AtomicI32::new(0),
+            len: AtomicUsize::new(0),
+            msg: UnsafeCell::new([0; ERROR_MSG_LEN]),
+        }
+    }
+
+    /// Copy (and truncate) the given error into the slot.
+    ///
+    /// Returns `false` if the slot already holds an error, in which case
+    /// the new error is discarded.
+    fn write(&self, code: rtaudio_sys::rtaudio_error_t, msg: &[u8]) -> bool {
+        if self
+            .state
+            .compare_exchange(
+                Self::EMPTY,
+                Self::WRITING,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            )
+            .is_err()
+        {
+            return false;
+        }
+
+        let len = msg.len().min(ERROR_MSG_LEN);
+
+        // Safe because the `WRITING` state guarantees that no other thread
+        // is accessing the message buffer.
+        unsafe { (&mut *self.msg.get())[..len].copy_from_slice(&msg[..len]) };
+        self.code.store(code, Ordering::Relaxed);
+        self.len.store(len, Ordering::Relaxed);
+
+        self.state.store(Self::FULL, Ordering::Release);
+
+        true
+    }
+
+    /// Take the error out of the slot, if there is one.
+    fn take(&self) -> Option<Error> {
+        if self.state.load(Ordering::Acquire) != Self::FULL {
+            return None;
+        }
+
+        let code = self.code.load(Ordering::Relaxed);
+        let len = self.len.load(Ordering::Relaxed);
+
+        // Safe because the `FULL` state guarantees that no other thread is
+        // writing to the message buffer.
+        let e = Error::from_raw(code, Some(unsafe { &(&*self.msg.get())[..len] }));
+
+        self.state.store(Self::EMPTY, Ordering::Release);
+
+        e
+    }
+}
+
+// Safe because access to `msg` is synchronized with `state`.
+unsafe impl Sync for ErrorSlot {}
+
+/// The warnings which are delivered by the notifier thread (see
+/// `StreamOptions::deliver_warnings`).
+struct NotifierWarnings {
+    warnings: Consumer<WarningEntry>,
+    handler: Option<WarningHandler>,
+}
+
+impl NotifierWarnings {
+    /// Send all queued warnings to the warning handler.
+    fn deliver(&mut self, events: &NotifierEvents) {
+        while let Some(entry) = self.warnings.pop() {
+            let warning = entry.to_warning();
+            events.warning(&warning);
+
+            let res = std::panic::catch_unwind(AssertUnwindSafe(|| {
+                crate::error::handle_warning(warning, self.handler.as_ref())
+            }));
+            if res.is_err() {
+                log::error!("The RtAudio warning handler panicked");
+            }
+        }
+    }
+}
+
+/// Start the thread which calls the user's error callback.
+fn spawn_notifier(
+    sink: Arc<ErrorSink>,
+    origin: ErrorOrigin,
+    error_callback: Box<dyn FnOnce(Error) + Send + 'static>,
+    mut warnings: Option<NotifierWarnings>,
+) -> std::io::Result<JoinHandle<()>> {
+    let thread_sink = Arc::clone(&sink);
+
+    let handle = std::thread::Builder::new()
+        .name(String::from("rtaudio-error-notifier"))
+        .spawn(move || {
+            let sink = thread_sink;
+            let mut error_callback = Some(error_callback);
+            let mut events = NotifierEvents::new(&sink);
+
+            loop {
+                // Check this before taking the error, so that an error which
+                // is reported while the stream is being closed is still
+                // delivered.
+                let closed = sink.closed.load(Ordering::Acquire);
+
+                if !closed {
+                    sink.poll();
+                    sink.apply_callback_stop();
+                    sink.resolve_realtime_status();
+                }
+
+                events.update(&sink);
+
+                if let Some(e) = sink.slot.take() {
+                    let e = e.with_origin(origin.clone());
+                    events.error(&e);
+
+                    if let Some(cb) = error_callback.take() {
+                        // A panic in the user's callback must not take the
+                        // notifier thread down with it, or closing the
+                        // stream could no longer deliver a final error.
+                        let res = std::panic::catch_unwind(AssertUnwindSafe(move || (cb)(e)));
+                        if res.is_err() {
+                            log::error!("The RtAudio stream error callback panicked");
+                        }
+                    }
+                }
+
+                if let Some(warnings) = &mut warnings {
+                    warnings.deliver(&events);
+                }
+
+                if closed {
+                    events.close();
+                    break;
+                }
+
+                std::thread::park_timeout(NOTIFIER_POLL_INTERVAL);
+            }
+        })?;
+
+    let _ = sink.notifier.set(handle.thread().clone());
+
+    Ok(handle)
+}
+
+/// Sends the events of the stream from the notifier thread to the
+/// `EventStream` returned by `StreamHandle::events()`, if there is one.
+///
+/// Without the `async` feature this does nothing.
+struct NotifierEvents {
+    #[cfg(feature = "async")]
+    queue: Option<Arc<EventQueue>>,
+    #[cfg(feature = "async")]
+    last_state: StreamState,
+    #[cfg(feature = "async")]
+    last_xruns: u64,
+}
+
+#[cfg_attr(not(feature = "async"), allow(unused_variables))]
+impl NotifierEvents {
+    fn new(sink: &ErrorSink) -> Self {
+        Self {
+            #[cfg(feature = "async")]
+            queue: None,
+            #[cfg(feature = "async")]
+            last_state: sink.shared.state(),
+            #[cfg(feature = "async")]
+            last_xruns: sink.shared.status.snapshot().xruns,
+        }
+    }
+
+    /// Pick up a new `EventStream`, and send any state change or xruns
+    /// since the last update.
+    fn update(&mut self, sink: &ErrorSink) {
+        #[cfg(feature = "async")]
+        {
+            self.queue = sink
+                .events
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner)
+                .clone();
+
+            let state = sink.shared.state();
+            if state != self.last_state {
+                self.send(StreamEvent::StateChanged(state.clone()));
+                self.last_state = state;
+            }
+
+            // The count goes down when the statistics are reset.
+            let stats = sink.shared.status.snapshot();
+            if stats.xruns > self.last_xruns {
+                self.send(StreamEvent::Xrun(stats));
+            }
+            self.last_xruns = stats.xruns;
+        }
+    }
+
+    fn error(&self, e: &Error) {
+        #[cfg(feature = "async")]
+        self.send(StreamEvent::Error(e.clone()));
+    }
+
+    fn warning(&self, warning: &RtAudioWarning) {
+        #[cfg(feature = "async")]
+        self.send(StreamEvent::Warning(warning.clone()));
+    }
+
+    /// Send the final state and end the `EventStream`.
+    fn close(&self) {
+        #[cfg(feature = "async")]
+        if let Some(queue) = &self.queue {
+            queue.push(StreamEvent::StateChanged(StreamState::Closed));
+            queue.close();
+        }
+    }
+
+    #[cfg(feature = "async")]
+    fn send(&self, event: StreamEvent) {
+        if let Some(queue) = &self.queue {
+            queue.push(event);
+        }
+    }
+}
+
+/// The number of warnings from the audio thread that can be queued.
+const WARNING_QUEUE_CAPACITY: usize = 32;
+
+/// The maximum number of callback overruns which are kept until they are
+/// taken with `StreamHandle::take_callback_overruns()`.
+const OVERRUN_QUEUE_CAPACITY: usize = 32;
+/// The maximum length of a queued warning message in bytes.
+const WARNING_MSG_LEN: usize = 256;
+
+/// A warning which can be queued without allocating.
+#[derive(Clone, Copy)]
+struct WarningEntry {
+    msg: [u8; WARNING_MSG_LEN],
+    len: usize,
+}
+
+impl WarningEntry {
+    /// Copy (and truncate) the given message without allocating.
+    fn new(msg: &[u8]) -> Self {
+        let len = msg.len().min(WARNING_MSG_LEN);
+        let mut entry = Self {
+            msg: [0; WARNING_MSG_LEN],
+            len,
+        };
+        entry.msg[..len].copy_from_slice(&msg[..len]);
+        entry
+    }
+
+    fn to_warning(self) -> RtAudioWarning {
+        let msg = String::from_utf8_lossy(&self.msg[..self.len]).to_string();
+
+        RtAudioWarning {
+            msg: if msg.is_empty() { None } else { Some(msg) },
+        }
+    }
+}
+
+/// Take all of the queued warnings.
+fn drain_warnings(warnings: &mut Consumer<WarningEntry>) -> Vec<RtAudioWarning> {
+    std::iter::from_fn(|| warnings.pop())
+        .map(|w| w.to_warning())
+        .collect()
 }
 
 #[no_mangle]
@@ -387,33 +2114,587 @@ pub(crate) unsafe extern "C" fn raw_error_callback(
     raw_err: rtaudio_sys::rtaudio_error_t,
     raw_msg: *const ::std::os::raw::c_char,
 ) {
-    if let Some(type_) = RtAudioErrorType::from_raw(raw_err) {
-        if type_ == RtAudioErrorType::Warning {
-            // Do nothing. While we could print the warning, we could be
-            // in the realtime thread so it's better to not do that.
-            return;
-        }
+    // We could be in the realtime thread, so this must not lock or
+    // allocate. See the note above `ERROR_SINK`.
 
-        // Safe because this C string will always be valid, we check
-        // for the null case, and we don't free the pointer.
-        let msg = unsafe {
-            if raw_msg.is_null() {
-                None
-            } else {
-                let msg = CStr::from_ptr(raw_msg).to_string_lossy().to_string();
+    let ptr = ERROR_SINK.load(Ordering::Acquire);
+    if ptr.is_null() {
+        return;
+    }
+    // Safe because the sink is only freed after it has been removed from
+    // `ERROR_SINK`, which only happens once RtAudio can no longer call this
+    // function for the stream.
+    let sink = unsafe { &*ptr };
+
+    let msg: &[u8] = if raw_msg.is_null() {
+        &[]
+    } else {
+        // Safe because this C string will always be valid, we checked for
+        // the null case, and we don't free the pointer.
+        unsafe { CStr::from_ptr(raw_msg).to_bytes() }
+    };
 
-                if msg.is_empty() {
-                    None
-                } else {
-                    Some(msg)
+    if raw_err == rtaudio_sys::RTAUDIO_ERROR_WARNING {
+        sink.push_warning(msg);
+    } else if raw_err != rtaudio_sys::RTAUDIO_ERROR_NONE {
+        sink.push_error(raw_err, msg);
+    }
+}
+
+/// Tests which open streams hold this lock, since only one stream can
+/// exist at a time.
+#[cfg(test)]
+pub(crate) fn lock_streams_for_test() -> std::sync::MutexGuard<'static, ()> {
+    static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    LOCK.lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// Wait until no stream exists, i.e. after a stream was opened or closed on
+/// another thread.
+#[cfg(test)]
+pub(crate) fn wait_until_no_stream() {
+    while !ERROR_SINK.load(Ordering::Acquire).is_null() {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DeviceID, StreamOptions};
+
+    /// Make RtAudio record an error in the error state of the instance, the
+    /// same way it records errors which occur on the audio thread.
+    fn record_error(sink: &ErrorSink) {
+        let _raw = sink.lock_raw();
+        // Safe because the instance is valid. The info is zero-filled since
+        // there is no such device.
+        unsafe { rtaudio_sys::rtaudio_get_device_info(sink.raw, c_uint::MAX) };
+    }
+
+    fn sink(host: &Host, deliver_warnings: bool) -> (Arc<ErrorSink>, Consumer<WarningEntry>) {
+        let shared = Arc::new(StreamShared::default());
+        shared.set_state(StreamState::Running);
+        let (warnings_tx, warnings_rx) = SpscRing::new(WARNING_QUEUE_CAPACITY);
+
+        let sink = ErrorSink::new(host.raw, shared, warnings_tx, deliver_warnings);
+        (Arc::new(sink), warnings_rx)
+    }
+
+    #[test]
+    fn polled_error_puts_the_stream_into_the_errored_state() {
+        let host = Host::new(Api::Unspecified).unwrap();
+        let (sink, _warnings) = sink(&host, false);
+        let observer = StreamStateObserver {
+            shared: Arc::clone(&sink.shared),
+        };
+
+        sink.poll();
+        assert_eq!(observer.state(), StreamState::Running);
+        assert!(sink.slot.take().is_none());
+
+        record_error(&sink);
+        sink.poll();
+        assert_eq!(
+            observer.state(),
+            StreamState::Errored {
+                type_: RtAudioErrorType::InvalidParameter
+            }
+        );
+
+        let e = sink.slot.take().unwrap();
+        assert_eq!(e.kind(), RtAudioErrorType::InvalidParameter);
+        assert!(e.msg().is_some());
+
+        // Polling took the error out of the instance.
+        assert_eq!(host.take_last_error(), None);
+    }
+
+    /// A callback context with every optional check of the data callback
+    /// turned off, and its queue of overruns.
+    fn callback_context(
+        info: StreamInfo,
+        shared: &Arc<StreamShared>,
+    ) -> (Box<CallbackContext>, Consumer<CallbackOverrun>) {
+        let (overruns, overruns_rx) = SpscRing::new(4);
+        let cb_context = Box::new(CallbackContext {
+            info,
+            cb: Box::new(|_, _, _| {}),
+            shared: Arc::clone(shared),
+            output_limiter: OutputLimiter::Off,
+            silence_watchdog: None,
+            silent_blocks: 0,
+            callback_budget: None,
+            timing: TimingAccumulator::default(),
+            status: StatusAccumulator::default(),
+            overruns,
+            realtime_recorded: false,
+        });
+
+        (cb_context, overruns_rx)
+    }
+
+    #[test]
+    fn data_callback_records_the_scheduling_of_its_thread() {
+        let shared = Arc::new(StreamShared::default());
+        let (mut cb_context, _overruns) = callback_context(StreamInfo::default(), &shared);
+        let (warnings, _warnings_rx) = SpscRing::new(1);
+        let sink = ErrorSink::new(std::ptr::null_mut(), Arc::clone(&shared), warnings, false);
+        let observer = StreamStateObserver { shared };
+        assert_eq!(observer.realtime_status(), RealtimeStatus::Unknown);
+
+        // Run the callback on a new thread, like a backend would, and keep
+        // that thread alive while the notifier reads its scheduling.
+        let userdata = &mut *cb_context as *mut CallbackContext as usize;
+        let (called_tx, called_rx) = std::sync::mpsc::channel();
+        let (resolved_tx, resolved_rx) = std::sync::mpsc::channel::<()>();
+        let audio_thread = std::thread::spawn(move || {
+            // Safe because the context outlives the thread, and a stream
+            // without channels has no buffers.
+            unsafe {
+                raw_data_callback(
+                    std::ptr::null_mut(),
+                    std::ptr::null_mut(),
+                    64,
+                    0.0,
+                    0,
+                    userdata as *mut c_void,
+                )
+            };
+            called_tx
+                .send(crate::realtime::current_thread_id())
+                .unwrap();
+            let _ = resolved_rx.recv();
+        });
+
+        let thread = called_rx.recv().unwrap();
+        assert!(cb_context.realtime_recorded);
+        // Only the id was recorded on the audio thread.
+        assert_eq!(observer.realtime_status(), RealtimeStatus::Unknown);
+
+        sink.resolve_realtime_status();
+        // Safe because the audio thread is still alive.
+        let expected = unsafe { crate::realtime::thread_status(thread) };
+        drop(resolved_tx);
+        audio_thread.join().unwrap();
+
+        assert_eq!(observer.realtime_status(), expected);
+        #[cfg(target_os = "linux")]
+        assert_eq!(expected, RealtimeStatus::Normal);
+    }
+
+    #[test]
+    fn callback_stop_stops_the_stream_after_the_block() {
+        let shared = Arc::new(StreamShared::default());
+        shared.set_state(StreamState::Running);
+        let (mut cb_context, _overruns) = callback_context(StreamInfo::default(), &shared);
+
+        let stop = CallbackStop {
+            shared: Arc::clone(&shared),
+        };
+        let mut blocks = 0;
+        cb_context.cb = Box::new(move |_, _, _| {
+            blocks += 1;
+            if blocks == 2 {
+                stop.request();
+            }
+        });
+
+        let userdata = &mut *cb_context as *mut CallbackContext as *mut c_void;
+        // Safe because a stream without channels has no buffers.
+        let run = || unsafe {
+            raw_data_callback(
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                64,
+                0.0,
+                0,
+                userdata,
+            )
+        };
+        assert_eq!(run(), 0);
+        assert_eq!(run(), 1);
+        // The block which requested the stop was still processed.
+        assert_eq!(shared.frame_counter.load(Ordering::Relaxed), 128);
+
+        // The notifier thread moves the state back to open.
+        let (warnings, _warnings_rx) = SpscRing::new(1);
+        let sink = ErrorSink::new(std::ptr::null_mut(), Arc::clone(&shared), warnings, false);
+        assert_eq!(shared.state(), StreamState::Running);
+        sink.apply_callback_stop();
+        assert_eq!(shared.state(), StreamState::Open);
+        assert!(!shared.stopped_by_callback.load(Ordering::Relaxed));
+    }
+
+    /// This only checks for allocations, not for locks or syscalls (see the
+    /// note above `raw_data_callback()`).
+    #[test]
+    fn data_callback_does_not_allocate() {
+        const FRAMES: usize = 64;
+
+        let shared = Arc::new(StreamShared::default());
+        let info = StreamInfo {
+            out_channels: 2,
+            in_channels: 2,
+            sample_format: SampleFormat::Float32,
+            sample_rate: 48000,
+            max_frames: FRAMES,
+            ..Default::default()
+        };
+        let (mut cb_context, mut overruns) = callback_context(info, &shared);
+
+        // Turn on every check, and make each of them trigger.
+        cb_context.output_limiter = OutputLimiter::SoftKnee;
+        cb_context.silence_watchdog = Some(SilenceWatchdog {
+            threshold_blocks: 1,
+        });
+        cb_context.callback_budget = Some(CallbackBudget {
+            budget_fraction: 0.0,
+        });
+        let mut loud = false;
+        cb_context.cb = Box::new(move |buffers, _info, _status| {
+            // Alternate between loud and silent blocks.
+            loud = !loud;
+            let gain = if loud { 4.0 } else { 0.0 };
+
+            if let Buffers::Float32 { output, input } = buffers {
+                for (out, s) in output.iter_mut().zip(input) {
+                    *out = s * gain;
                 }
             }
+        });
+
+        let mut output = vec![0.0f32; FRAMES * 2];
+        let mut input = vec![0.5f32; FRAMES * 2];
+        let userdata = &mut *cb_context as *mut CallbackContext as *mut c_void;
+
+        let allocations = crate::rt::count_allocations(|| {
+            for block in 0..1000 {
+                let status = if block % 10 == 0 {
+                    rtaudio_sys::RTAUDIO_STATUS_OUTPUT_UNDERFLOW
+                } else {
+                    0
+                };
+
+                // Safe because both buffers hold `FRAMES` frames of the
+                // channels and format in the info of the context.
+                unsafe {
+                    raw_data_callback(
+                        output.as_mut_ptr() as *mut c_void,
+                        input.as_mut_ptr() as *mut c_void,
+                        FRAMES as c_uint,
+                        block as f64 * FRAMES as f64 / 48000.0,
+                        status,
+                        userdata,
+                    )
+                };
+            }
+        });
+
+        assert_eq!(allocations, 0);
+
+        // Every check actually ran.
+        assert_eq!(
+            shared.frame_counter.load(Ordering::Relaxed),
+            1000 * FRAMES as u64
+        );
+        assert!(shared.limited_samples.load(Ordering::Relaxed) > 0);
+        assert!(shared.silence_events.load(Ordering::Relaxed) > 0);
+        assert_eq!(shared.timing.snapshot().calls, 1000);
+        assert!(overruns.pop().is_some());
+        assert_eq!(shared.status.snapshot().output_underflows, 100);
+    }
+
+    #[test]
+    fn polled_warnings_are_queued_for_take_warnings() {
+        let host = Host::new(Api::Unspecified).unwrap();
+        let (sink, mut warnings) = sink(&host, false);
+
+        let warning = RtAudioWarning {
+            msg: Some(String::from(
+                "RtApiAlsa::callbackEvent: audio write error, underrun.",
+            )),
         };
+        sink.report(LastError::Warning(warning.clone()));
+        sink.report(LastError::Warning(RtAudioWarning { msg: None }));
+
+        // A warning does not stop the stream.
+        assert_eq!(sink.shared.state(), StreamState::Running);
+        assert!(sink.slot.take().is_none());
+
+        assert_eq!(
+            drain_warnings(&mut warnings),
+            vec![warning, RtAudioWarning { msg: None }]
+        );
+        assert!(drain_warnings(&mut warnings).is_empty());
+    }
+
+    #[test]
+    fn host_is_reusable_after_a_failed_open() {
+        let _lock = lock_streams_for_test();
+        let mut host = Host::new(Api::Unspecified).unwrap();
+
+        for _ in 0..3 {
+            // There are no devices, so opening fails in RtAudio itself.
+            let e = host
+                .open_stream(
+                    Some(DeviceParams {
+                        device_id: DeviceID::new(1),
+                        num_channels: 2,
+                        first_channel: 0,
+                    }),
+                    None,
+                    SampleFormat::Float32,
+                    48000,
+                    256,
+                    StreamOptions::default(),
+                    |_| {},
+                )
+                .err()
+                .unwrap();
+            host = e.host;
+
+            assert_eq!(host.take_last_error(), None);
+            assert_eq!(host.iter_devices().count(), 0);
+            assert_eq!(host.take_last_error(), None);
+        }
+    }
+
+    #[test]
+    fn concurrent_streams_are_admitted_one_at_a_time() {
+        let _lock = lock_streams_for_test();
+        let open = Arc::new(AtomicUsize::new(0));
+
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let open = Arc::clone(&open);
+                std::thread::spawn(move || {
+                    let host = Host::new(Api::Unspecified).unwrap();
+                    let (sink, _warnings) = sink(&host, false);
+                    let mut opened = 0;
+
+                    for _ in 0..200 {
+                        if sink.install() {
+                            assert_eq!(open.fetch_add(1, Ordering::SeqCst), 0);
+                            std::thread::yield_now();
+                            open.fetch_sub(1, Ordering::SeqCst);
+                            sink.uninstall();
+                            opened += 1;
+                        }
+                        std::thread::yield_now();
+                    }
+
+                    opened
+                })
+            })
+            .collect();
+
+        let opened: usize = threads.into_iter().map(|t| t.join().unwrap()).sum();
+        assert!(opened > 0);
+        assert!(ERROR_SINK.load(Ordering::Acquire).is_null());
+    }
+
+    #[test]
+    fn concurrent_failed_opens_leave_no_stream_behind() {
+        let _lock = lock_streams_for_test();
+
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                std::thread::spawn(|| {
+                    let mut host = Host::new(Api::Unspecified).unwrap();
+
+                    for _ in 0..10 {
+                        // There are no devices, so this fails either in RtAudio
+                        // or because another thread is opening a stream.
+                        let e = host
+                            .open_stream(
+                                Some(DeviceParams {
+                                    device_id: DeviceID::new(1),
+                                    num_channels: 2,
+                                    first_channel: 0,
+                                }),
+                                None,
+                                SampleFormat::Float32,
+                                48000,
+                                256,
+                                StreamOptions::default(),
+                                |_| {},
+                            )
+                            .err()
+                            .unwrap();
+                        host = e.host;
+                    }
+                })
+            })
+            .collect();
+
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        assert!(ERROR_SINK.load(Ordering::Acquire).is_null());
+    }
+
+    #[test]
+    fn error_callback_does_not_allocate() {
+        let _lock = lock_streams_for_test();
+        let host = Host::new(Api::Unspecified).unwrap();
+        let (sink, mut warnings) = sink(&host, false);
+        assert!(sink.install());
+
+        let msg = c"RtApiAlsa::callbackEvent: the device was disconnected.";
+        let allocations = crate::rt::count_allocations(|| unsafe {
+            raw_error_callback(rtaudio_sys::RTAUDIO_ERROR_WARNING, msg.as_ptr());
+            raw_error_callback(rtaudio_sys::RTAUDIO_ERROR_DEVICE_DISCONNECT, msg.as_ptr());
+            raw_error_callback(rtaudio_sys::RTAUDIO_ERROR_SYSTEM_ERROR, std::ptr::null());
+        });
+        sink.uninstall();
+
+        assert_eq!(allocations, 0);
+
+        // Only the first error is kept.
+        let e = sink.slot.take().unwrap();
+        assert_eq!(e.kind(), RtAudioErrorType::DeviceDisconnect);
+        assert!(sink.slot.take().is_none());
+        assert_eq!(drain_warnings(&mut warnings).len(), 1);
+    }
+
+    /// Run the notifier thread of `sink` until `f` returns.
+    fn with_notifier<R>(
+        sink: &Arc<ErrorSink>,
+        error_callback: impl FnOnce(Error) + Send + 'static,
+        warnings: Option<NotifierWarnings>,
+        f: impl FnOnce() -> R,
+    ) -> R {
+        let notifier = spawn_notifier(
+            Arc::clone(sink),
+            ErrorOrigin::new(Api::Dummy),
+            Box::new(error_callback),
+            warnings,
+        )
+        .unwrap();
+
+        let res = f();
 
-        let e = RtAudioError { type_, msg };
+        sink.closed.store(true, Ordering::Release);
+        notifier.thread().unpark();
+        notifier.join().unwrap();
+
+        res
+    }
+
+    #[test]
+    fn error_channel_receives_the_polled_error() {
+        let host = Host::new(Api::Unspecified).unwrap();
+        let (sink, _warnings) = sink(&host, false);
+        let (error_callback, error_rx) = crate::error_channel::error_channel();
+
+        let e = with_notifier(&sink, error_callback, None, || {
+            assert!(error_rx.try_recv().is_none());
+
+            record_error(&sink);
+            error_rx.recv_timeout(Duration::from_secs(5))
+        })
+        .unwrap();
+
+        assert_eq!(e.kind(), RtAudioErrorType::InvalidParameter);
+        assert_eq!(e.origin().map(|o| o.api), Some(Api::Dummy));
+
+        // The notifier thread has exited, so the channel is disconnected
+        // and there is only ever one error.
+        assert!(error_rx.recv().is_none());
+    }
+
+    #[test]
+    fn notifier_delivers_warnings_to_the_handler() {
+        let host = Host::new(Api::Unspecified).unwrap();
+        let (sink, warnings) = sink(&host, true);
+
+        let (warning_tx, warning_rx) = std::sync::mpsc::channel();
+        let warning_tx = Mutex::new(warning_tx);
+        let handler: WarningHandler = Arc::new(move |warning| {
+            let _ = warning_tx.lock().unwrap().send(warning);
+        });
+
+        let warning = RtAudioWarning {
+            msg: Some(String::from(
+                "RtApiAlsa::probeDeviceInfo: no supported sample rates found for device (hw:1,0).",
+            )),
+        };
+        let received = with_notifier(
+            &sink,
+            |_| {},
+            Some(NotifierWarnings {
+                warnings,
+                handler: Some(handler),
+            }),
+            || {
+                sink.report(LastError::Warning(warning.clone()));
+                warning_rx.recv_timeout(Duration::from_secs(5))
+            },
+        );
+
+        assert_eq!(received, Ok(warning));
+        assert_eq!(sink.shared.state(), StreamState::Running);
+    }
+
+    #[test]
+    fn panicking_error_callback_does_not_block_the_next_stream() {
+        let _lock = lock_streams_for_test();
+        let host = Host::new(Api::Unspecified).unwrap();
+
+        let (first, _warnings) = sink(&host, false);
+        assert!(first.install());
+        let (called_tx, called_rx) = std::sync::mpsc::channel();
+        with_notifier(
+            &first,
+            move |_| {
+                called_tx.send(()).unwrap();
+                panic!("error callback panicked");
+            },
+            None,
+            || {
+                record_error(&first);
+                called_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+            },
+        );
+        // This is what closing the stream does once the notifier thread has
+        // exited (which `with_notifier()` checks did not panic).
+        first.uninstall();
+
+        let (second, _warnings) = sink(&host, false);
+        assert!(second.install());
+        let (error_callback, error_rx) = crate::error_channel::error_channel();
+        let e = with_notifier(&second, error_callback, None, || {
+            record_error(&second);
+            error_rx.recv_timeout(Duration::from_secs(5))
+        });
+        second.uninstall();
+
+        assert_eq!(
+            e.map(|e| e.kind()),
+            Some(RtAudioErrorType::InvalidParameter)
+        );
+    }
 
-        if let Some(cb) = { ERROR_CB_SINGLETON.lock().unwrap().cb.take() } {
-            (cb)(e);
+    #[test]
+    fn stream_state_round_trips_through_its_raw_value() {
+        let states = [
+            StreamState::Closed,
+            StreamState::Open,
+            StreamState::Running,
+            StreamState::Errored {
+                type_: RtAudioErrorType::DeviceDisconnect,
+            },
+            StreamState::Errored {
+                type_: RtAudioErrorType::Unknown,
+            },
+        ];
+
+        for state in states {
+            assert_eq!(StreamState::from_raw(state.to_raw()), state);
         }
     }
 }
@@ -0,0 +1,324 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::task::{Context, Poll, Waker};
+
+use crate::error::ErrorContext;
+use crate::{
+    Api, Buffers, DeviceParams, Error, EventStream, Host, StreamConfig, StreamHandle, StreamInfo,
+    StreamState, StreamStateObserver, StreamStatus,
+};
+
+type DataCallback = Box<dyn FnMut(Buffers<'_>, &StreamInfo, StreamStatus) + Send + 'static>;
+
+/// An audio stream which can be controlled from async code without blocking
+/// the runtime.
+///
+/// Opening, stopping, and closing a stream can block for tens to hundreds
+/// of milliseconds, and neither `Host` nor `StreamHandle` can be sent to
+/// another thread. So the stream is owned by a dedicated thread which this
+/// starts, and the async methods hand the blocking calls to that thread and
+/// wait for the result without blocking. This works with any async runtime
+/// (i.e. tokio), and `StreamTask` can be sent to and shared between tasks.
+///
+/// Dropping a `StreamTask` closes the stream on the owner thread in the
+/// background. Use `StreamTask::close()` to wait for it instead.
+///
+/// ```no_run
+/// use rtaudio::{Api, DeviceParams, StreamConfig, StreamTask};
+/// # fn block_on<F: std::future::Future>(fut: F) -> F::Output {
+/// #     struct ThreadWaker(std::thread::Thread);
+/// #     impl std::task::Wake for ThreadWaker {
+/// #         fn wake(self: std::sync::Arc<Self>) {
+/// #             self.0.unpark();
+/// #         }
+/// #     }
+/// #     let waker = std::task::Waker::from(std::sync::Arc::new(ThreadWaker(std::thread::current())));
+/// #     let mut cx = std::task::Context::from_waker(&waker);
+/// #     let mut fut = Box::pin(fut);
+/// #     loop {
+/// #         if let std::task::Poll::Ready(output) = fut.as_mut().poll(&mut cx) {
+/// #             return output;
+/// #         }
+/// #         std::thread::park();
+/// #     }
+/// # }
+///
+/// # block_on(async {
+/// let task = StreamTask::open(
+///     Api::Unspecified,
+///     Some(DeviceParams::default()),
+///     None,
+///     StreamConfig::default(),
+///     |error| eprintln!("{}", error),
+/// )
+/// .await?;
+///
+/// task.start(|mut buffers, _info, _status| buffers.fill_output_silence())
+///     .await?;
+/// assert!(task.is_running());
+///
+/// task.stop().await?;
+/// task.close().await?;
+/// # Ok::<(), rtaudio::Error>(())
+/// # })?;
+/// # Ok::<(), rtaudio::Error>(())
+/// ```
+pub struct StreamTask {
+    commands: Sender<Command>,
+    info: StreamInfo,
+    observer: StreamStateObserver,
+}
+
+enum Command {
+    Start(DataCallback, ReplySender<Result<(), Error>>),
+    Stop(ReplySender<Result<(), Error>>),
+    Events(ReplySender<EventStream>),
+    Close(ReplySender<Result<(), Error>>),
+}
+
+impl StreamTask {
+    /// Create a host for the given API and open a stream with it on a new
+    /// owner thread (see `Host::open_stream_with_config()`).
+    ///
+    /// Only one stream can be opened at a time (this is a limitation with RtAudio).
+    pub async fn open<E>(
+        api: Api,
+        output_device: Option<DeviceParams>,
+        input_device: Option<DeviceParams>,
+        config: StreamConfig,
+        error_callback: E,
+    ) -> Result<Self, Error>
+    where
+        E: FnOnce(Error) + Send + 'static,
+    {
+        let (commands, commands_rx) = mpsc::channel();
+        let (opened, opened_rx) = reply();
+
+        std::thread::Builder::new()
+            .name(String::from("rtaudio-stream-owner"))
+            .spawn(move || {
+                let stream = Host::new(api).and_then(|host| {
+                    host.open_stream_with_config(
+                        output_device,
+                        input_device,
+                        &config,
+                        error_callback,
+                    )
+                    .map_err(|e| e.error)
+                });
+
+                match stream {
+                    Ok(stream) => {
+                        opened.send(Ok((stream.info().clone(), stream.state_observer())));
+                        run_owner(stream, commands_rx);
+                    }
+                    Err(e) => opened.send(Err(e)),
+                }
+            })
+            .map_err(|e| Error::ThreadError {
+                msg: Some(format!("failed to spawn the stream owner thread: {}", e).into()),
+                context: Some(ErrorContext::OpenStream),
+                origin: None,
+            })?;
+
+        let (info, observer) = opened_rx.await.ok_or_else(owner_stopped)??;
+
+        Ok(Self {
+            commands,
+            info,
+            observer,
+        })
+    }
+
+    /// Start the stream (see `StreamHandle::start()`).
+    pub async fn start<F>(&self, data_callback: F) -> Result<(), Error>
+    where
+        F: FnMut(Buffers<'_>, &StreamInfo, StreamStatus) + Send + 'static,
+    {
+        self.request(|reply| Command::Start(Box::new(data_callback), reply))
+            .await?
+    }
+
+    /// Stop the stream (see `StreamHandle::stop()`).
+    ///
+    /// Returns the error reported by the backend while stopping (if any).
+    /// The stream is considered stopped either way.
+    pub async fn stop(&self) -> Result<(), Error> {
+        self.request(Command::Stop).await?
+    }
+
+    /// Receive the events of the stream (see `StreamHandle::events()`).
+    pub async fn events(&self) -> Result<EventStream, Error> {
+        self.request(Command::Events).await
+    }
+
+    /// Close the stream and wait for the owner thread to finish with it.
+    ///
+    /// Returns the error reported by the backend while closing (if any).
+    pub async fn close(self) -> Result<(), Error> {
+        self.request(Command::Close).await?
+    }
+
+    /// Information about the stream.
+    pub fn info(&self) -> &StreamInfo {
+        &self.info
+    }
+
+    /// The current state of the stream.
+    pub fn state(&self) -> StreamState {
+        self.observer.state()
+    }
+
+    /// Whether the stream is running.
+    pub fn is_running(&self) -> bool {
+        self.observer.state() == StreamState::Running
+    }
+
+    /// The number of seconds of audio that have been processed since the
+    /// stream was last started.
+    pub fn stream_time(&self) -> f64 {
+        self.observer.frame_counter() as f64 / f64::from(self.info.sample_rate)
+    }
+
+    /// The average ratio of the time the data callback takes to run to the
+    /// time budget of a process call (see `CallbackTiming::avg`).
+    ///
+    /// This is `None` if `StreamOptions::callback_budget` is not set, or if
+    /// nothing has been measured yet.
+    pub fn cpu_load(&self) -> Option<f32> {
+        let timing = self.observer.callback_timing();
+        if timing.calls == 0 || self.info.max_frames == 0 {
+            return None;
+        }
+
+        let budget = self.info.max_frames as f64 / f64::from(self.info.sample_rate);
+        Some((timing.avg.as_secs_f64() / budget) as f32)
+    }
+
+    /// An observer of the state of the stream, which stays valid after the
+    /// stream is closed.
+    pub fn state_observer(&self) -> StreamStateObserver {
+        self.observer.clone()
+    }
+
+    /// Send a command to the owner thread and wait for its reply.
+    async fn request<T>(
+        &self,
+        command: impl FnOnce(ReplySender<T>) -> Command,
+    ) -> Result<T, Error> {
+        let (reply, reply_rx) = reply();
+
+        self.commands
+            .send(command(reply))
+            .map_err(|_| owner_stopped())?;
+
+        reply_rx.await.ok_or_else(owner_stopped)
+    }
+}
+
+impl std::fmt::Debug for StreamTask {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StreamTask")
+            .field("state", &self.state())
+            .field("info", &self.info)
+            .finish()
+    }
+}
+
+/// Run the commands sent to the owner thread until the stream is closed,
+/// or until the `StreamTask` is dropped.
+fn run_owner(mut stream: StreamHandle, commands: Receiver<Command>) {
+    while let Ok(command) = commands.recv() {
+        match command {
+            Command::Start(data_callback, reply) => reply.send(stream.start(data_callback)),
+            Command::Stop(reply) => reply.send(stream.try_stop()),
+            Command::Events(reply) => reply.send(stream.events()),
+            Command::Close(reply) => {
+                reply.send(stream.try_close().map(drop).map_err(|e| e.error));
+                return;
+            }
+        }
+    }
+}
+
+fn owner_stopped() -> Error {
+    Error::ThreadError {
+        msg: Some("the stream owner thread has stopped".into()),
+        context: None,
+        origin: None,
+    }
+}
+
+/// Create a one-shot channel for the reply to a command.
+fn reply<T>() -> (ReplySender<T>, ReplyReceiver<T>) {
+    let shared = Arc::new(Mutex::new(ReplyState {
+        value: None,
+        waker: None,
+        done: false,
+    }));
+
+    (
+        ReplySender {
+            shared: Arc::clone(&shared),
+        },
+        ReplyReceiver { shared },
+    )
+}
+
+struct ReplyState<T> {
+    value: Option<T>,
+    waker: Option<Waker>,
+    /// Set once the sender has been used or dropped.
+    done: bool,
+}
+
+fn lock<T>(shared: &Mutex<ReplyState<T>>) -> MutexGuard<'_, ReplyState<T>> {
+    shared.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+struct ReplySender<T> {
+    shared: Arc<Mutex<ReplyState<T>>>,
+}
+
+impl<T> ReplySender<T> {
+    fn send(self, value: T) {
+        lock(&self.shared).value = Some(value);
+        // Dropping `self` wakes the receiver.
+    }
+}
+
+impl<T> Drop for ReplySender<T> {
+    fn drop(&mut self) {
+        let mut state = lock(&self.shared);
+        state.done = true;
+        let waker = state.waker.take();
+        drop(state);
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+/// Resolves to the reply, or to `None` if the owner thread stopped without
+/// replying.
+struct ReplyReceiver<T> {
+    shared: Arc<Mutex<ReplyState<T>>>,
+}
+
+impl<T> Future for ReplyReceiver<T> {
+    type Output = Option<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = lock(&self.shared);
+
+        if state.done {
+            return Poll::Ready(state.value.take());
+        }
+
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
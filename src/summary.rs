@@ -0,0 +1,228 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::{Api, DeviceID, InterleaveMode, Negotiated, SampleFormat, StreamFlags};
+
+/// A device which is used by a stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SummaryDevice {
+    /// The ID of the device.
+    pub id: DeviceID,
+    /// The name of the device, if it could be obtained.
+    pub name: Option<String>,
+    /// The number of channels which are used by the stream.
+    pub channels: usize,
+    /// The index of the first channel of the device which is used by the
+    /// stream.
+    pub first_channel: usize,
+}
+
+impl fmt::Display for SummaryDevice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "\"{}\" {}ch", name, self.channels)?,
+            None => write!(f, "(ID {}) {}ch", self.id, self.channels)?,
+        }
+
+        if self.first_channel != 0 {
+            write!(f, " from channel {}", self.first_channel)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The effective configuration of an open stream, gathered in one place for
+/// logs and bug reports.
+///
+/// This is returned by `StreamHandle::summary()`. The `Display` output fits
+/// on one line:
+///
+/// ```text
+/// WASAPI out "Speakers" 2ch + in "Mic" 1ch, Float32 @ 48000 Hz, 512 frames (asked 256), latency 960, interleaved, flags: SCHEDULE_REALTIME
+/// ```
+///
+/// Use `StreamSummary::to_map()` (or the `serde` feature) to send it
+/// somewhere in a structured form instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StreamSummary {
+    /// The API of the stream.
+    pub api: Api,
+    /// The output device, if there is one.
+    pub output: Option<SummaryDevice>,
+    /// The input device, if there is one.
+    pub input: Option<SummaryDevice>,
+    /// The sample format.
+    pub sample_format: SampleFormat,
+    /// The sample rate.
+    pub sample_rate: Negotiated<u32>,
+    /// The maximum number of frames in each process call.
+    pub buffer_frames: Negotiated<u32>,
+    /// The internal latency in frames, if the API reports it.
+    pub latency: Option<usize>,
+    /// How the samples are laid out in the buffers.
+    pub interleave_mode: InterleaveMode,
+    /// The stream flags which were passed to the backend, including the
+    /// flags added by `StreamOptions::priority` and
+    /// `StreamOptions::api_specific`.
+    pub flags: StreamFlags,
+}
+
+impl StreamSummary {
+    /// The summary as a flat map of strings, i.e. for telemetry.
+    ///
+    /// Devices which are not used, and a latency which is not reported, are
+    /// left out. Flags are listed by name, separated by `|`.
+    ///
+    /// ```
+    /// use rtaudio::{
+    ///     Api, DeviceID, InterleaveMode, Negotiated, SampleFormat, StreamFlags, StreamSummary,
+    ///     SummaryDevice,
+    /// };
+    ///
+    /// let summary = StreamSummary {
+    ///     api: Api::WindowsWASAPI,
+    ///     output: Some(SummaryDevice {
+    ///         id: DeviceID::new(3).unwrap(),
+    ///         name: Some(String::from("Speakers")),
+    ///         channels: 2,
+    ///         first_channel: 0,
+    ///     }),
+    ///     input: None,
+    ///     sample_format: SampleFormat::Float32,
+    ///     sample_rate: Negotiated { requested: 48000, actual: 48000 },
+    ///     buffer_frames: Negotiated { requested: 256, actual: 512 },
+    ///     latency: Some(960),
+    ///     interleave_mode: InterleaveMode::Interleaved,
+    ///     flags: StreamFlags::SCHEDULE_REALTIME | StreamFlags::MINIMIZE_LATENCY,
+    /// };
+    ///
+    /// assert_eq!(
+    ///     summary.to_string(),
+    ///     "WASAPI out \"Speakers\" 2ch, Float32 @ 48000 Hz, 512 frames (asked 256), \
+    ///      latency 960, interleaved, flags: MINIMIZE_LATENCY | SCHEDULE_REALTIME"
+    /// );
+    ///
+    /// let map = summary.to_map();
+    /// assert_eq!(map["api"], "wasapi");
+    /// assert_eq!(map["output_device_name"], "Speakers");
+    /// assert_eq!(map["buffer_frames"], "512");
+    /// assert_eq!(map["requested_buffer_frames"], "256");
+    /// assert_eq!(map["flags"], "MINIMIZE_LATENCY|SCHEDULE_REALTIME");
+    /// assert!(!map.contains_key("input_device_id"));
+    /// ```
+    pub fn to_map(&self) -> BTreeMap<&'static str, String> {
+        let mut map = BTreeMap::new();
+
+        map.insert("api", self.api.to_string());
+
+        for (device, keys) in [
+            (
+                &self.output,
+                [
+                    "output_device_id",
+                    "output_device_name",
+                    "output_channels",
+                    "output_first_channel",
+                ],
+            ),
+            (
+                &self.input,
+                [
+                    "input_device_id",
+                    "input_device_name",
+                    "input_channels",
+                    "input_first_channel",
+                ],
+            ),
+        ] {
+            let Some(device) = device else {
+                continue;
+            };
+            let [id, name, channels, first_channel] = keys;
+
+            map.insert(id, device.id.to_string());
+            if let Some(device_name) = &device.name {
+                map.insert(name, device_name.clone());
+            }
+            map.insert(channels, device.channels.to_string());
+            map.insert(first_channel, device.first_channel.to_string());
+        }
+
+        map.insert("sample_format", format!("{:?}", self.sample_format));
+        map.insert("sample_rate", self.sample_rate.actual.to_string());
+        map.insert(
+            "requested_sample_rate",
+            self.sample_rate.requested.to_string(),
+        );
+        map.insert("buffer_frames", self.buffer_frames.actual.to_string());
+        map.insert(
+            "requested_buffer_frames",
+            self.buffer_frames.requested.to_string(),
+        );
+        if let Some(latency) = self.latency {
+            map.insert("latency", latency.to_string());
+        }
+        map.insert(
+            "interleave_mode",
+            interleave_mode_name(self.interleave_mode).to_string(),
+        );
+        map.insert("flags", flag_names(self.flags).join("|"));
+
+        map
+    }
+}
+
+impl fmt::Display for StreamSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.api.display_name_static())?;
+
+        match (&self.output, &self.input) {
+            (Some(output), Some(input)) => write!(f, " out {} + in {}", output, input)?,
+            (Some(output), None) => write!(f, " out {}", output)?,
+            (None, Some(input)) => write!(f, " in {}", input)?,
+            (None, None) => {}
+        }
+
+        write!(
+            f,
+            ", {:?} @ {} Hz",
+            self.sample_format, self.sample_rate.actual
+        )?;
+        if self.sample_rate.changed() {
+            write!(f, " (asked {})", self.sample_rate.requested)?;
+        }
+
+        write!(f, ", {} frames", self.buffer_frames.actual)?;
+        if self.buffer_frames.changed() {
+            write!(f, " (asked {})", self.buffer_frames.requested)?;
+        }
+
+        match self.latency {
+            Some(latency) => write!(f, ", latency {}", latency)?,
+            None => write!(f, ", latency unknown")?,
+        }
+
+        write!(f, ", {}", interleave_mode_name(self.interleave_mode))?;
+
+        let flags = flag_names(self.flags);
+        if flags.is_empty() {
+            write!(f, ", flags: none")
+        } else {
+            write!(f, ", flags: {}", flags.join(" | "))
+        }
+    }
+}
+
+fn interleave_mode_name(mode: InterleaveMode) -> &'static str {
+    match mode {
+        InterleaveMode::Interleaved => "interleaved",
+        InterleaveMode::Planar => "planar",
+    }
+}
+
+fn flag_names(flags: StreamFlags) -> Vec<&'static str> {
+    flags.iter_names().map(|(name, _)| name).collect()
+}
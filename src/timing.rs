@@ -0,0 +1,148 @@
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::time::Duration;
+
+/// A summary of how long the data callback has taken to run since the
+/// stream was last started.
+///
+/// This is only measured if `StreamOptions::callback_budget` is set. See
+/// `StreamHandle::callback_timing()`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CallbackTiming {
+    /// The number of process calls which were measured.
+    pub calls: u64,
+    /// The shortest time the data callback took to run.
+    pub min: Duration,
+    /// The longest time the data callback took to run.
+    pub max: Duration,
+    /// The average time the data callback took to run.
+    pub avg: Duration,
+    /// The highest ratio of the time the data callback took to run to the
+    /// time budget of the process call (`frames / sample_rate`). A value
+    /// of `1.0` or more means that the deadline was missed.
+    pub peak_load: f32,
+    /// The number of process calls in which the data callback took longer
+    /// than allowed by `CallbackBudget::budget_fraction`.
+    pub overruns: u64,
+}
+
+/// A process call in which the data callback took longer than allowed by
+/// the `CallbackBudget`.
+///
+/// See `StreamHandle::take_callback_overruns()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CallbackOverrun {
+    /// The value of `StreamInfo::frame_counter` in the process call.
+    pub frame: u64,
+    /// The number of frames in the process call.
+    pub frames: usize,
+    /// The time the data callback took to run.
+    pub duration: Duration,
+    /// The time budget of the process call (`frames / sample_rate`).
+    pub budget: Duration,
+}
+
+/// The callback timing which is published by the audio thread.
+///
+/// Only the audio thread writes to this, so each value is simply stored.
+/// The values are read independently of each other, so some of the values
+/// a reader sees may already include the next process call.
+#[derive(Debug, Default)]
+pub(crate) struct SharedTiming {
+    calls: AtomicU64,
+    total_ns: AtomicU64,
+    min_ns: AtomicU64,
+    max_ns: AtomicU64,
+    peak_load: AtomicU32,
+    overruns: AtomicU64,
+}
+
+impl SharedTiming {
+    pub(crate) fn snapshot(&self) -> CallbackTiming {
+        let calls = self.calls.load(Ordering::Acquire);
+        if calls == 0 {
+            return CallbackTiming::default();
+        }
+
+        CallbackTiming {
+            calls,
+            min: Duration::from_nanos(self.min_ns.load(Ordering::Relaxed)),
+            max: Duration::from_nanos(self.max_ns.load(Ordering::Relaxed)),
+            avg: Duration::from_nanos(self.total_ns.load(Ordering::Relaxed) / calls),
+            peak_load: f32::from_bits(self.peak_load.load(Ordering::Relaxed)),
+            overruns: self.overruns.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// The callback timing as it is accumulated on the audio thread.
+#[derive(Debug, Default)]
+pub(crate) struct TimingAccumulator {
+    calls: u64,
+    total_ns: u64,
+    min_ns: u64,
+    max_ns: u64,
+    peak_load: f32,
+    overruns: u64,
+}
+
+impl TimingAccumulator {
+    /// Reset the timing. This must only be called while the stream is not
+    /// running.
+    pub(crate) fn reset(&mut self, shared: &SharedTiming) {
+        *self = Self::default();
+        self.publish(shared);
+    }
+
+    /// Record a single process call.
+    ///
+    /// Returns `true` if the call took longer than `budget_fraction` of
+    /// `budget`.
+    ///
+    /// This does not lock or allocate.
+    pub(crate) fn record(
+        &mut self,
+        duration: Duration,
+        budget: Duration,
+        budget_fraction: f32,
+        shared: &SharedTiming,
+    ) -> bool {
+        let ns = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+
+        self.min_ns = if self.calls == 0 {
+            ns
+        } else {
+            self.min_ns.min(ns)
+        };
+        self.max_ns = self.max_ns.max(ns);
+        self.total_ns = self.total_ns.saturating_add(ns);
+        self.calls += 1;
+
+        let mut overrun = false;
+        if !budget.is_zero() {
+            let load = (duration.as_secs_f64() / budget.as_secs_f64()) as f32;
+            self.peak_load = self.peak_load.max(load);
+
+            if load > budget_fraction {
+                self.overruns += 1;
+                overrun = true;
+            }
+        }
+
+        self.publish(shared);
+
+        overrun
+    }
+
+    fn publish(&self, shared: &SharedTiming) {
+        shared.min_ns.store(self.min_ns, Ordering::Relaxed);
+        shared.max_ns.store(self.max_ns, Ordering::Relaxed);
+        shared.total_ns.store(self.total_ns, Ordering::Relaxed);
+        shared
+            .peak_load
+            .store(self.peak_load.to_bits(), Ordering::Relaxed);
+        shared.overruns.store(self.overruns, Ordering::Relaxed);
+        // Publish this last, so that a reader which sees a count also sees
+        // the values which were stored along with it.
+        shared.calls.store(self.calls, Ordering::Release);
+    }
+}
@@ -0,0 +1,156 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// The version of RtAudio, parsed from the string returned by
+/// `rtaudio::version()`.
+///
+/// Versions are compared (and hashed) by their numeric `major.minor.patch`
+/// triple only. `raw` is kept for display and logging.
+///
+/// ```
+/// use rtaudio::VersionInfo;
+///
+/// let version = VersionInfo::parse("6.0.1");
+/// assert_eq!((version.major, version.minor, version.patch), (6, 0, 1));
+/// assert!(version >= VersionInfo::new(6, 0, 1));
+/// assert!(version < VersionInfo::new(6, 1, 0));
+///
+/// // Missing components default to 0.
+/// assert_eq!(VersionInfo::parse("6"), VersionInfo::new(6, 0, 0));
+/// assert_eq!(VersionInfo::parse("5.2"), VersionInfo::new(5, 2, 0));
+///
+/// // Suffixes (e.g. from distro patches) are ignored, but kept in `raw`.
+/// let patched = VersionInfo::parse("6.0.1-2ubuntu1");
+/// assert_eq!(patched, VersionInfo::new(6, 0, 1));
+/// assert_eq!(patched.raw, "6.0.1-2ubuntu1");
+/// assert_eq!(VersionInfo::parse("v5.1.0rc1"), VersionInfo::new(5, 1, 0));
+/// assert_eq!(VersionInfo::parse(" 6.0.1\n"), VersionInfo::new(6, 0, 1));
+///
+/// // Anything which cannot be parsed falls back to zeros.
+/// for raw in ["", "error", "six", ".1.2", "99999999999.1"] {
+///     let version = VersionInfo::parse(raw);
+///     assert_eq!(version, VersionInfo::new(0, 0, 0), "{:?}", raw);
+///     assert_eq!(version.raw, raw);
+/// }
+/// // Parsing stops at the first component which is not a number.
+/// assert_eq!(VersionInfo::parse("6.x.1"), VersionInfo::new(6, 0, 0));
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VersionInfo {
+    /// The major version.
+    pub major: u32,
+    /// The minor version.
+    pub minor: u32,
+    /// The patch version.
+    pub patch: u32,
+    /// The version string as it was returned by RtAudio.
+    pub raw: String,
+}
+
+impl VersionInfo {
+    /// A version with the given numbers, e.g. to compare against.
+    pub fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+            raw: format!("{}.{}.{}", major, minor, patch),
+        }
+    }
+
+    /// Parse a version string leniently. This never fails.
+    ///
+    /// Leading and trailing whitespace and a leading `v` are ignored. Each
+    /// of the up to three `.`-separated components is read up to its first
+    /// non-digit, and parsing stops at the first component which has a
+    /// suffix or which is not a number. Missing components are `0`.
+    pub fn parse(raw: &str) -> Self {
+        let mut numbers = [0u32; 3];
+
+        let s = raw.trim();
+        let s = s.strip_prefix(['v', 'V']).unwrap_or(s);
+
+        for (number, component) in numbers.iter_mut().zip(s.split('.')) {
+            let num_digits = component.bytes().take_while(|b| b.is_ascii_digit()).count();
+
+            let Ok(n) = component[..num_digits].parse() else {
+                break;
+            };
+            *number = n;
+
+            if num_digits < component.len() {
+                break;
+            }
+        }
+
+        let [major, minor, patch] = numbers;
+
+        Self {
+            major,
+            minor,
+            patch,
+            raw: raw.to_string(),
+        }
+    }
+
+    /// The oldest version of RtAudio which this crate works with.
+    pub fn min_supported() -> Self {
+        Self::new(6, 0, 0)
+    }
+
+    /// Whether or not this version is at least
+    /// `VersionInfo::min_supported()`.
+    ///
+    /// The bundled copy of RtAudio always is, so this is only useful to
+    /// double-check a copy of RtAudio which was linked in some other way.
+    ///
+    /// ```
+    /// use rtaudio::VersionInfo;
+    ///
+    /// assert!(rtaudio::version_info().is_supported());
+    /// assert!(VersionInfo::parse("6.0.1").is_supported());
+    /// assert!(!VersionInfo::parse("5.2.0").is_supported());
+    /// ```
+    pub fn is_supported(&self) -> bool {
+        *self >= Self::min_supported()
+    }
+
+    fn triple(&self) -> (u32, u32, u32) {
+        (self.major, self.minor, self.patch)
+    }
+}
+
+impl PartialEq for VersionInfo {
+    fn eq(&self, other: &Self) -> bool {
+        self.triple() == other.triple()
+    }
+}
+
+impl Eq for VersionInfo {}
+
+impl PartialOrd for VersionInfo {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for VersionInfo {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.triple().cmp(&other.triple())
+    }
+}
+
+impl Hash for VersionInfo {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.triple().hash(state);
+    }
+}
+
+/// Displays the raw version string.
+impl fmt::Display for VersionInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
@@ -0,0 +1,729 @@
+//! Recording streams to WAV files, and playing WAV files.
+//!
+//! This module is only available with the `wav` feature. Files are read and
+//! written with [`hound`], which is re-exported so the `WavSpec` in
+//! `WavStats` can be named without depending on it separately.
+//!
+//! ```
+//! use rtaudio::wav::{hound, spec_for_stream};
+//! use rtaudio::SampleFormat;
+//!
+//! // 8-bit WAV files are unsigned, so 8-bit streams are recorded as 16-bit.
+//! let spec = spec_for_stream(SampleFormat::SInt8, 2, 48000);
+//! assert_eq!(spec.bits_per_sample, 16);
+//! assert_eq!(spec.sample_format, hound::SampleFormat::Int);
+//!
+//! let spec = spec_for_stream(SampleFormat::Float64, 1, 44100);
+//! assert_eq!(spec.bits_per_sample, 32);
+//! assert_eq!(spec.sample_format, hound::SampleFormat::Float);
+//! ```
+
+use std::fmt;
+use std::io::{self, Read, Seek, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+pub use hound;
+
+use crate::{Error, OutputWriter, SampleFormat, StreamHandle};
+
+/// How long `record_to_file()` waits for more input when the buffer is
+/// empty.
+const RECORD_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// How much input the buffer of `record_to_file()` can hold, in seconds.
+const RECORD_BUFFER_SECS: usize = 2;
+
+/// How much output the buffer of `play_file()` can hold, in seconds.
+const PLAY_BUFFER_SECS: usize = 1;
+
+/// How long the reader thread of `play_file()` waits for space in the
+/// buffer.
+const PLAY_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// The number of frames which are read from a file at once.
+const BLOCK_FRAMES: usize = 1024;
+
+/// The layout of the WAV file which `record_to_file()` writes for a stream
+/// with the given sample format, number of input channels and sample rate.
+///
+/// 8-bit streams are recorded as 16-bit (8-bit WAV files are unsigned),
+/// and 64-bit float streams as 32-bit float.
+pub fn spec_for_stream(format: SampleFormat, channels: u16, sample_rate: u32) -> hound::WavSpec {
+    let (bits_per_sample, sample_format) = match format {
+        SampleFormat::SInt8 | SampleFormat::SInt16 => (16, hound::SampleFormat::Int),
+        SampleFormat::SInt24 => (24, hound::SampleFormat::Int),
+        SampleFormat::SInt32 => (32, hound::SampleFormat::Int),
+        SampleFormat::Float32 | SampleFormat::Float64 => (32, hound::SampleFormat::Float),
+    };
+
+    hound::WavSpec {
+        channels,
+        sample_rate,
+        bits_per_sample,
+        sample_format,
+    }
+}
+
+/// Write interleaved samples, converted to the sample format of `writer`.
+/// Samples outside of `-1.0..=1.0` are clipped when written as integers.
+fn write_samples<W: Write + Seek>(
+    writer: &mut hound::WavWriter<W>,
+    samples: &[f32],
+) -> Result<(), hound::Error> {
+    let spec = writer.spec();
+
+    match (spec.sample_format, spec.bits_per_sample) {
+        (hound::SampleFormat::Float, _) => {
+            for &s in samples {
+                writer.write_sample(s)?;
+            }
+        }
+        (hound::SampleFormat::Int, 16) => {
+            for &s in samples {
+                writer.write_sample((s * 32_768.0).round().clamp(-32_768.0, 32_767.0) as i16)?;
+            }
+        }
+        (hound::SampleFormat::Int, bits) => {
+            let scale = f64::from(1u32 << (bits - 1));
+            for &s in samples {
+                let value = (f64::from(s) * scale).round().clamp(-scale, scale - 1.0);
+                writer.write_sample(value as i32)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Stops a recording started with `record_to_file()` from another thread.
+#[derive(Debug, Clone, Default)]
+pub struct StopHandle {
+    stopped: Arc<AtomicBool>,
+}
+
+impl StopHandle {
+    /// Create a handle which has not been stopped.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stop the recording. It is finalized once the input which was already
+    /// captured has been written.
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Release);
+    }
+
+    /// Whether `StopHandle::stop()` has been called.
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Acquire)
+    }
+}
+
+/// When `record_to_file()` stops recording.
+#[derive(Debug, Clone)]
+pub enum RecordUntil {
+    /// Record this much audio.
+    Duration(Duration),
+    /// Record until the handle is stopped.
+    Stopped(StopHandle),
+}
+
+/// Statistics of a recording made with `record_to_file()`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WavStats {
+    /// The layout of the file that was written.
+    pub spec: hound::WavSpec,
+    /// The number of frames written to the file.
+    pub frames_written: u64,
+    /// The number of frames which were dropped because the file could not
+    /// be written fast enough.
+    pub frames_dropped: u64,
+    /// The length of the recording (`frames_written / sample_rate`).
+    pub duration: Duration,
+}
+
+/// An error which occurred while recording with `record_to_file()` or
+/// playing with `play_file()`.
+#[derive(Debug)]
+pub enum WavError {
+    /// The stream could not be started.
+    Stream(Error),
+    /// The file could not be read or written.
+    Wav(hound::Error),
+    /// The sample rate of the file does not match the stream. Files are not
+    /// resampled, so the stream has to be opened at the rate of the file.
+    SampleRateMismatch {
+        /// The sample rate of the file.
+        file: u32,
+        /// The sample rate of the stream.
+        stream: u32,
+    },
+}
+
+impl std::error::Error for WavError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Stream(e) => Some(e),
+            Self::Wav(e) => Some(e),
+            Self::SampleRateMismatch { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for WavError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Stream(e) => write!(f, "failed to start the stream: {}", e),
+            Self::Wav(e) => write!(f, "failed to read or write the WAV file: {}", e),
+            Self::SampleRateMismatch { file, stream } => write!(
+                f,
+                "cannot play a {} Hz WAV file on a {} Hz stream: files are not resampled, so open the stream at {} Hz",
+                file, stream, file
+            ),
+        }
+    }
+}
+
+impl From<Error> for WavError {
+    fn from(e: Error) -> Self {
+        Self::Stream(e)
+    }
+}
+
+impl From<hound::Error> for WavError {
+    fn from(e: hound::Error) -> Self {
+        Self::Wav(e)
+    }
+}
+
+/// Record the input of `stream` to a WAV file at `path`, blocking until
+/// `until` is reached. The stream is stopped afterwards.
+///
+/// The layout of the file is chosen from the stream (see
+/// `spec_for_stream()`). The stream is started with
+/// `StreamHandle::start_input_capture()`, and the calling thread writes the
+/// captured input to the file. If writing falls behind, then frames are
+/// dropped (and counted in `WavStats::frames_dropped`) rather than blocking
+/// the audio thread. The header is finalized when recording stops, also if
+/// writing fails.
+///
+/// ```no_run
+/// use rtaudio::wav::RecordUntil;
+/// use rtaudio::{Api, DeviceParams, SampleFormat};
+/// use std::time::Duration;
+///
+/// let host = rtaudio::Host::new(Api::Unspecified)?;
+/// let mut stream = host
+///     .stream_builder()
+///     .input_device(DeviceParams::default())
+///     .sample_format(SampleFormat::SInt16)
+///     .open(|error| eprintln!("{}", error))
+///     .map_err(|e| e.error)?;
+///
+/// let stats = rtaudio::wav::record_to_file(
+///     "recording.wav",
+///     &mut stream,
+///     RecordUntil::Duration(Duration::from_secs(5)),
+/// )?;
+/// println!("recorded {:?}, dropped {} frames", stats.duration, stats.frames_dropped);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn record_to_file(
+    path: impl AsRef<Path>,
+    stream: &mut StreamHandle,
+    until: RecordUntil,
+) -> Result<WavStats, WavError> {
+    let info = stream.info();
+    let spec = spec_for_stream(
+        info.sample_format,
+        u16::try_from(info.in_channels).unwrap_or(u16::MAX),
+        info.sample_rate,
+    );
+    let max_frames = match &until {
+        RecordUntil::Duration(duration) => {
+            (duration.as_secs_f64() * f64::from(spec.sample_rate)).round() as u64
+        }
+        RecordUntil::Stopped(_) => u64::MAX,
+    };
+
+    let mut reader = stream.start_input_capture(spec.sample_rate as usize * RECORD_BUFFER_SECS)?;
+    let mut writer = match hound::WavWriter::create(path, spec) {
+        Ok(writer) => writer,
+        Err(e) => {
+            stream.stop();
+            return Err(e.into());
+        }
+    };
+
+    let channels = reader.channels();
+    let mut block = vec![0.0f32; BLOCK_FRAMES * channels];
+    let mut frames = 0;
+    let mut stopped = false;
+
+    let res = loop {
+        if let RecordUntil::Stopped(handle) = &until {
+            if !stopped && handle.is_stopped() {
+                // Write what was already captured before finishing.
+                stream.stop();
+                stopped = true;
+            }
+        }
+
+        let n = reader.read(&mut block);
+        let remaining = usize::try_from(max_frames - frames).unwrap_or(usize::MAX);
+        let n = n.min(remaining.saturating_mul(channels));
+        frames += (n / channels) as u64;
+
+        if let Err(e) = write_samples(&mut writer, &block[..n]) {
+            break Err(e);
+        }
+        if frames >= max_frames || (stopped && reader.available() == 0) {
+            break Ok(());
+        }
+        if n == 0 {
+            std::thread::sleep(RECORD_POLL_INTERVAL);
+        }
+    };
+
+    stream.stop();
+
+    let frames_written = u64::from(writer.duration());
+    let frames_dropped = reader.overruns();
+    let finalized = writer.finalize();
+    res?;
+    finalized?;
+
+    Ok(WavStats {
+        spec,
+        frames_written,
+        frames_dropped,
+        duration: Duration::from_secs_f64(frames_written as f64 / f64::from(spec.sample_rate)),
+    })
+}
+
+/// The playback of a file started with `play_file()`.
+///
+/// Dropping this stops reading the file, but not the stream.
+pub struct PlaybackHandle {
+    shared: Arc<PlaybackShared>,
+    thread: Option<JoinHandle<Result<(), hound::Error>>>,
+}
+
+struct PlaybackShared {
+    frames: AtomicU64,
+    finished: AtomicBool,
+    cancelled: AtomicBool,
+}
+
+impl PlaybackHandle {
+    /// The number of frames of the file which have been handed to the
+    /// stream (or are waiting in its buffer).
+    pub fn frames_queued(&self) -> u64 {
+        self.shared.frames.load(Ordering::Relaxed)
+    }
+
+    /// Whether the whole file has been handed to the device, or reading it
+    /// failed. The stream then stops once the device has played it.
+    pub fn is_finished(&self) -> bool {
+        self.shared.finished.load(Ordering::Acquire)
+    }
+
+    /// Wait until the file has finished playing, or until `timeout` has
+    /// elapsed.
+    ///
+    /// Returns `true` if playback finished in time.
+    pub fn wait_until_finished(&self, timeout: Duration) -> bool {
+        let start = std::time::Instant::now();
+
+        loop {
+            if self.is_finished() {
+                return true;
+            }
+            if start.elapsed() >= timeout {
+                return false;
+            }
+
+            std::thread::sleep(PLAY_POLL_INTERVAL);
+        }
+    }
+
+    /// Stop reading the file, and wait for the reader thread to exit.
+    ///
+    /// Returns the error which stopped reading the file early (if any).
+    pub fn stop(mut self) -> Result<(), hound::Error> {
+        self.shared.cancelled.store(true, Ordering::Release);
+        self.join()
+    }
+
+    fn join(&mut self) -> Result<(), hound::Error> {
+        match self.thread.take().map(JoinHandle::join) {
+            Some(Ok(res)) => res,
+            Some(Err(_)) => Err(hound::Error::IoError(io::Error::other(
+                "the WAV reader thread panicked",
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for PlaybackHandle {
+    fn drop(&mut self) {
+        self.shared.cancelled.store(true, Ordering::Release);
+        let _ = self.join();
+    }
+}
+
+impl fmt::Debug for PlaybackHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PlaybackHandle")
+            .field("frames_queued", &self.frames_queued())
+            .field("finished", &self.is_finished())
+            .finish()
+    }
+}
+
+/// Play the WAV file at `path` on `stream`.
+///
+/// The header is read here, and the stream is started with
+/// `StreamHandle::start_output_playback()`. A reader thread then reads and
+/// converts the file ahead of the stream, so the audio thread never touches
+/// the disk. Every format which hound reads can be played, including 8-bit
+/// (unsigned) files. Once the whole file has been handed to the device,
+/// `PlaybackHandle::is_finished()` returns `true`, and the stream stops
+/// itself after the buffered audio has played (its state goes back to
+/// `StreamState::Open`). The same happens if reading the file fails part of
+/// the way through.
+///
+/// The channels of the file are mapped to the channels of the stream the
+/// same way as `Buffers::route_input_to_output()`:
+///
+/// * A mono file is copied to every output channel.
+/// * Otherwise each channel of the file is copied to the output channel
+///   with the same index. Extra channels of the file are discarded, and
+///   extra output channels are filled with silence.
+///
+/// Files are not resampled: `WavError::SampleRateMismatch` is returned
+/// (before the stream is started) if the file does not have the sample rate
+/// of the stream.
+///
+/// ```no_run
+/// use rtaudio::{Api, DeviceParams};
+/// use std::time::Duration;
+///
+/// let reader = rtaudio::wav::hound::WavReader::open("song.wav")?;
+/// let sample_rate = reader.spec().sample_rate;
+/// let duration = Duration::from_secs_f64(f64::from(reader.duration()) / f64::from(sample_rate));
+///
+/// let host = rtaudio::Host::new(Api::Unspecified)?;
+/// let mut stream = host
+///     .stream_builder()
+///     .output_device(DeviceParams::default())
+///     .sample_rate(sample_rate)
+///     .open(|error| eprintln!("{}", error))
+///     .map_err(|e| e.error)?;
+///
+/// let playback = rtaudio::wav::play_file("song.wav", &mut stream)?;
+/// playback.wait_until_finished(duration + Duration::from_secs(1));
+/// playback.stop()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn play_file(
+    path: impl AsRef<Path>,
+    stream: &mut StreamHandle,
+) -> Result<PlaybackHandle, WavError> {
+    let reader = hound::WavReader::open(path)?;
+    let spec = reader.spec();
+
+    let sample_rate = stream.info().sample_rate;
+    if spec.sample_rate != sample_rate {
+        return Err(WavError::SampleRateMismatch {
+            file: spec.sample_rate,
+            stream: sample_rate,
+        });
+    }
+
+    let mut writer = stream.start_output_playback(sample_rate as usize * PLAY_BUFFER_SECS)?;
+
+    let shared = Arc::new(PlaybackShared {
+        frames: AtomicU64::new(0),
+        finished: AtomicBool::new(false),
+        cancelled: AtomicBool::new(false),
+    });
+    let thread_shared = Arc::clone(&shared);
+
+    let thread = std::thread::Builder::new()
+        .name(String::from("rtaudio-wav-reader"))
+        .spawn(move || {
+            let shared = thread_shared;
+            let res = feed_output(
+                samples_f32(reader),
+                usize::from(spec.channels),
+                &mut writer,
+                &shared,
+            );
+
+            if !shared.cancelled.load(Ordering::Acquire) {
+                writer.stop_when_drained();
+
+                while !writer.flush_blocking(PLAY_POLL_INTERVAL) {
+                    if shared.cancelled.load(Ordering::Acquire) {
+                        break;
+                    }
+                }
+            }
+
+            shared.finished.store(true, Ordering::Release);
+            res
+        });
+
+    let thread = match thread {
+        Ok(thread) => thread,
+        Err(e) => {
+            stream.stop();
+            return Err(hound::Error::IoError(e).into());
+        }
+    };
+
+    Ok(PlaybackHandle {
+        shared,
+        thread: Some(thread),
+    })
+}
+
+/// The samples of the file, converted to `f32`.
+fn samples_f32<R: Read + Send + 'static>(
+    reader: hound::WavReader<R>,
+) -> Box<dyn Iterator<Item = Result<f32, hound::Error>> + Send> {
+    let spec = reader.spec();
+
+    match (spec.sample_format, spec.bits_per_sample) {
+        (hound::SampleFormat::Float, _) => Box::new(reader.into_samples::<f32>()),
+        // hound converts 8-bit files from unsigned.
+        (hound::SampleFormat::Int, 8) => Box::new(
+            reader
+                .into_samples::<i8>()
+                .map(|s| s.map(|s| f32::from(s) / 128.0)),
+        ),
+        (hound::SampleFormat::Int, 16) => Box::new(
+            reader
+                .into_samples::<i16>()
+                .map(|s| s.map(|s| f32::from(s) / 32_768.0)),
+        ),
+        (hound::SampleFormat::Int, bits) => {
+            let scale = f64::from(1u32 << (bits - 1));
+            Box::new(
+                reader
+                    .into_samples::<i32>()
+                    .map(move |s| s.map(|s| (f64::from(s) / scale) as f32)),
+            )
+        }
+    }
+}
+
+/// Map interleaved frames with `file_channels` channels in `src` to frames
+/// with `channels` channels in `dst`.
+fn map_channels(src: &[f32], file_channels: usize, dst: &mut [f32], channels: usize) {
+    for (src, dst) in src
+        .chunks_exact(file_channels)
+        .zip(dst.chunks_exact_mut(channels))
+    {
+        for (c, s) in dst.iter_mut().enumerate() {
+            *s = match file_channels {
+                1 => src[0],
+                _ => src.get(c).copied().unwrap_or(0.0),
+            };
+        }
+    }
+}
+
+/// Read the whole file into the output buffer, mapping its channels to the
+/// channels of the stream.
+fn feed_output(
+    mut samples: impl Iterator<Item = Result<f32, hound::Error>>,
+    file_channels: usize,
+    writer: &mut OutputWriter,
+    shared: &PlaybackShared,
+) -> Result<(), hound::Error> {
+    let channels = writer.channels();
+
+    let mut file_block = Vec::with_capacity(BLOCK_FRAMES * file_channels);
+    let mut block = vec![0.0f32; BLOCK_FRAMES * channels];
+
+    loop {
+        file_block.clear();
+        for s in samples.by_ref().take(BLOCK_FRAMES * file_channels) {
+            file_block.push(s?);
+        }
+
+        // A partial frame at the end of the file is dropped.
+        let frames = file_block.len() / file_channels;
+        if frames == 0 {
+            return Ok(());
+        }
+
+        map_channels(
+            &file_block[..frames * file_channels],
+            file_channels,
+            &mut block[..frames * channels],
+            channels,
+        );
+
+        let mut remaining = &block[..frames * channels];
+        while !remaining.is_empty() {
+            if shared.cancelled.load(Ordering::Acquire) {
+                return Ok(());
+            }
+
+            let written = writer.write(remaining);
+            remaining = &remaining[written..];
+            if written == 0 {
+                std::thread::sleep(PLAY_POLL_INTERVAL);
+            }
+        }
+
+        shared.frames.fetch_add(frames as u64, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn round_trip(format: SampleFormat, samples: &[f32]) -> (hound::WavSpec, Vec<f32>) {
+        let spec = spec_for_stream(format, 1, 48000);
+
+        let mut bytes = Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(&mut bytes, spec).unwrap();
+        write_samples(&mut writer, samples).unwrap();
+        writer.finalize().unwrap();
+
+        let mut reader = hound::WavReader::new(Cursor::new(bytes.into_inner())).unwrap();
+        let read = match spec.sample_format {
+            hound::SampleFormat::Float => reader.samples::<f32>().map(Result::unwrap).collect(),
+            hound::SampleFormat::Int => {
+                let scale = (1u32 << (spec.bits_per_sample - 1)) as f32;
+                reader
+                    .samples::<i32>()
+                    .map(|s| s.unwrap() as f32 / scale)
+                    .collect()
+            }
+        };
+
+        (reader.spec(), read)
+    }
+
+    #[test]
+    fn samples_are_converted_to_the_format_of_the_stream() {
+        let samples = [0.0, 0.25, -0.5, 0.75, -1.0];
+
+        for (format, bits) in [
+            (SampleFormat::SInt8, 16),
+            (SampleFormat::SInt16, 16),
+            (SampleFormat::SInt24, 24),
+            (SampleFormat::SInt32, 32),
+            (SampleFormat::Float32, 32),
+            (SampleFormat::Float64, 32),
+        ] {
+            let (spec, read) = round_trip(format, &samples);
+            assert_eq!(spec.bits_per_sample, bits, "{:?}", format);
+            assert_eq!(read, samples, "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn integer_samples_are_clipped() {
+        let (_, read) = round_trip(SampleFormat::SInt16, &[1.0, 2.0, -2.0]);
+        assert_eq!(read, [32_767.0 / 32_768.0, 32_767.0 / 32_768.0, -1.0]);
+
+        let (_, read) = round_trip(SampleFormat::SInt32, &[1.5]);
+        assert_eq!(read, [2_147_483_647.0 / 2_147_483_648.0]);
+    }
+
+    fn read_back(
+        spec: hound::WavSpec,
+        write: impl FnOnce(&mut hound::WavWriter<&mut Cursor<Vec<u8>>>),
+    ) -> Vec<f32> {
+        let mut bytes = Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(&mut bytes, spec).unwrap();
+        write(&mut writer);
+        writer.finalize().unwrap();
+
+        let reader = hound::WavReader::new(Cursor::new(bytes.into_inner())).unwrap();
+        samples_f32(reader).map(Result::unwrap).collect()
+    }
+
+    #[test]
+    fn files_are_read_as_f32() {
+        let spec = |bits_per_sample, sample_format| hound::WavSpec {
+            channels: 1,
+            sample_rate: 44100,
+            bits_per_sample,
+            sample_format,
+        };
+        let expected = [0.0, 0.25, -0.5, -1.0];
+
+        // 8-bit files are unsigned, which hound converts to `i8`.
+        let read = read_back(spec(8, hound::SampleFormat::Int), |w| {
+            for s in [0i8, 32, -64, -128] {
+                w.write_sample(s).unwrap();
+            }
+        });
+        assert_eq!(read, expected);
+
+        let read = read_back(spec(16, hound::SampleFormat::Int), |w| {
+            for s in [0i16, 8192, -16384, -32768] {
+                w.write_sample(s).unwrap();
+            }
+        });
+        assert_eq!(read, expected);
+
+        let read = read_back(spec(24, hound::SampleFormat::Int), |w| {
+            for s in [0i32, 2_097_152, -4_194_304, -8_388_608] {
+                w.write_sample(s).unwrap();
+            }
+        });
+        assert_eq!(read, expected);
+
+        let read = read_back(spec(32, hound::SampleFormat::Float), |w| {
+            for s in expected {
+                w.write_sample(s).unwrap();
+            }
+        });
+        assert_eq!(read, expected);
+    }
+
+    #[test]
+    fn channels_are_mapped_to_the_stream() {
+        // A mono file is copied to every channel.
+        let mut dst = [9.0; 6];
+        map_channels(&[0.5, -0.5], 1, &mut dst, 3);
+        assert_eq!(dst, [0.5, 0.5, 0.5, -0.5, -0.5, -0.5]);
+
+        // Extra output channels are silent.
+        let mut dst = [9.0; 6];
+        map_channels(&[0.1, 0.2, 0.3, 0.4], 2, &mut dst, 3);
+        assert_eq!(dst, [0.1, 0.2, 0.0, 0.3, 0.4, 0.0]);
+
+        // Extra channels of the file are discarded.
+        let mut dst = [9.0; 2];
+        map_channels(&[0.1, 0.2, 0.3, 0.4, 0.5, 0.6], 3, &mut dst, 1);
+        assert_eq!(dst, [0.1, 0.4]);
+    }
+
+    #[test]
+    fn sample_rate_mismatch_says_how_to_fix_it() {
+        let e = WavError::SampleRateMismatch {
+            file: 44100,
+            stream: 48000,
+        };
+        assert_eq!(
+            e.to_string(),
+            "cannot play a 44100 Hz WAV file on a 48000 Hz stream: files are not resampled, so open the stream at 44100 Hz"
+        );
+    }
+}